@@ -0,0 +1,354 @@
+//! # s_app_dir_core
+//!
+//! The directory layout rules of `s_app_dir`, computed from environment
+//! values and a home directory the caller supplies, without `std::env` or
+//! `std::fs`. `s_app_dir` builds on this with the process environment;
+//! embedded and unikernel users can implement `EnvSource` for their own
+//! environment, or use `StrEnv`.
+//!
+//! ```rust
+//! extern crate s_app_dir_core;
+//!
+//! use s_app_dir_core::{app_dir, Kind, Options, Platform, StrEnv};
+//!
+//! fn main() {
+//!     let env = StrEnv {
+//!         vars: &[("XDG_CACHE_HOME", "/var/cache/user")],
+//!         home: Some("/home/user"),
+//!         platform: Platform::Unix,
+//!     };
+//!     let options = Options::default();
+//!     let config = app_dir(Kind::Config, "foo", Platform::Unix, options, &env);
+//!     assert_eq!(Ok("/home/user/.config/foo".to_string()), config);
+//!     let cache = app_dir(Kind::Cache, "foo", Platform::Unix, options, &env);
+//!     assert_eq!(Ok("/var/cache/user/foo".to_string()), cache);
+//! }
+//! ```
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::String;
+use core::fmt::{self, Display, Formatter};
+
+/// A kind of directory, as `s_app_dir::XdgDir`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Kind {
+    Data,
+    Config,
+    Cache,
+    Bin,
+}
+
+impl Kind {
+    /// The variable that overrides the base directory, e.g.
+    /// `XDG_CONFIG_HOME`.
+    pub fn var(&self) -> &'static str {
+        match *self {
+            Kind::Data => "XDG_DATA_HOME",
+            Kind::Config => "XDG_CONFIG_HOME",
+            Kind::Cache => "XDG_CACHE_HOME",
+            Kind::Bin => "XDG_BIN_HOME",
+        }
+    }
+
+    /// The base directory relative to the home directory on Unix, e.g.
+    /// `.config`.
+    pub fn home_relative(&self) -> &'static str {
+        match *self {
+            Kind::Data => ".local/share",
+            Kind::Config => ".config",
+            Kind::Cache => ".cache",
+            Kind::Bin => ".local/bin",
+        }
+    }
+}
+
+/// Whose rules apply: the XDG layout under the home directory, or
+/// `%APPDATA%`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Platform {
+    Unix,
+    Windows,
+}
+
+impl Platform {
+    /// `Windows` on Windows, `Unix` everywhere else.
+    pub fn current() -> Platform {
+        if cfg!(windows) {
+            Platform::Windows
+        } else {
+            Platform::Unix
+        }
+    }
+}
+
+/// How strictly the rules treat the environment.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Options {
+    /// Reject relative and unusable values instead of ignoring them.
+    pub strict: bool,
+    /// Only consult the variables themselves, never the home directory or
+    /// `%APPDATA%`, and fail with `Error::NotSet` instead of guessing.
+    pub pure_env: bool,
+}
+
+/// Why a directory could not be resolved.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Error {
+    /// The home directory could not be determined.
+    NoHome,
+    /// A variable the fallback depends on, such as `APPDATA`, is not set.
+    NotSet(&'static str),
+    /// In strict mode, the variable is set to a relative or unusable path.
+    Invalid(&'static str),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Error::NoHome => write!(f, "cannot determine the home directory"),
+            Error::NotSet(key) => write!(f, "{} is not set", key),
+            Error::Invalid(key) => write!(f, "{} is not an absolute path", key),
+        }
+    }
+}
+
+/// Where the rules read environment values and the home directory from,
+/// and how they build paths.
+pub trait EnvSource {
+    /// A path, such as `String` or `std::path::PathBuf`.
+    type Path;
+
+    /// The variable `key`: `None` if it is unset, empty or only whitespace,
+    /// as the XDG spec requires, and `Some(None)` if it is set to something
+    /// that cannot be a path, e.g. invalid Unicode.
+    fn var(&self, key: &str) -> Option<Option<Self::Path>>;
+
+    /// The home directory, if known.
+    fn home(&self) -> Option<Self::Path>;
+
+    fn is_absolute(&self, path: &Self::Path) -> bool;
+
+    /// `base` with the `/`-separated `relative` appended.
+    fn join(&self, base: Self::Path, relative: &str) -> Self::Path;
+}
+
+/// The directory in `key`, if set. Unusable values are treated as unset,
+/// unless `options.strict` is on, in which case they are rejected along
+/// with relative paths.
+pub fn var_dir<E>(key: &'static str,
+                  options: Options,
+                  env: &E)
+                  -> Result<Option<E::Path>, Error>
+    where E: EnvSource + ?Sized
+{
+    named_var_dir(key, options, env).map_err(Error::Invalid)
+}
+
+/// Like `var_dir`, but for a variable named at run time, such as a step of
+/// a custom resolution chain, so it fails with just the `key`.
+pub fn named_var_dir<'k, E>(key: &'k str,
+                            options: Options,
+                            env: &E)
+                            -> Result<Option<E::Path>, &'k str>
+    where E: EnvSource + ?Sized
+{
+    match env.var(key) {
+        None => Ok(None),
+        Some(Some(ref dir)) if options.strict && !env.is_absolute(dir) => Err(key),
+        Some(None) if options.strict => Err(key),
+        Some(dir) => Ok(dir),
+    }
+}
+
+/// The base directory of `kind`, shared by all apps: its variable, or else
+/// the home-relative directory on Unix and `%APPDATA%` on Windows.
+pub fn base_dir<E>(kind: Kind,
+                   platform: Platform,
+                   options: Options,
+                   env: &E)
+                   -> Result<E::Path, Error>
+    where E: EnvSource + ?Sized
+{
+    match var_dir(kind.var(), options, env)? {
+        Some(dir) => return Ok(dir),
+        None if options.pure_env => return Err(Error::NotSet(kind.var())),
+        None => {}
+    }
+    match platform {
+        Platform::Unix => {
+            env.home().map(|home| env.join(home, kind.home_relative())).ok_or(Error::NoHome)
+        }
+        Platform::Windows => appdata(env),
+    }
+}
+
+/// The directory for the app's executables. Windows has no shared,
+/// on-`PATH` directory for user executables, so without `XDG_BIN_HOME`
+/// each app gets its own `bin` directory there; elsewhere it is the shared
+/// base directory.
+pub fn bin_dir<E>(app_name: &str,
+                  platform: Platform,
+                  options: Options,
+                  env: &E)
+                  -> Result<E::Path, Error>
+    where E: EnvSource + ?Sized
+{
+    match platform {
+        Platform::Unix => base_dir(Kind::Bin, platform, options, env),
+        Platform::Windows => {
+            match var_dir(Kind::Bin.var(), options, env)? {
+                Some(dir) => Ok(dir),
+                None if options.pure_env => Err(Error::NotSet(Kind::Bin.var())),
+                None => appdata(env).map(|dir| env.join(env.join(dir, app_name), "bin")),
+            }
+        }
+    }
+}
+
+/// The directory of `kind` for `app_name`.
+pub fn app_dir<E>(kind: Kind,
+                  app_name: &str,
+                  platform: Platform,
+                  options: Options,
+                  env: &E)
+                  -> Result<E::Path, Error>
+    where E: EnvSource + ?Sized
+{
+    match kind {
+        Kind::Bin => bin_dir(app_name, platform, options, env),
+        _ => base_dir(kind, platform, options, env).map(|dir| env.join(dir, app_name)),
+    }
+}
+
+fn appdata<E>(env: &E) -> Result<E::Path, Error>
+    where E: EnvSource + ?Sized
+{
+    env.var("APPDATA").and_then(|dir| dir).ok_or(Error::NotSet("APPDATA"))
+}
+
+/// An `EnvSource` over borrowed strings, building `String` paths with the
+/// separator of `platform`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct StrEnv<'a> {
+    pub vars: &'a [(&'a str, &'a str)],
+    pub home: Option<&'a str>,
+    pub platform: Platform,
+}
+
+impl<'a> EnvSource for StrEnv<'a> {
+    type Path = String;
+
+    fn var(&self, key: &str) -> Option<Option<String>> {
+        self.vars
+            .iter()
+            .rev()
+            .find(|&&(k, _)| k == key)
+            .map(|&(_, value)| value)
+            .filter(|value| !value.trim().is_empty())
+            .map(|value| Some(value.into()))
+    }
+
+    fn home(&self) -> Option<String> {
+        self.home.map(String::from)
+    }
+
+    fn is_absolute(&self, path: &String) -> bool {
+        match self.platform {
+            Platform::Unix => path.starts_with('/'),
+            Platform::Windows => {
+                let bytes = path.as_bytes();
+                path.starts_with(r"\\") ||
+                (bytes.len() > 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' &&
+                 (bytes[2] == b'\\' || bytes[2] == b'/'))
+            }
+        }
+    }
+
+    fn join(&self, mut base: String, relative: &str) -> String {
+        let separator = match self.platform {
+            Platform::Unix => '/',
+            Platform::Windows => '\\',
+        };
+        if !base.ends_with('/') && !base.ends_with(separator) {
+            base.push(separator);
+        }
+        match self.platform {
+            Platform::Unix => base.push_str(relative),
+            Platform::Windows => {
+                base.extend(relative.chars().map(|c| if c == '/' { '\\' } else { c }))
+            }
+        }
+        base
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use {app_dir, named_var_dir, Error, Kind, Options, Platform, StrEnv};
+
+    const STRICT: Options = Options { strict: true, pure_env: false };
+
+    /// Fall back to the home directory on Unix, unless the variable is set
+    /// and not blank.
+    #[test]
+    fn unix() {
+        let env = StrEnv {
+            vars: &[("XDG_DATA_HOME", "/data"),
+                    ("XDG_CONFIG_HOME", " "),
+                    ("XDG_CACHE_HOME", "relative")],
+            home: Some("/home/a/"),
+            platform: Platform::Unix,
+        };
+        let dir = |kind| app_dir(kind, "app", Platform::Unix, Options::default(), &env);
+        assert_eq!(Ok(String::from("/data/app")), dir(Kind::Data));
+        assert_eq!(Ok(String::from("/home/a/.config/app")), dir(Kind::Config));
+        assert_eq!(Ok(String::from("relative/app")), dir(Kind::Cache));
+        assert_eq!(Ok(String::from("/home/a/.local/bin")), dir(Kind::Bin));
+        assert_eq!(Err(Error::Invalid("XDG_CACHE_HOME")),
+                   app_dir(Kind::Cache, "app", Platform::Unix, STRICT, &env));
+        let key = String::from("XDG_CACHE_HOME");
+        assert_eq!(Err(&key[..]), named_var_dir(&key, STRICT, &env));
+
+        let homeless = StrEnv { home: None, ..env };
+        assert_eq!(Err(Error::NoHome),
+                   app_dir(Kind::Config, "app", Platform::Unix, Options::default(), &homeless));
+    }
+
+    /// Fall back to `%APPDATA%` on Windows, with a `bin` directory per app.
+    #[test]
+    fn windows() {
+        let env = StrEnv {
+            vars: &[("APPDATA", r"C:\Users\a\AppData\Roaming")],
+            home: Some(r"C:\Users\a"),
+            platform: Platform::Windows,
+        };
+        let dir = |kind| app_dir(kind, "app", Platform::Windows, STRICT, &env);
+        assert_eq!(Ok(String::from(r"C:\Users\a\AppData\Roaming\app")), dir(Kind::Config));
+        assert_eq!(Ok(String::from(r"C:\Users\a\AppData\Roaming\app\bin")), dir(Kind::Bin));
+
+        let bare = StrEnv { vars: &[], ..env };
+        assert_eq!(Err(Error::NotSet("APPDATA")),
+                   app_dir(Kind::Data, "app", Platform::Windows, Options::default(), &bare));
+    }
+
+    /// Use only the variables in pure-env mode.
+    #[test]
+    fn pure_env() {
+        let env = StrEnv {
+            vars: &[("XDG_DATA_HOME", "/data"), ("APPDATA", r"C:\AppData")],
+            home: Some("/home/a"),
+            platform: Platform::Unix,
+        };
+        let pure = Options { strict: false, pure_env: true };
+        assert_eq!(Ok(String::from("/data/app")),
+                   app_dir(Kind::Data, "app", Platform::Unix, pure, &env));
+        assert_eq!(Err(Error::NotSet("XDG_CONFIG_HOME")),
+                   app_dir(Kind::Config, "app", Platform::Unix, pure, &env));
+        assert_eq!(Err(Error::NotSet("XDG_BIN_HOME")),
+                   app_dir(Kind::Bin, "app", Platform::Windows, pure, &env));
+    }
+}
@@ -0,0 +1,37 @@
+/* The C interface of s_app_dir, built with the `ffi` feature.
+ *
+ * Each function writes the directory of the NUL-terminated UTF-8 app_name
+ * to buf as a NUL-terminated UTF-8 string if it fits in len bytes, and
+ * returns its length without the NUL, or -1 on error. Pass a null buf to
+ * size the buffer. The _w variants, on Windows only, use UTF-16.
+ */
+
+#ifndef S_APP_DIR_H
+#define S_APP_DIR_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+intptr_t s_app_dir_data_dir(const char *app_name, char *buf, size_t len);
+intptr_t s_app_dir_config_dir(const char *app_name, char *buf, size_t len);
+intptr_t s_app_dir_cache_dir(const char *app_name, char *buf, size_t len);
+intptr_t s_app_dir_bin_dir(const char *app_name, char *buf, size_t len);
+intptr_t s_app_dir_temp_dir(const char *app_name, char *buf, size_t len);
+
+#ifdef _WIN32
+intptr_t s_app_dir_data_dir_w(const uint16_t *app_name, uint16_t *buf, size_t len);
+intptr_t s_app_dir_config_dir_w(const uint16_t *app_name, uint16_t *buf, size_t len);
+intptr_t s_app_dir_cache_dir_w(const uint16_t *app_name, uint16_t *buf, size_t len);
+intptr_t s_app_dir_bin_dir_w(const uint16_t *app_name, uint16_t *buf, size_t len);
+intptr_t s_app_dir_temp_dir_w(const uint16_t *app_name, uint16_t *buf, size_t len);
+#endif
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,113 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::mem;
+use std::os::windows::ffi::OsStrExt;
+use std::path::Path;
+use std::ptr;
+use std::slice;
+
+use windows_sys::Win32::Foundation::{CloseHandle, LocalFree, HANDLE};
+use windows_sys::Win32::Security::Authorization::{ConvertSidToStringSidW,
+                                                  ConvertStringSecurityDescriptorToSecurityDescriptorW,
+                                                  SDDL_REVISION_1};
+use windows_sys::Win32::Security::{GetTokenInformation, SetFileSecurityW, TokenUser,
+                                   DACL_SECURITY_INFORMATION,
+                                   PROTECTED_DACL_SECURITY_INFORMATION, PSECURITY_DESCRIPTOR,
+                                   SECURITY_ATTRIBUTES, TOKEN_QUERY, TOKEN_USER};
+use windows_sys::Win32::Storage::FileSystem::CreateDirectoryW;
+use windows_sys::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+
+/// A security descriptor allocated by Windows, freed on drop.
+struct SecurityDescriptor(PSECURITY_DESCRIPTOR);
+
+impl Drop for SecurityDescriptor {
+    fn drop(&mut self) {
+        unsafe {
+            LocalFree(self.0);
+        }
+    }
+}
+
+fn wide(s: &OsStr) -> Vec<u16> {
+    s.encode_wide().chain(Some(0)).collect()
+}
+
+/// The SID of the user running this process, in string form.
+fn current_user_sid() -> io::Result<String> {
+    unsafe {
+        let mut token: HANDLE = ptr::null_mut();
+        if OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut len = 0u32;
+        GetTokenInformation(token, TokenUser, ptr::null_mut(), 0, &mut len);
+        // u64 elements keep the buffer aligned for the pointers inside TOKEN_USER.
+        let mut buf = vec![0u64; (len as usize).div_ceil(8)];
+        let ok = GetTokenInformation(token, TokenUser, buf.as_mut_ptr() as *mut _, len, &mut len);
+        let err = io::Error::last_os_error();
+        CloseHandle(token);
+        if ok == 0 {
+            return Err(err);
+        }
+
+        let user = &*(buf.as_ptr() as *const TOKEN_USER);
+        let mut sid = ptr::null_mut();
+        if ConvertSidToStringSidW(user.User.Sid, &mut sid) == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let len = (0..).take_while(|&i| *sid.offset(i) != 0).count();
+        let string = String::from_utf16_lossy(slice::from_raw_parts(sid, len));
+        LocalFree(sid as *mut _);
+        Ok(string)
+    }
+}
+
+/// A protected DACL granting full control to the current user only, the
+/// Windows counterpart of mode `0700`.
+fn private_descriptor() -> io::Result<SecurityDescriptor> {
+    let sddl = wide(OsStr::new(&format!("D:P(A;OICI;FA;;;{})", current_user_sid()?)));
+    let mut descriptor = ptr::null_mut();
+    let ok = unsafe {
+        ConvertStringSecurityDescriptorToSecurityDescriptorW(sddl.as_ptr(),
+                                                             SDDL_REVISION_1,
+                                                             &mut descriptor,
+                                                             ptr::null_mut())
+    };
+    if ok == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(SecurityDescriptor(descriptor))
+}
+
+/// Create `dir` accessible only to the current user, or restrict it if it
+/// already exists. Parents are created with inherited permissions.
+pub fn create_private_dir(dir: &Path) -> io::Result<()> {
+    if let Some(parent) = dir.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let descriptor = private_descriptor()?;
+    let path = wide(dir.as_os_str());
+    let attributes = SECURITY_ATTRIBUTES {
+        nLength: mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+        lpSecurityDescriptor: descriptor.0,
+        bInheritHandle: 0,
+    };
+    if unsafe { CreateDirectoryW(path.as_ptr(), &attributes) } != 0 {
+        return Ok(());
+    }
+
+    let err = io::Error::last_os_error();
+    if err.kind() != io::ErrorKind::AlreadyExists {
+        return Err(err);
+    }
+    if !fs::symlink_metadata(dir)?.is_dir() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists,
+                                  format!("not a directory: {}", dir.display())));
+    }
+    let info = DACL_SECURITY_INFORMATION | PROTECTED_DACL_SECURITY_INFORMATION;
+    if unsafe { SetFileSecurityW(path.as_ptr(), info, descriptor.0) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
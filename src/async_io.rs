@@ -0,0 +1,250 @@
+use std::fs;
+use std::future::Future;
+use std::io;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+#[cfg(feature="tokio")]
+use tokio::runtime::Handle;
+#[cfg(feature="tokio")]
+use tokio::task::{self, JoinHandle};
+
+use ensure::create_dir_with_mode;
+use harden;
+use {write_atomic, AppDir, XdgDir};
+
+type Job<T> = Box<dyn FnOnce() -> io::Result<T> + Send>;
+
+/// The result of blocking IO run off the async executor.
+///
+/// Nothing runs until the future is first polled. If that happens within a
+/// Tokio runtime (with the `tokio` feature), the IO runs on Tokio's blocking
+/// thread pool, the same way `tokio::fs` runs it; otherwise it runs on a new
+/// thread, so the future works with any executor.
+pub struct Blocking<T> {
+    state: State<T>,
+}
+
+enum State<T> {
+    Idle(Job<T>),
+    #[cfg(feature="tokio")]
+    Tokio(JoinHandle<io::Result<T>>),
+    Thread(Arc<Mutex<Slot<T>>>),
+    Done,
+}
+
+/// Where a thread started by `Blocking` leaves its result.
+struct Slot<T> {
+    result: Option<io::Result<T>>,
+    waker: Option<Waker>,
+}
+
+impl<T> Future for Blocking<T>
+    where T: Send + 'static
+{
+    type Output = io::Result<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<T>> {
+        if let State::Idle(_) = self.state {
+            if let State::Idle(job) = mem::replace(&mut self.state, State::Done) {
+                self.state = start(job);
+            }
+        }
+        let result = match self.state {
+            State::Idle(_) => unreachable!(),
+            #[cfg(feature="tokio")]
+            State::Tokio(ref mut handle) => {
+                match Pin::new(handle).poll(cx) {
+                    Poll::Ready(Ok(result)) => result,
+                    Poll::Ready(Err(err)) => Err(io::Error::other(err)),
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+            State::Thread(ref slot) => {
+                let mut slot = slot.lock().unwrap_or_else(|e| e.into_inner());
+                match slot.result.take() {
+                    Some(result) => result,
+                    None => {
+                        slot.waker = Some(cx.waker().clone());
+                        return Poll::Pending;
+                    }
+                }
+            }
+            State::Done => panic!("`Blocking` polled after completion"),
+        };
+        self.state = State::Done;
+        Poll::Ready(result)
+    }
+}
+
+#[cfg(feature="tokio")]
+fn start<T>(job: Job<T>) -> State<T>
+    where T: Send + 'static
+{
+    if Handle::try_current().is_ok() {
+        State::Tokio(task::spawn_blocking(job))
+    } else {
+        start_thread(job)
+    }
+}
+
+#[cfg(not(feature="tokio"))]
+fn start<T>(job: Job<T>) -> State<T>
+    where T: Send + 'static
+{
+    start_thread(job)
+}
+
+fn start_thread<T>(job: Job<T>) -> State<T>
+    where T: Send + 'static
+{
+    let slot = Arc::new(Mutex::new(Slot {
+        result: None,
+        waker: None,
+    }));
+    let shared = slot.clone();
+    let spawned = thread::Builder::new().name("s_app_dir-io".to_string()).spawn(move || {
+        let result = panic::catch_unwind(AssertUnwindSafe(job))
+            .unwrap_or_else(|_| Err(io::Error::other("blocking IO panicked")));
+        let mut slot = shared.lock().unwrap_or_else(|e| e.into_inner());
+        slot.result = Some(result);
+        if let Some(waker) = slot.waker.take() {
+            waker.wake();
+        }
+    });
+    if let Err(err) = spawned {
+        slot.lock().unwrap_or_else(|e| e.into_inner()).result = Some(Err(err));
+    }
+    State::Thread(slot)
+}
+
+fn spawn<T, F>(f: F) -> Blocking<T>
+    where F: FnOnce() -> io::Result<T> + Send + 'static
+{
+    Blocking { state: State::Idle(Box::new(f)) }
+}
+
+/// Async variants of the IO helpers. The directory is resolved on the
+/// calling thread, so `AppDir::with_overrides` applies; only creating it
+/// runs off the executor.
+impl AppDir {
+    pub fn ensure_xdg_dir_async(&self, xdg: XdgDir) -> Blocking<PathBuf> {
+        let dir = self.try_xdg_dir(xdg);
+        spawn(move || {
+            let dir = dir?;
+            fs::create_dir_all(&dir)?;
+            Ok(dir)
+        })
+    }
+
+    pub fn ensure_xdg_dir_with_mode_async(&self, xdg: XdgDir, mode: u32) -> Blocking<PathBuf> {
+        if xdg == XdgDir::Bin {
+            return self.ensure_xdg_dir_async(xdg);
+        }
+        let dir = self.try_xdg_dir(xdg);
+        spawn(move || {
+            let dir = dir?;
+            create_dir_with_mode(&dir, mode)?;
+            Ok(dir)
+        })
+    }
+
+    pub fn ensure_temp_dir_async(&self) -> Blocking<PathBuf> {
+        let dir = self.temp_dir();
+        let checked = harden::check(self, &dir);
+        spawn(move || {
+            checked?;
+            fs::create_dir_all(&dir)?;
+            Ok(dir)
+        })
+    }
+}
+
+/// Like `write_atomic`, but off the async executor.
+pub fn write_atomic_async<P, C>(path: P, contents: C) -> Blocking<()>
+    where P: Into<PathBuf>,
+          C: Into<Vec<u8>>
+{
+    let (path, contents) = (path.into(), contents.into());
+    spawn(move || Ok(write_atomic(path, &contents)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::future::Future;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake};
+    use std::thread::{self, Thread};
+
+    struct Unpark(Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    /// A minimal executor, standing in for one that isn't Tokio.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Arc::new(Unpark(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    /// Create the directory and write a file into it without a Tokio runtime.
+    #[test]
+    fn ensure_xdg_dir_async() {
+        let guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+
+        let dir = block_on(app_dir.ensure_xdg_dir_async(::XdgDir::Cache)).unwrap();
+        assert!(dir.starts_with(guard.home()));
+        assert!(dir.is_dir());
+        block_on(super::write_atomic_async(dir.join("a"), "b")).unwrap();
+        assert_eq!("b", fs::read_to_string(dir.join("a")).unwrap());
+    }
+
+    /// Create the directory overridden where the future was made, though it
+    /// is created on another thread.
+    #[test]
+    fn ensure_xdg_dir_async_overrides() {
+        let guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        let base = guard.home().join("overridden");
+
+        let future = ::AppDir::with_overrides(vec![(::XdgDir::Config, base.clone())], || {
+            app_dir.ensure_xdg_dir_with_mode_async(::XdgDir::Config, 0o700)
+        });
+        assert_eq!(base.join("s_app_dir"), block_on(future).unwrap());
+        assert!(base.join("s_app_dir").is_dir());
+        assert!(!app_dir.xdg_dir(::XdgDir::Config).unwrap().exists());
+    }
+
+    /// Run on Tokio's blocking pool from within a runtime.
+    #[cfg(feature="tokio")]
+    #[test]
+    fn ensure_xdg_dir_async_tokio() {
+        use tokio::runtime::Builder;
+
+        let guard = ::TempEnvGuard::new().unwrap();
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+
+        let dir = runtime.block_on(app_dir.ensure_temp_dir_async()).unwrap();
+        assert!(dir.is_dir());
+        runtime.block_on(super::write_atomic_async(guard.home().join("a"), "b")).unwrap();
+        assert_eq!("b", fs::read_to_string(guard.home().join("a")).unwrap());
+    }
+}
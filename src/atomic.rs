@@ -0,0 +1,137 @@
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Replace the contents of `path` by writing a temporary file next to it
+/// and renaming it over, so readers never see a partial file. The parent
+/// directory is created if missing.
+pub fn write_atomic<P>(path: P, contents: &[u8]) -> Result<(), WriteError>
+    where P: AsRef<Path>
+{
+    let path = path.as_ref();
+    let dir = match path.parent() {
+        Some(dir) => dir,
+        None => {
+            let err = io::Error::new(io::ErrorKind::InvalidInput, "no parent directory");
+            return Err(WriteError::classify(path, err));
+        }
+    };
+    fs::create_dir_all(dir).map_err(|err| WriteError::classify(dir, err))?;
+    let mut tmp_name = path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    tmp_name.push(".tmp");
+    let tmp = dir.join(tmp_name);
+    if let Err(err) = fs::write(&tmp, contents) {
+        let _ = fs::remove_file(&tmp);
+        return Err(WriteError::classify(path, err));
+    }
+    fs::rename(&tmp, path).map_err(|err| WriteError::classify(path, err))
+}
+
+/// Errors from the write helpers, classified so apps can tell users what to
+/// do about them. Each names the file or directory that could not be
+/// written.
+#[derive(Debug)]
+pub enum WriteError {
+    /// The disk or the user's quota is full.
+    NoSpace(PathBuf, io::Error),
+    /// The process may not write there.
+    PermissionDenied(PathBuf, io::Error),
+    /// The filesystem is mounted read-only.
+    ReadOnlyFilesystem(PathBuf, io::Error),
+    /// Any other failure.
+    Io(PathBuf, io::Error),
+}
+
+impl WriteError {
+    /// Classify `err` from writing to `path`.
+    pub fn classify<P>(path: P, err: io::Error) -> WriteError
+        where P: AsRef<Path>
+    {
+        let path = path.as_ref().to_path_buf();
+        match err.kind() {
+            io::ErrorKind::StorageFull | io::ErrorKind::QuotaExceeded => {
+                WriteError::NoSpace(path, err)
+            }
+            io::ErrorKind::PermissionDenied => WriteError::PermissionDenied(path, err),
+            io::ErrorKind::ReadOnlyFilesystem => WriteError::ReadOnlyFilesystem(path, err),
+            _ => WriteError::Io(path, err),
+        }
+    }
+
+    /// The file or directory that could not be written.
+    pub fn path(&self) -> &Path {
+        match *self {
+            WriteError::NoSpace(ref path, _) |
+            WriteError::PermissionDenied(ref path, _) |
+            WriteError::ReadOnlyFilesystem(ref path, _) |
+            WriteError::Io(ref path, _) => path,
+        }
+    }
+
+    /// The underlying error.
+    pub fn io_error(&self) -> &io::Error {
+        match *self {
+            WriteError::NoSpace(_, ref err) |
+            WriteError::PermissionDenied(_, ref err) |
+            WriteError::ReadOnlyFilesystem(_, ref err) |
+            WriteError::Io(_, ref err) => err,
+        }
+    }
+}
+
+impl Display for WriteError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let path = self.path().display();
+        match *self {
+            WriteError::NoSpace(..) => write!(f, "{}: no space left on the disk or quota", path),
+            WriteError::PermissionDenied(..) => write!(f, "{}: permission denied", path),
+            WriteError::ReadOnlyFilesystem(..) => write!(f, "{}: read-only filesystem", path),
+            WriteError::Io(_, ref err) => write!(f, "{}: {}", path, err),
+        }
+    }
+}
+
+impl error::Error for WriteError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(self.io_error())
+    }
+}
+
+/// Keeps the kind of the underlying error, with the `WriteError` inside.
+impl From<WriteError> for io::Error {
+    fn from(err: WriteError) -> io::Error {
+        io::Error::new(err.io_error().kind(), err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io;
+
+    use WriteError;
+
+    /// Classify errors by kind and keep the kind through `io::Error`.
+    #[test]
+    fn classify() {
+        let full = io::Error::from(io::ErrorKind::StorageFull);
+        assert!(matches!(WriteError::classify("/a", full), WriteError::NoSpace(..)));
+        let denied = WriteError::classify("/a", io::ErrorKind::PermissionDenied.into());
+        assert_eq!("/a: permission denied", denied.to_string());
+        assert_eq!(io::ErrorKind::PermissionDenied, io::Error::from(denied).kind());
+    }
+
+    /// Name the directory that could not be created.
+    #[test]
+    fn write_atomic() {
+        let guard = ::TempEnvGuard::new().unwrap();
+        let file = guard.home().join("file");
+        ::write_atomic(&file, b"a").unwrap();
+        assert_eq!("a", fs::read_to_string(&file).unwrap());
+
+        let err = ::write_atomic(file.join("a"), b"b").unwrap_err();
+        assert_eq!(file, err.path());
+    }
+}
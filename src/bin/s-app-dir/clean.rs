@@ -0,0 +1,97 @@
+use std::io;
+use std::time::Duration;
+
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use s_app_dir::CleanReport;
+
+use {app_arg, app_dir};
+
+pub fn command() -> Command {
+    Command::new("clean")
+        .about("Remove the files in the app's cache and temp directories")
+        .arg(app_arg())
+        .arg(Arg::new("cache")
+            .long("cache")
+            .action(ArgAction::SetTrue)
+            .help("Clean the cache directory"))
+        .arg(Arg::new("temp")
+            .long("temp")
+            .action(ArgAction::SetTrue)
+            .help("Clean the temp directory"))
+        .arg(Arg::new("older-than")
+            .long("older-than")
+            .value_parser(parse_age)
+            .help("Only remove files last modified longer ago than this, e.g. 30d, 12h or 90s"))
+        .arg(Arg::new("dry-run")
+            .long("dry-run")
+            .action(ArgAction::SetTrue)
+            .help("Print what would be removed without removing it"))
+}
+
+pub fn run(matches: &ArgMatches) -> io::Result<()> {
+    let app_dir = app_dir(matches);
+    let older_than = matches.get_one::<Duration>("older-than").cloned();
+    let dry_run = matches.get_flag("dry-run");
+    let (mut cache, mut temp) = (matches.get_flag("cache"), matches.get_flag("temp"));
+    if !cache && !temp {
+        cache = true;
+        temp = true;
+    }
+    let mut total = CleanReport::default();
+    if cache {
+        total = merge(total, app_dir.clean_cache(older_than, dry_run)?);
+    }
+    if temp {
+        total = merge(total, app_dir.clean_temp(older_than, dry_run)?);
+    }
+    for path in &total.removed {
+        println!("{}", path.display());
+    }
+    eprintln!("{} {} files, {} bytes",
+              if dry_run { "would remove" } else { "removed" },
+              total.removed.len(),
+              total.bytes);
+    Ok(())
+}
+
+fn merge(mut total: CleanReport, report: CleanReport) -> CleanReport {
+    total.removed.extend(report.removed);
+    total.bytes += report.bytes;
+    total
+}
+
+/// Parse an age such as `30d`: a number followed by `s`, `m`, `h`, `d` or
+/// `w`.
+fn parse_age(age: &str) -> Result<Duration, String> {
+    let split = age.find(|c: char| !c.is_ascii_digit()).unwrap_or(age.len());
+    let (number, unit) = age.split_at(split);
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(format!("not an age like 30d: {}", age)),
+    };
+    number.parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(seconds))
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("not an age like 30d: {}", age))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    /// Parse ages in each unit and reject others.
+    #[test]
+    fn parse_age() {
+        assert_eq!(Ok(Duration::from_secs(30 * 86400)), super::parse_age("30d"));
+        assert_eq!(Ok(Duration::from_secs(2 * 3600)), super::parse_age("2h"));
+        assert_eq!(Ok(Duration::from_secs(90)), super::parse_age("90s"));
+        assert!(super::parse_age("30").is_err());
+        assert!(super::parse_age("d").is_err());
+        assert!(super::parse_age("1y").is_err());
+    }
+}
@@ -0,0 +1,65 @@
+use std::io;
+
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+use s_app_dir::XdgDir;
+
+use {app_arg, app_dir, custom_arg};
+
+pub fn command() -> Command {
+    Command::new("create")
+        .about("Create the app's directories if missing and print their paths")
+        .arg(app_arg())
+        .arg(custom_arg())
+        .arg(Arg::new("kinds")
+            .num_args(0..)
+            .value_parser(value_parser!(XdgDir))
+            .help("The directories to create [default: data config cache]"))
+        .arg(Arg::new("mode")
+            .long("mode")
+            .value_parser(parse_mode)
+            .default_value("700")
+            .help("The octal permissions of each app directory; the shared bin directory keeps \
+                   the default ones"))
+        .arg(Arg::new("cachedir-tag")
+            .long("cachedir-tag")
+            .action(ArgAction::SetTrue)
+            .help("Mark the cache directory with a CACHEDIR.TAG"))
+}
+
+pub fn run(matches: &ArgMatches) -> io::Result<()> {
+    let app_dir = app_dir(matches);
+    let mode = *matches.get_one::<u32>("mode").expect("mode has a default");
+    let kinds = match matches.get_many::<XdgDir>("kinds") {
+        Some(kinds) => kinds.cloned().collect(),
+        None => vec![XdgDir::Data, XdgDir::Config, XdgDir::Cache],
+    };
+    for xdg in kinds {
+        println!("{}", app_dir.ensure_xdg_dir_with_mode(xdg, mode)?.display());
+    }
+    for (name, _) in app_dir.custom_dirs() {
+        println!("{}", app_dir.ensure_custom_with_mode(name, mode)?.display());
+    }
+    if matches.get_flag("cachedir-tag") {
+        app_dir.tag_cache_dir()?;
+    }
+    Ok(())
+}
+
+fn parse_mode(mode: &str) -> Result<u32, String> {
+    match u32::from_str_radix(mode, 8) {
+        Ok(mode) if mode <= 0o7777 => Ok(mode),
+        _ => Err(format!("not an octal mode: {}", mode)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    /// Parse octal modes and reject others.
+    #[test]
+    fn parse_mode() {
+        assert_eq!(Ok(0o750), super::parse_mode("750"));
+        assert_eq!(Ok(0o700), super::parse_mode("0700"));
+        assert!(super::parse_mode("800").is_err());
+        assert!(super::parse_mode("17777").is_err());
+    }
+}
@@ -0,0 +1,79 @@
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use clap::builder::PossibleValuesParser;
+use clap::{Arg, ArgMatches, Command};
+use s_app_dir::{variable_name, AppDir, XdgDir};
+
+use {app_arg, app_dir, custom_arg};
+
+pub fn command() -> Command {
+    Command::new("export")
+        .about("Print shell commands setting <APP>_<KIND>_DIR for every resolved directory")
+        .arg(app_arg())
+        .arg(custom_arg())
+        .arg(Arg::new("shell")
+            .long("shell")
+            .value_parser(PossibleValuesParser::new(["bash", "fish", "powershell"]))
+            .default_value("bash")
+            .help("The shell to print commands for; bash also suits sh and zsh"))
+}
+
+pub fn run(matches: &ArgMatches) -> io::Result<()> {
+    let app_dir = app_dir(matches);
+    let shell = matches.get_one::<String>("shell").expect("shell has a default");
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for (name, dir) in variables(&app_dir) {
+        let value = dir.to_str().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData,
+                           format!("not valid Unicode: {}", dir.display()))
+        })?;
+        writeln!(out, "{}", export_line(shell, &name, value))?;
+    }
+    Ok(())
+}
+
+/// Each resolved directory with the variable it is exported as, e.g.
+/// `FOO_BAR_CONFIG_DIR` for the config directory of `foo-bar`.
+fn variables(app_dir: &AppDir) -> Vec<(String, PathBuf)> {
+    let prefix = variable_name(app_dir.app_name());
+    let mut variables = XdgDir::all()
+        .iter()
+        .filter_map(|&xdg| {
+            app_dir.xdg_dir(xdg).map(|dir| {
+                (format!("{}_{}_DIR", prefix, xdg.as_str().to_ascii_uppercase()), dir)
+            })
+        })
+        .collect::<Vec<_>>();
+    variables.push((format!("{}_TEMP_DIR", prefix), app_dir.temp_dir()));
+    for (name, dir) in app_dir.custom_dirs() {
+        if let Some(dir) = dir {
+            variables.push((format!("{}_{}_DIR", prefix, variable_name(name)), dir));
+        }
+    }
+    variables
+}
+
+fn export_line(shell: &str, name: &str, value: &str) -> String {
+    match shell {
+        "fish" => {
+            format!("set -gx {} '{}'", name, value.replace('\\', "\\\\").replace('\'', "\\'"))
+        }
+        "powershell" => format!("$env:{} = '{}'", name, value.replace('\'', "''")),
+        _ => format!("export {}='{}'", name, value.replace('\'', "'\\''")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    /// Quote values for each shell.
+    #[test]
+    fn export_line() {
+        use super::export_line;
+
+        assert_eq!("export A_DIR='/it'\\''s'", export_line("bash", "A_DIR", "/it's"));
+        assert_eq!("set -gx A_DIR '/it\\'s\\\\'", export_line("fish", "A_DIR", "/it's\\"));
+        assert_eq!("$env:A_DIR = '/it''s'", export_line("powershell", "A_DIR", "/it's"));
+    }
+}
@@ -0,0 +1,91 @@
+//! `s-app-dir`: the directory resolution of `s_app_dir`, for shell scripts,
+//! Makefiles and packaging.
+
+extern crate clap;
+extern crate s_app_dir;
+
+mod clean;
+mod create;
+mod export;
+mod migrate;
+
+use std::process;
+
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use s_app_dir::{AppDir, XdgDir};
+
+fn command() -> Command {
+    Command::new("s-app-dir")
+        .about("Resolve the standard directories of an app")
+        .subcommand_required(true)
+        .subcommand(clean::command())
+        .subcommand(create::command())
+        .subcommand(export::command())
+        .subcommand(migrate::command())
+}
+
+/// The `AppDir` named by the `app` argument of a subcommand, with the
+/// custom directories of its `custom` argument, if it takes one.
+fn app_dir(matches: &ArgMatches) -> AppDir {
+    let mut builder = AppDir::builder(matches.get_one::<String>("app").expect("app is required"));
+    let customs = matches.try_get_many::<(String, XdgDir, String)>("custom").ok().flatten();
+    for (name, base, subpath) in customs.into_iter().flatten() {
+        builder = builder.custom_dir(name, *base, subpath);
+    }
+    builder.build()
+}
+
+/// The positional app name argument every subcommand takes.
+fn app_arg() -> Arg {
+    Arg::new("app").required(true).help("The app name, as passed to AppDir::new")
+}
+
+/// The `--custom` option of subcommands that handle every directory.
+fn custom_arg() -> Arg {
+    Arg::new("custom")
+        .long("custom")
+        .action(ArgAction::Append)
+        .value_parser(parse_custom)
+        .value_name("NAME=KIND[:SUBPATH]")
+        .help("Also handle the custom directory NAME, as SUBPATH (default: NAME) of the KIND \
+               directory, e.g. 'screenshots=data:media/screenshots'")
+}
+
+fn parse_custom(custom: &str) -> Result<(String, XdgDir, String), String> {
+    let (name, dir) = custom.split_once('=')
+        .ok_or_else(|| format!("expected NAME=KIND[:SUBPATH]: {}", custom))?;
+    let (kind, subpath) = dir.split_once(':').unwrap_or((dir, name));
+    let xdg = kind.parse::<XdgDir>().map_err(|err| err.to_string())?;
+    Ok((name.to_string(), xdg, subpath.to_string()))
+}
+
+fn main() {
+    let matches = command().get_matches();
+    let result = match matches.subcommand() {
+        Some(("clean", matches)) => clean::run(matches),
+        Some(("create", matches)) => create::run(matches),
+        Some(("export", matches)) => export::run(matches),
+        Some(("migrate", matches)) => migrate::run(matches),
+        _ => unreachable!("a subcommand is required"),
+    };
+    if let Err(err) = result {
+        eprintln!("s-app-dir: {}", err);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use s_app_dir::XdgDir;
+
+    /// Split custom directories into a name, a base kind and a subpath.
+    #[test]
+    fn parse_custom() {
+        assert_eq!(Ok(("shots".to_string(), XdgDir::Data, "media/shots".to_string())),
+                   super::parse_custom("shots=data:media/shots"));
+        assert_eq!(Ok(("models".to_string(), XdgDir::Cache, "models".to_string())),
+                   super::parse_custom("models=cache"));
+        assert!(super::parse_custom("models").is_err());
+        assert!(super::parse_custom("models=nowhere").is_err());
+    }
+}
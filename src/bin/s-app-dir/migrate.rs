@@ -0,0 +1,62 @@
+use std::io;
+
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use s_app_dir::{Migration, XdgDir};
+
+use {app_arg, app_dir};
+
+pub fn command() -> Command {
+    Command::new("migrate")
+        .about("Move the app's legacy ~/.<app> directory into its XDG directories")
+        .arg(app_arg())
+        .arg(Arg::new("rule")
+            .long("rule")
+            .action(ArgAction::Append)
+            .value_parser(parse_rule)
+            .value_name("PATTERN=KIND")
+            .help("Move entries matching PATTERN into the KIND directory instead of the data \
+                   directory, e.g. '*.toml=config'"))
+        .arg(Arg::new("dry-run")
+            .long("dry-run")
+            .action(ArgAction::SetTrue)
+            .help("Print the moves without making them"))
+        .arg(Arg::new("symlink-compat")
+            .long("symlink-compat")
+            .action(ArgAction::SetTrue)
+            .help("Leave a symlink from the legacy directory to the data directory"))
+}
+
+pub fn run(matches: &ArgMatches) -> io::Result<()> {
+    let app_dir = app_dir(matches);
+    let mut migration = Migration::new()
+        .dry_run(matches.get_flag("dry-run"))
+        .symlink_compat(matches.get_flag("symlink-compat"));
+    for (pattern, xdg) in matches.get_many::<(String, XdgDir)>("rule").into_iter().flatten() {
+        migration = migration.rule(pattern, *xdg);
+    }
+    for (from, to) in app_dir.migrate_legacy(&migration)? {
+        println!("{} -> {}", from.display(), to.display());
+    }
+    Ok(())
+}
+
+fn parse_rule(rule: &str) -> Result<(String, XdgDir), String> {
+    let (pattern, kind) = rule.rsplit_once('=')
+        .ok_or_else(|| format!("expected PATTERN=KIND: {}", rule))?;
+    let xdg = kind.parse::<XdgDir>().map_err(|err| err.to_string())?;
+    Ok((pattern.to_string(), xdg))
+}
+
+#[cfg(test)]
+mod tests {
+    use s_app_dir::XdgDir;
+
+    /// Split rules into a pattern and a directory kind.
+    #[test]
+    fn parse_rule() {
+        assert_eq!(Ok(("*.toml".to_string(), XdgDir::Config)),
+                   super::parse_rule("*.toml=config"));
+        assert!(super::parse_rule("*.toml").is_err());
+        assert!(super::parse_rule("*.toml=nowhere").is_err());
+    }
+}
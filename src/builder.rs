@@ -0,0 +1,289 @@
+use std::io;
+use std::path::Path;
+
+use relocation;
+use resolver::Resolver;
+use unicode;
+use {AppDir, LinkPolicy, PathForm, PathResolver, ResolveStep, SlugPolicy, XdgDir};
+
+/// Configures an `AppDir` beyond its app name.
+#[derive(Clone, Debug)]
+pub struct AppDirBuilder {
+    app_dir: AppDir,
+    relocation_file: bool,
+    platform_name: Option<String>,
+}
+
+impl AppDirBuilder {
+    pub fn new(app_name: &str) -> AppDirBuilder {
+        AppDirBuilder {
+            app_dir: AppDir::new(app_name),
+            relocation_file: false,
+            platform_name: None,
+        }
+    }
+
+    /// Turn the app name, given as the app's display name, into a directory
+    /// name with `policy`, e.g. `my-great-app` for `My Great App` with
+    /// `SlugPolicy::platform()` on Linux.
+    pub fn slug(mut self, policy: &SlugPolicy) -> AppDirBuilder {
+        let app_name = policy.apply(self.app_dir.app_name());
+        self.app_dir.set_app_name(&app_name);
+        self
+    }
+
+    /// Use `name` as the app name on Linux, whatever else the builder was
+    /// given, e.g. to keep a historical directory name.
+    pub fn linux_name(self, name: &str) -> AppDirBuilder {
+        self.platform_name(cfg!(target_os = "linux"), name)
+    }
+
+    /// Use `name` as the app name on Windows, e.g. `My App`.
+    pub fn windows_name(self, name: &str) -> AppDirBuilder {
+        self.platform_name(cfg!(windows), name)
+    }
+
+    /// Use `name` as the app name on macOS, e.g. a bundle identifier such as
+    /// `com.example.MyApp`.
+    pub fn macos_name(self, name: &str) -> AppDirBuilder {
+        self.platform_name(cfg!(target_os = "macos"), name)
+    }
+
+    fn platform_name(mut self, current: bool, name: &str) -> AppDirBuilder {
+        if current {
+            self.platform_name = Some(name.to_string());
+        }
+        self
+    }
+
+    /// The organization or vendor publishing the app, used where platforms
+    /// group apps by vendor, such as the Windows registry.
+    pub fn organization(mut self, organization: &str) -> AppDirBuilder {
+        self.app_dir.organization = Some(unicode::nfc(organization));
+        self
+    }
+
+    /// Normalize every resolved path into `form`. Defaults to `PathForm::AsIs`.
+    pub fn path_form(mut self, form: PathForm) -> AppDirBuilder {
+        self.app_dir.path_form = form;
+        self
+    }
+
+    /// What to do about links on the way to a directory with
+    /// `PathForm::Canonical`. Defaults to `LinkPolicy::Follow`; other path
+    /// forms never resolve links, so the policy does not apply to them.
+    pub fn link_policy(mut self, policy: LinkPolicy) -> AppDirBuilder {
+        self.app_dir.link_policy = policy;
+        self
+    }
+
+    /// Return extended-length (`\\?\`) paths on Windows when a resolved path
+    /// exceeds `MAX_PATH`. Has no effect on other platforms.
+    pub fn long_paths(mut self, enabled: bool) -> AppDirBuilder {
+        self.app_dir.long_paths = enabled;
+        self
+    }
+
+    /// Fail with a `ResolveError` instead of falling back when an XDG
+    /// variable is not an absolute path. Resolution through `xdg_dir` then
+    /// returns `None` for such values; use `try_xdg_dir` for the reason.
+    pub fn strict(mut self, enabled: bool) -> AppDirBuilder {
+        self.app_dir.strict = enabled;
+        self
+    }
+
+    /// Resolve directories from their XDG variables only, failing with
+    /// `ResolveError::NotSet` instead of falling back to the home directory
+    /// or `%APPDATA%`, as hermetic builds and sandboxes require. The
+    /// registry is not read either.
+    pub fn pure_env(mut self, enabled: bool) -> AppDirBuilder {
+        self.app_dir.pure_env = enabled;
+        self
+    }
+
+    /// Resolve `xdg` through `steps` instead, e.g. an in-house
+    /// `$SITE_CONFIG_ROOT`, then a fixed path, then `ResolveStep::Default`.
+    /// Overrides from `AppDir::with_overrides` still come first.
+    pub fn fallback_chain<I>(mut self, xdg: XdgDir, steps: I) -> AppDirBuilder
+        where I: IntoIterator<Item = ResolveStep>
+    {
+        self.app_dir.chains.insert(xdg, steps.into_iter().collect());
+        self
+    }
+
+    /// Register the custom directory `name` as `subpath` of the `base`
+    /// directory, e.g. `custom_dir("screenshots", XdgDir::Data,
+    /// "screenshots")`, for `AppDir::custom` and `AppDir::resolve_all`.
+    pub fn custom_dir(mut self, name: &str, base: XdgDir, subpath: &str) -> AppDirBuilder {
+        self.app_dir.custom_dirs.insert(name.to_string(), (base, subpath.to_string()));
+        self
+    }
+
+    /// Resolve directories under `root` as if it were the home directory
+    /// when the home directory is missing or read-only, e.g. in scratch
+    /// containers and kiosks; `AppDir::provenance` reports the substitution.
+    /// `root` might be under `env::temp_dir()` or `/var`.
+    pub fn home_fallback<P>(mut self, root: P) -> AppDirBuilder
+        where P: AsRef<Path>
+    {
+        self.app_dir.home_fallback = Some(root.as_ref().to_path_buf());
+        self
+    }
+
+    /// Before creating the temp directories or binding sockets, make sure
+    /// their shared parent, such as `/tmp`, is neither a symlink another
+    /// user owns nor world-writable without the sticky bit, and that
+    /// another user did not create the directory first. Such directories
+    /// fail with an `InsecureDir` error. Has no effect on Windows.
+    pub fn check_shared_dirs(mut self, enabled: bool) -> AppDirBuilder {
+        self.app_dir.check_shared_dirs = enabled;
+        self
+    }
+
+    /// Put temp files in `tmp` under `AppDir::runtime_dir` on Linux when
+    /// `XDG_RUNTIME_DIR` is set, since it is per-user, usually in memory and
+    /// removed at logout. Elsewhere, or without the variable, the temp
+    /// directory stays where it was.
+    pub fn prefer_runtime_dir(mut self, enabled: bool) -> AppDirBuilder {
+        self.app_dir.prefer_runtime_dir = enabled;
+        self
+    }
+
+    /// Resolve directories through `resolver` instead of
+    /// `PlatformResolver`. `AppDir`s with a custom resolver are skipped when
+    /// serialized, and only compare equal to their clones.
+    pub fn resolver<R>(mut self, resolver: R) -> AppDirBuilder
+        where R: PathResolver + 'static
+    {
+        self.app_dir.resolver = Resolver::new(resolver);
+        self
+    }
+
+    /// Read `dirs.toml` from the app's config directory when building, and
+    /// resolve the kinds it lists to the paths given there instead, e.g.
+    /// `cache = "/mnt/big/cache/foo"`. The config directory itself cannot be
+    /// relocated.
+    pub fn relocation_file(mut self, enabled: bool) -> AppDirBuilder {
+        self.relocation_file = enabled;
+        self
+    }
+
+    /// Build the `AppDir`, ignoring an invalid relocation file (with a
+    /// warning, if the `log` feature is enabled).
+    pub fn build(mut self) -> AppDir {
+        self.apply_platform_name();
+        if self.relocation_file {
+            match relocation::load(&self.app_dir) {
+                Ok(relocations) => self.app_dir.relocations = relocations,
+                Err(err) => relocation::warn_invalid(&err),
+            }
+        }
+        self.app_dir
+    }
+
+    /// Like `build`, but fail if the relocation file cannot be read or is
+    /// invalid.
+    pub fn try_build(mut self) -> io::Result<AppDir> {
+        self.apply_platform_name();
+        if self.relocation_file {
+            self.app_dir.relocations = relocation::load(&self.app_dir)?;
+        }
+        Ok(self.app_dir)
+    }
+
+    /// Apply the name for the current platform last, so it also overrides
+    /// `slug`.
+    fn apply_platform_name(&mut self) {
+        if let Some(ref name) = self.platform_name {
+            self.app_dir.set_app_name(name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    /// Apply the configured path form to resolved paths.
+    #[test]
+    fn path_form() {
+        let app_dir = ::AppDir::builder("s_app_dir/../s_app_dir-path_form")
+            .path_form(::PathForm::Lexical)
+            .build();
+        assert_eq!(::PathForm::Lexical.apply(env::temp_dir()).join("s_app_dir-path_form"),
+                   app_dir.temp_dir());
+    }
+
+    /// Use the name given for the current platform, even after a slug.
+    #[test]
+    fn platform_name() {
+        let app_dir = ::AppDir::builder("My App")
+            .linux_name("myapp")
+            .windows_name("My App (Legacy)")
+            .macos_name("com.example.MyApp")
+            .slug(&::SlugPolicy::new().lowercase(true))
+            .build();
+        let expect = if cfg!(target_os = "linux") {
+            "myapp"
+        } else if cfg!(windows) {
+            "My App (Legacy)"
+        } else if cfg!(target_os = "macos") {
+            "com.example.MyApp"
+        } else {
+            "my app"
+        };
+        assert_eq!(expect, app_dir.app_name());
+    }
+
+    /// Keep the organization, which `new` leaves unset.
+    #[test]
+    fn organization() {
+        assert_eq!(None, ::AppDir::new("s_app_dir").organization());
+        let app_dir = ::AppDir::builder("s_app_dir").organization("sifyfy").build();
+        assert_eq!(Some("sifyfy"), app_dir.organization());
+    }
+
+    /// Reject relative XDG values in strict mode only.
+    #[test]
+    fn strict() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        guard.set("XDG_CACHE_HOME", "relative");
+        let lenient = ::AppDir::new("s_app_dir");
+        let strict = ::AppDir::builder("s_app_dir").strict(true).build();
+        assert_eq!(Some(::std::path::PathBuf::from("relative").join("s_app_dir")),
+                   lenient.xdg_dir(::XdgDir::Cache));
+        let err = ::ResolveError::Invalid("XDG_CACHE_HOME".to_string(),
+                                          "relative".into(),
+                                          Default::default());
+        assert_eq!(Err(err), strict.try_xdg_dir(::XdgDir::Cache));
+        assert_eq!(None, strict.xdg_dir(::XdgDir::Cache));
+        guard.remove("XDG_CACHE_HOME");
+        assert_eq!(lenient.xdg_dir(::XdgDir::Cache), strict.xdg_dir(::XdgDir::Cache));
+    }
+
+    /// Require the XDG variable in pure-env mode.
+    #[test]
+    fn pure_env() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::builder("s_app_dir").pure_env(true).build();
+        guard.remove("XDG_CONFIG_HOME");
+        let err = ::ResolveError::NotSet("XDG_CONFIG_HOME".to_string(), Default::default());
+        assert_eq!(Err(err), app_dir.try_xdg_dir(::XdgDir::Config));
+
+        let config = guard.home().join("config");
+        guard.set("XDG_CONFIG_HOME", &config);
+        assert_eq!(Some(config.join("s_app_dir")), app_dir.xdg_dir(::XdgDir::Config));
+
+        #[cfg(all(windows, feature="windows-registry"))]
+        {
+            let registered = guard.home().join("registered");
+            ::registry::set_directory("s_app_dir-pure_env", ::XdgDir::Config, &registered);
+            let pure = ::AppDir::builder("s_app_dir-pure_env").pure_env(true).build();
+            let registry = ::AppDir::new("s_app_dir-pure_env").xdg_dir(::XdgDir::Config);
+            let pure = pure.xdg_dir(::XdgDir::Config);
+            ::registry::remove_app("s_app_dir-pure_env");
+            assert_eq!(Some(registered), registry);
+            assert_eq!(Some(config.join("s_app_dir-pure_env")), pure);
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use {AppDir, XdgDir};
+
+impl AppDir {
+    /// An existing directory whose name differs from the resolved `xdg`
+    /// directory's only in case, such as `MyApp` for `myapp` after the app
+    /// was renamed. On case-insensitive filesystems, as on Windows and
+    /// macOS, it would be used in place of the directory, keeping its old
+    /// name; on others both would exist side by side.
+    pub fn case_collision(&self, xdg: XdgDir) -> Option<PathBuf> {
+        collision(&self.xdg_dir(xdg)?)
+    }
+
+    /// Rename the `case_collision` of the `xdg` directory to the resolved
+    /// name and return its old path, or `None` if there is nothing to
+    /// rename. Fails with `io::ErrorKind::AlreadyExists` if the resolved
+    /// directory exists separately too, so its contents must be merged by
+    /// hand.
+    pub fn resolve_case_collision(&self, xdg: XdgDir) -> io::Result<Option<PathBuf>> {
+        let dir = self.try_xdg_dir(xdg)?;
+        let old = match collision(&dir) {
+            Some(old) => old,
+            None => return Ok(None),
+        };
+        if names_in(dir.parent().expect("app dirs have a parent"))?.contains(&dir) {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists,
+                                      format!("both {} and {} exist",
+                                              old.display(),
+                                              dir.display())));
+        }
+        // Go through another name, since a case-insensitive filesystem may
+        // ignore a rename that only changes case.
+        let mut temp = dir.clone().into_os_string();
+        temp.push(".case-rename");
+        fs::rename(&old, &temp)?;
+        fs::rename(&temp, &dir)?;
+        Ok(Some(old))
+    }
+}
+
+/// The entry next to `dir` whose name matches it except for case.
+fn collision(dir: &Path) -> Option<PathBuf> {
+    let name = dir.file_name()?.to_str()?.to_lowercase();
+    names_in(dir.parent()?).ok()?.into_iter().find(|entry| {
+        entry.as_path() != dir && entry.is_dir() &&
+        entry.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.to_lowercase() == name)
+    })
+}
+
+/// The entries of `dir`, with their names as stored.
+fn names_in(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    fs::read_dir(dir)?.map(|entry| entry.map(|entry| entry.path())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io;
+
+    /// Find a differently-cased directory and rename it.
+    #[test]
+    fn case_collision() {
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("myapp");
+        assert_eq!(None, app_dir.case_collision(::XdgDir::Data));
+        let dir = app_dir.xdg_dir(::XdgDir::Data).unwrap();
+        let old = dir.with_file_name("MyApp");
+        fs::create_dir_all(&old).unwrap();
+        fs::write(old.join("state"), "a").unwrap();
+        assert_eq!(Some(old.clone()), app_dir.case_collision(::XdgDir::Data));
+
+        assert_eq!(Some(old), app_dir.resolve_case_collision(::XdgDir::Data).unwrap());
+        assert_eq!("a", fs::read_to_string(dir.join("state")).unwrap());
+        assert_eq!(None, app_dir.case_collision(::XdgDir::Data));
+        assert_eq!(None, app_dir.resolve_case_collision(::XdgDir::Data).unwrap());
+
+        if cfg!(target_os = "linux") {
+            fs::create_dir(dir.with_file_name("MYAPP")).unwrap();
+            let err = app_dir.resolve_case_collision(::XdgDir::Data).unwrap_err();
+            assert_eq!(io::ErrorKind::AlreadyExists, err.kind());
+        }
+    }
+}
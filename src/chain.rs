@@ -0,0 +1,113 @@
+use std::path::PathBuf;
+
+#[cfg(feature="serde")]
+use serde::{Deserialize, Serialize};
+
+use {AppDir, Env, ResolveError, XdgDir};
+
+/// A step of a custom resolution chain; see
+/// `AppDirBuilder::fallback_chain`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(rename_all = "snake_case"))]
+pub enum ResolveStep {
+    /// The base directory in this environment variable, if set. Values that
+    /// are not valid Unicode are skipped, or rejected along with relative
+    /// paths in strict mode.
+    Var(String),
+    /// This base directory.
+    Path(PathBuf),
+    /// The platform's own resolution, from the XDG variable and the home
+    /// directory or `%APPDATA%`.
+    Default,
+}
+
+/// Resolve `xdg` for `app_dir` through `steps`, taking the first one that
+/// yields a base directory. As for the XDG variables, the app name is
+/// appended to the base except for the shared bin directory.
+pub fn resolve(app_dir: &AppDir,
+               xdg: XdgDir,
+               steps: &[ResolveStep],
+               env: &Env)
+               -> Result<PathBuf, ResolveError> {
+    for step in steps {
+        let base = match *step {
+            ResolveStep::Var(ref key) => {
+                s_app_dir_core::named_var_dir(key, app_dir.options(), env)
+                    .map_err(|key| env.invalid(key))?
+            }
+            ResolveStep::Path(ref dir) => Some(dir.clone()),
+            ResolveStep::Default => return app_dir.resolve_default(xdg, env),
+        };
+        if let Some(base) = base {
+            return Ok(match xdg {
+                XdgDir::Bin => base,
+                _ => base.join(&app_dir.app_name),
+            });
+        }
+    }
+    let keys = steps.iter()
+        .filter_map(|step| match *step {
+            ResolveStep::Var(ref key) => Some(&key[..]),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    Err(env.not_set(&keys.join(" or ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ResolveStep;
+
+    /// Take the first step that yields a directory.
+    #[test]
+    fn fallback_chain() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        guard.remove("S_APP_DIR_SITE_CONFIG_ROOT");
+        let site = guard.home().join("site");
+        let app_dir = ::AppDir::builder("s_app_dir")
+            .fallback_chain(::XdgDir::Config,
+                            vec![ResolveStep::Var("S_APP_DIR_SITE_CONFIG_ROOT".to_string()),
+                                 ResolveStep::Path(PathBuf::from("/etc/site"))])
+            .fallback_chain(::XdgDir::Cache,
+                            vec![ResolveStep::Var("S_APP_DIR_SITE_CONFIG_ROOT".to_string()),
+                                 ResolveStep::Default])
+            .build();
+        assert_eq!(Some(PathBuf::from("/etc/site/s_app_dir")),
+                   app_dir.xdg_dir(::XdgDir::Config));
+        assert_eq!(::AppDir::new("s_app_dir").xdg_dir(::XdgDir::Cache),
+                   app_dir.xdg_dir(::XdgDir::Cache));
+
+        guard.set("S_APP_DIR_SITE_CONFIG_ROOT", &site);
+        assert_eq!(Some(site.join("s_app_dir")), app_dir.xdg_dir(::XdgDir::Config));
+
+        guard.set("S_APP_DIR_SITE_CONFIG_ROOT", "relative");
+        let strict = ::AppDir::builder("s_app_dir")
+            .strict(true)
+            .fallback_chain(::XdgDir::Config,
+                            vec![ResolveStep::Var("S_APP_DIR_SITE_CONFIG_ROOT".to_string())])
+            .build();
+        let err = ::ResolveError::Invalid("S_APP_DIR_SITE_CONFIG_ROOT".to_string(),
+                                          "relative".into(),
+                                          Default::default());
+        assert_eq!(Err(err), strict.try_xdg_dir(::XdgDir::Config));
+    }
+
+    /// Name the variables when no step yields a directory.
+    #[test]
+    fn fallback_chain_not_set() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        guard.remove("S_APP_DIR_A");
+        guard.remove("S_APP_DIR_B");
+        let app_dir = ::AppDir::builder("s_app_dir")
+            .fallback_chain(::XdgDir::Data,
+                            vec![ResolveStep::Var("S_APP_DIR_A".to_string()),
+                                 ResolveStep::Var("S_APP_DIR_B".to_string())])
+            .build();
+        let err = ::ResolveError::NotSet("S_APP_DIR_A or S_APP_DIR_B".to_string(),
+                                         Default::default());
+        assert_eq!(Err(err), app_dir.try_xdg_dir(::XdgDir::Data));
+    }
+}
@@ -0,0 +1,32 @@
+use clap::builder::PossibleValue;
+use clap::ValueEnum;
+
+use XdgDir;
+
+impl ValueEnum for XdgDir {
+    fn value_variants<'a>() -> &'a [XdgDir] {
+        XdgDir::all()
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{value_parser, Arg, Command};
+
+    /// Accept kind names as argument values and reject unknown ones.
+    #[test]
+    fn value_enum() {
+        let command = Command::new("s_app_dir")
+            .arg(Arg::new("dir-kind").long("dir-kind").value_parser(value_parser!(::XdgDir)));
+
+        let matches = command.clone()
+            .try_get_matches_from(vec!["s_app_dir", "--dir-kind", "config"])
+            .unwrap();
+        assert_eq!(Some(&::XdgDir::Config), matches.get_one::<::XdgDir>("dir-kind"));
+        assert!(command.try_get_matches_from(vec!["s_app_dir", "--dir-kind", "state"]).is_err());
+    }
+}
@@ -0,0 +1,181 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use {AppDir, Fs, StdFs, XdgDir};
+
+/// The files removed by `AppDir::clean_cache` or `AppDir::clean_temp`, or
+/// that would have been in a dry run.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct CleanReport {
+    /// The removed files, sorted.
+    pub removed: Vec<PathBuf>,
+    /// The total size of the removed files.
+    pub bytes: u64,
+}
+
+impl AppDir {
+    /// Remove the files in the cache directory, or only those last modified
+    /// more than `older_than` ago, then any directories left empty that are
+    /// also that old. The `CACHEDIR.TAG` is kept.
+    ///
+    /// With `dry_run`, nothing is removed, but the report lists what would
+    /// be. A missing directory is already clean.
+    pub fn clean_cache(&self,
+                       older_than: Option<Duration>,
+                       dry_run: bool)
+                       -> io::Result<CleanReport> {
+        self.clean_cache_with_fs(&StdFs, older_than, dry_run)
+    }
+
+    /// Like `clean_cache`, but go through `fs`.
+    pub fn clean_cache_with_fs<F>(&self,
+                                  fs: &F,
+                                  older_than: Option<Duration>,
+                                  dry_run: bool)
+                                  -> io::Result<CleanReport>
+        where F: Fs + ?Sized
+    {
+        clean_dir(fs, &self.try_xdg_dir(XdgDir::Cache)?, older_than, dry_run)
+    }
+
+    /// Like `clean_cache`, but for the temp directory.
+    pub fn clean_temp(&self,
+                      older_than: Option<Duration>,
+                      dry_run: bool)
+                      -> io::Result<CleanReport> {
+        clean_dir(&StdFs, &self.temp_dir(), older_than, dry_run)
+    }
+}
+
+fn clean_dir<F>(fs: &F,
+                dir: &Path,
+                older_than: Option<Duration>,
+                dry_run: bool)
+                -> io::Result<CleanReport>
+    where F: Fs + ?Sized
+{
+    let cutoff = match older_than {
+        Some(age) => SystemTime::now().checked_sub(age),
+        None => None,
+    };
+    let mut report = CleanReport::default();
+    if let Err(err) = clean_entries(fs, dir, true, cutoff, dry_run, &mut report) {
+        if err.kind() != io::ErrorKind::NotFound {
+            return Err(err);
+        }
+    }
+    report.removed.sort();
+    Ok(report)
+}
+
+/// Clean `dir` and return whether it is now empty. Symlinks are removed,
+/// never followed. A directory's age is taken before its entries are
+/// removed, since that touches it.
+fn clean_entries<F>(fs: &F,
+                    dir: &Path,
+                    top: bool,
+                    cutoff: Option<SystemTime>,
+                    dry_run: bool,
+                    report: &mut CleanReport)
+                    -> io::Result<bool>
+    where F: Fs + ?Sized
+{
+    let mut empty = true;
+    for path in fs.read_dir(dir)? {
+        let metadata = fs.symlink_metadata(&path)?;
+        let stale = cutoff.is_none_or(|cutoff| metadata.modified.is_some_and(|m| m < cutoff));
+        if metadata.is_dir {
+            if clean_entries(fs, &path, false, cutoff, dry_run, report)? && stale && !dry_run {
+                fs.remove_dir(&path)?;
+            } else {
+                empty = false;
+            }
+            continue;
+        }
+        if !stale || (top && path.file_name().is_some_and(|name| name == "CACHEDIR.TAG")) {
+            empty = false;
+            continue;
+        }
+        if dry_run {
+            empty = false;
+        } else {
+            fs.remove_file(&path)?;
+        }
+        report.bytes += metadata.len;
+        report.removed.push(path);
+    }
+    Ok(empty)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::Duration;
+
+    /// Report without removing in a dry run, then remove everything but the
+    /// tag, including emptied directories.
+    #[test]
+    fn clean_cache() {
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        assert_eq!(::CleanReport::default(), app_dir.clean_cache(None, false).unwrap());
+
+        app_dir.tag_cache_dir().unwrap();
+        let cache = app_dir.xdg_dir(::XdgDir::Cache).unwrap();
+        fs::create_dir_all(cache.join("objects")).unwrap();
+        fs::write(cache.join("objects").join("a"), b"abc").unwrap();
+        fs::write(cache.join("index"), b"de").unwrap();
+
+        let recent = app_dir.clean_cache(Some(Duration::from_secs(3600)), false).unwrap();
+        assert!(recent.removed.is_empty());
+        let expect = ::CleanReport {
+            removed: vec![cache.join("index"), cache.join("objects").join("a")],
+            bytes: 5,
+        };
+        assert_eq!(expect, app_dir.clean_cache(None, true).unwrap());
+        assert!(cache.join("index").exists());
+        assert_eq!(expect, app_dir.clean_cache(None, false).unwrap());
+        let left = fs::read_dir(&cache)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["CACHEDIR.TAG"], left);
+    }
+
+    /// Remove only old files, and emptied directories only if they are old
+    /// too.
+    #[test]
+    fn clean_cache_with_fs() {
+        use std::time::UNIX_EPOCH;
+
+        use Fs;
+
+        // The real filesystem, except that entries named `old*` are dated
+        // 1970.
+        let aged = ::filesystem::PatchedFs::default().symlink_metadata(|path| {
+            let mut metadata = ::StdFs.symlink_metadata(path)?;
+            if path.file_name().unwrap().to_string_lossy().starts_with("old") {
+                metadata.modified = Some(UNIX_EPOCH);
+            }
+            Ok(metadata)
+        });
+
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        let cache = app_dir.ensure_xdg_dir(::XdgDir::Cache).unwrap();
+        for dir in &["old_dir", "new_dir"] {
+            fs::create_dir(cache.join(dir)).unwrap();
+            fs::write(cache.join(dir).join("old_file"), b"abc").unwrap();
+        }
+        fs::write(cache.join("new_file"), b"de").unwrap();
+
+        let report = app_dir.clean_cache_with_fs(&aged, Some(Duration::from_secs(3600)), false)
+            .unwrap();
+        let expect = vec![cache.join("new_dir/old_file"), cache.join("old_dir/old_file")];
+        assert_eq!(expect, report.removed);
+        assert!(!cache.join("old_dir").exists());
+        assert!(cache.join("new_dir").is_dir());
+        assert!(cache.join("new_file").exists());
+    }
+}
@@ -0,0 +1,384 @@
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+#[cfg(feature="serde")]
+use serde::{Deserialize, Serialize};
+
+use glob;
+use normalize;
+use {AppDir, WriteError, XdgDir};
+
+/// A config file format, identified by its file extension.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(rename_all = "lowercase"))]
+pub enum ConfigFormat {
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// Every format, in the default detection order.
+    pub fn all() -> &'static [ConfigFormat] {
+        static ALL: [ConfigFormat; 3] = [ConfigFormat::Toml, ConfigFormat::Yaml, ConfigFormat::Json];
+        &ALL
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match *self {
+            ConfigFormat::Toml => "toml",
+            ConfigFormat::Yaml => "yaml",
+            ConfigFormat::Json => "json",
+        }
+    }
+}
+
+/// The config file picked by format detection.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+pub struct FoundConfig {
+    pub path: PathBuf,
+    pub format: ConfigFormat,
+}
+
+/// Which part of the config search path a file was found in.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(rename_all = "lowercase"))]
+pub enum ConfigScope {
+    /// A project-local config, found by `AppDir::project_config`.
+    Project,
+    /// The user's own config directory.
+    User,
+    /// A directory in the app's search-path variable; see `AppDir::path_var`.
+    PathVar,
+    /// A system-wide directory such as one in `XDG_CONFIG_DIRS`.
+    System,
+}
+
+/// A config file found by `AppDir::search_configs`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+pub struct ConfigMatch {
+    pub path: PathBuf,
+    pub scope: ConfigScope,
+}
+
+/// Errors from loading a config file.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The config directory could not be resolved.
+    NoConfigDir,
+    /// None of the paths in the config search path exist.
+    NotFound(Vec<PathBuf>),
+    /// The config file could not be read.
+    Io(PathBuf, io::Error),
+    /// The config file was read but could not be parsed.
+    Parse(PathBuf, Box<dyn error::Error + Send + Sync>),
+    /// The config file was parsed but rejected by validation.
+    Invalid(PathBuf, Box<dyn error::Error + Send + Sync>),
+    /// The config could not be serialized for writing.
+    Serialize(PathBuf, Box<dyn error::Error + Send + Sync>),
+    /// The config file could not be written.
+    Write(WriteError),
+    /// The config file could not be watched for changes.
+    #[cfg(feature="notify")]
+    Watch(::notify::Error),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::NoConfigDir => write!(f, "cannot resolve the config directory"),
+            ConfigError::NotFound(ref paths) => {
+                write!(f, "config not found in:")?;
+                for path in paths {
+                    write!(f, " {}", path.display())?;
+                }
+                Ok(())
+            }
+            ConfigError::Io(ref path, ref err) => write!(f, "{}: {}", path.display(), err),
+            ConfigError::Parse(ref path, ref err) => write!(f, "{}: {}", path.display(), err),
+            ConfigError::Invalid(ref path, ref err) => {
+                write!(f, "{}: invalid config: {}", path.display(), err)
+            }
+            ConfigError::Serialize(ref path, ref err) => {
+                write!(f, "{}: cannot serialize config: {}", path.display(), err)
+            }
+            ConfigError::Write(ref err) => err.fmt(f),
+            #[cfg(feature="notify")]
+            ConfigError::Watch(ref err) => write!(f, "cannot watch config: {}", err),
+        }
+    }
+}
+
+impl error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            ConfigError::NoConfigDir | ConfigError::NotFound(_) => None,
+            ConfigError::Io(_, ref err) => Some(err),
+            ConfigError::Parse(_, ref err) |
+            ConfigError::Invalid(_, ref err) |
+            ConfigError::Serialize(_, ref err) => Some(&**err),
+            ConfigError::Write(ref err) => err.source(),
+            #[cfg(feature="notify")]
+            ConfigError::Watch(ref err) => Some(err),
+        }
+    }
+}
+
+impl AppDir {
+    /// The first existing file `name` in the config search path.
+    pub fn find_config(&self, name: &str) -> Result<PathBuf, ConfigError> {
+        let candidates = self.config_search_path()
+            .into_iter()
+            .map(|dir| dir.join(name))
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            return Err(ConfigError::NoConfigDir);
+        }
+        match candidates.iter().position(|path| path.is_file()) {
+            Some(i) => Ok(candidates[i].clone()),
+            None => Err(ConfigError::NotFound(candidates)),
+        }
+    }
+
+    /// Read the config file `name` and hand its contents to `parse`.
+    ///
+    /// `parse` is typically a deserializer such as `toml::from_str`.
+    pub fn load_config<T, F, E>(&self, name: &str, parse: F) -> Result<T, ConfigError>
+        where F: FnOnce(&str) -> Result<T, E>,
+              E: Into<Box<dyn error::Error + Send + Sync>>
+    {
+        load_file(self.find_config(name)?, parse)
+    }
+
+    /// Like `load_config`, but also run `validate` on the parsed value.
+    ///
+    /// A rejected value is reported as `ConfigError::Invalid` together with
+    /// the file it was loaded from.
+    pub fn load_validated_config<T, F, E, V, VE>(&self,
+                                                 name: &str,
+                                                 parse: F,
+                                                 validate: V)
+                                                 -> Result<T, ConfigError>
+        where F: FnOnce(&str) -> Result<T, E>,
+              E: Into<Box<dyn error::Error + Send + Sync>>,
+              V: FnOnce(&T) -> Result<(), VE>,
+              VE: Into<Box<dyn error::Error + Send + Sync>>
+    {
+        let path = self.find_config(name)?;
+        let value = load_file(path.clone(), parse)?;
+        match validate(&value) {
+            Ok(()) => Ok(value),
+            Err(err) => Err(ConfigError::Invalid(path, err.into())),
+        }
+    }
+
+    /// The first existing `stem.<extension>` in the config search path.
+    ///
+    /// Each directory is tried with every format in `formats` before moving
+    /// on to the next directory, so a user config in any format wins over a
+    /// system one.
+    pub fn find_config_format(&self,
+                              stem: &str,
+                              formats: &[ConfigFormat])
+                              -> Result<FoundConfig, ConfigError> {
+        let candidates = self.config_search_path()
+            .into_iter()
+            .flat_map(|dir| {
+                formats.iter().map(move |format| {
+                    FoundConfig {
+                        path: dir.join(format!("{}.{}", stem, format.extension())),
+                        format: *format,
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            return Err(ConfigError::NoConfigDir);
+        }
+        match candidates.iter().position(|found| found.path.is_file()) {
+            Some(i) => Ok(candidates[i].clone()),
+            None => Err(ConfigError::NotFound(candidates.into_iter().map(|c| c.path).collect())),
+        }
+    }
+
+    /// Detect the config file with `find_config_format` and parse it.
+    ///
+    /// `parse` receives the detected format so it can pick a deserializer.
+    pub fn load_config_format<T, F, E>(&self,
+                                       stem: &str,
+                                       formats: &[ConfigFormat],
+                                       parse: F)
+                                       -> Result<(T, FoundConfig), ConfigError>
+        where F: FnOnce(ConfigFormat, &str) -> Result<T, E>,
+              E: Into<Box<dyn error::Error + Send + Sync>>
+    {
+        let found = self.find_config_format(stem, formats)?;
+        let format = found.format;
+        let value = load_file(found.path.clone(), |s| parse(format, s))?;
+        Ok((value, found))
+    }
+
+    /// Every file matching the glob `pattern` in the config search path,
+    /// searched recursively, e.g. `conf.d/*.toml` for conf.d-style fragments.
+    ///
+    /// Matches are in order of precedence: the user directory first, then
+    /// those in the app's search-path variable, then each system directory,
+    /// each sorted by path. See `list` for the pattern syntax.
+    pub fn search_configs(&self, pattern: &str) -> io::Result<Vec<ConfigMatch>> {
+        let user = self.xdg_dir(XdgDir::Config);
+        let path_var = normalize::search_path(self.path_var_dirs());
+        let mut matches = Vec::new();
+        for dir in self.config_search_path() {
+            let scope = if Some(&dir) == user.as_ref() {
+                ConfigScope::User
+            } else if path_var.contains(&dir) {
+                ConfigScope::PathVar
+            } else {
+                ConfigScope::System
+            };
+            for path in glob::glob(&dir, pattern, true)? {
+                matches.push(ConfigMatch { path, scope });
+            }
+        }
+        Ok(matches)
+    }
+}
+
+pub fn load_file<T, F, E>(path: PathBuf, parse: F) -> Result<T, ConfigError>
+    where F: FnOnce(&str) -> Result<T, E>,
+          E: Into<Box<dyn error::Error + Send + Sync>>
+{
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) => return Err(ConfigError::Io(path, err)),
+    };
+    parse(&contents).map_err(|err| ConfigError::Parse(path, err.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    /// Return the parsed value of an existing file.
+    #[test]
+    fn load_file() {
+        let path = env::temp_dir().join("s_app_dir-load_file");
+        fs::write(&path, "42").unwrap();
+        let value = super::load_file(path.clone(), |s| s.parse::<u32>());
+        assert_eq!(42, value.unwrap());
+        let _ = fs::remove_file(&path);
+    }
+
+    /// Return `ConfigError::Parse` with the path if parsing fails.
+    #[test]
+    fn load_file_parse_error() {
+        let path = env::temp_dir().join("s_app_dir-load_file_parse_error");
+        fs::write(&path, "forty-two").unwrap();
+        match super::load_file(path.clone(), |s| s.parse::<u32>()) {
+            Err(::ConfigError::Parse(p, _)) => assert_eq!(path, p),
+            other => panic!("unexpected result: {:?}", other.map_err(|e| e.to_string())),
+        }
+        let _ = fs::remove_file(&path);
+    }
+
+    /// Return `ConfigError::Io` if the file is missing.
+    #[test]
+    fn load_file_missing() {
+        let path = env::temp_dir().join("s_app_dir-load_file_missing");
+        let _ = fs::remove_file(&path);
+        match super::load_file(path, |s| s.parse::<u32>()) {
+            Err(::ConfigError::Io(..)) => {}
+            other => panic!("unexpected result: {:?}", other.map_err(|e| e.to_string())),
+        }
+    }
+
+    /// Return `ConfigError::Invalid` with the system layer path if validation fails.
+    #[cfg(unix)]
+    #[test]
+    fn load_validated_config_invalid() {
+        let app_name = "s_app_dir-load_validated_config_invalid";
+        let system = env::temp_dir().join(app_name);
+        let _ = fs::remove_dir_all(&system);
+        fs::create_dir_all(system.join(app_name)).unwrap();
+        let path = system.join(app_name).join("config");
+        fs::write(&path, "0").unwrap();
+
+        let value = {
+            let mut guard = ::TempEnvGuard::new().unwrap();
+            guard.set("XDG_CONFIG_DIRS", &system);
+            ::AppDir::new(app_name).load_validated_config("config",
+                                                          |s| s.parse::<u32>(),
+                                                          |n| if *n > 0 { Ok(()) } else { Err("zero") })
+        };
+        match value {
+            Err(::ConfigError::Invalid(p, _)) => assert_eq!(path, p),
+            other => panic!("unexpected result: {:?}", other.map_err(|e| e.to_string())),
+        }
+        let _ = fs::remove_dir_all(&system);
+    }
+
+    /// Prefer an earlier format in the same directory and any format in an earlier directory.
+    #[cfg(unix)]
+    #[test]
+    fn find_config_format() {
+        let app_name = "s_app_dir-find_config_format";
+        let base = env::temp_dir().join(app_name);
+        let _ = fs::remove_dir_all(&base);
+        let (first, second) = (base.join("first"), base.join("second"));
+        fs::create_dir_all(first.join(app_name)).unwrap();
+        fs::create_dir_all(second.join(app_name)).unwrap();
+        fs::write(first.join(app_name).join("config.json"), "{}").unwrap();
+        fs::write(second.join(app_name).join("config.toml"), "").unwrap();
+        fs::write(second.join(app_name).join("config.yaml"), "").unwrap();
+
+        let app_dir = ::AppDir::new(app_name);
+        let (found, reordered) = {
+            let mut guard = ::TempEnvGuard::new().unwrap();
+            guard.set("XDG_CONFIG_DIRS", env::join_paths([&first, &second]).unwrap());
+            (app_dir.find_config_format("config", ::ConfigFormat::all()).unwrap(),
+             app_dir.find_config_format("config", &[::ConfigFormat::Yaml]).unwrap())
+        };
+        assert_eq!(first.join(app_name).join("config.json"), found.path);
+        assert_eq!(::ConfigFormat::Json, found.format);
+        assert_eq!(second.join(app_name).join("config.yaml"), reordered.path);
+        assert_eq!(::ConfigFormat::Yaml, reordered.format);
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    /// Tag conf.d fragments with their layer, user first.
+    #[cfg(unix)]
+    #[test]
+    fn search_configs() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        let system = guard.home().join("xdg");
+        guard.set("XDG_CONFIG_DIRS", &system);
+        let app_dir = ::AppDir::new("s_app_dir");
+        let user = app_dir.ensure_xdg_dir(::XdgDir::Config).unwrap();
+        for dir in &[user.clone(), system.join("s_app_dir")] {
+            fs::create_dir_all(dir.join("conf.d")).unwrap();
+            fs::write(dir.join("conf.d/10-base.toml"), "").unwrap();
+        }
+        fs::write(user.join("conf.d/20-local.toml"), "").unwrap();
+        fs::write(user.join("conf.d/README"), "").unwrap();
+        let extra = guard.home().join("extra");
+        fs::create_dir_all(extra.join("conf.d")).unwrap();
+        fs::write(extra.join("conf.d/30-site.toml"), "").unwrap();
+        guard.set(app_dir.path_var(), &extra);
+
+        let found = app_dir.search_configs("conf.d/*.toml").unwrap();
+        let expect = vec![(user.join("conf.d/10-base.toml"), ::ConfigScope::User),
+                          (user.join("conf.d/20-local.toml"), ::ConfigScope::User),
+                          (extra.join("conf.d/30-site.toml"), ::ConfigScope::PathVar),
+                          (system.join("s_app_dir/conf.d/10-base.toml"), ::ConfigScope::System)];
+        assert_eq!(expect,
+                   found.into_iter().map(|m| (m.path, m.scope)).collect::<Vec<_>>());
+    }
+}
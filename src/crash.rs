@@ -0,0 +1,92 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::SystemTime;
+
+use timestamp::Utc;
+use {AppDir, XdgDir};
+
+/// How many crash reports `write_crash_report` keeps.
+const KEPT_REPORTS: usize = 20;
+
+impl AppDir {
+    /// `crashes` in the data directory, where panic hooks and crash handlers
+    /// leave their reports. It is not in the cache directory, since reports
+    /// should survive until the user or app deals with them.
+    pub fn crash_dir(&self) -> Option<PathBuf> {
+        self.xdg_dir(XdgDir::Data).map(|dir| dir.join("crashes"))
+    }
+
+    /// Write `contents` to a new file in `crash_dir`, named after the UTC
+    /// time and the process ID, e.g.
+    /// `crash-20261016T093000.123456789Z-4242.txt`, and return its path.
+    ///
+    /// All but the newest 20 reports are then removed. Failing to remove
+    /// them is not an error, since the report itself was written.
+    pub fn write_crash_report(&self, contents: &[u8]) -> io::Result<PathBuf> {
+        let dir = self.try_xdg_dir(XdgDir::Data)?.join("crashes");
+        fs::create_dir_all(&dir)?;
+        let stem = format!("crash-{}-{}", Utc::from(SystemTime::now()).compact(), process::id());
+        let mut attempt = 0;
+        let (path, mut file) = loop {
+            let name = match attempt {
+                0 => format!("{}.txt", stem),
+                n => format!("{}-{}.txt", stem, n),
+            };
+            let path = dir.join(name);
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => break (path, file),
+                Err(ref err) if err.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => {
+                    attempt += 1
+                }
+                Err(err) => return Err(err),
+            }
+        };
+        file.write_all(contents)?;
+        file.sync_all()?;
+        let _ = prune(&dir, KEPT_REPORTS);
+        Ok(path)
+    }
+}
+
+/// Remove all but the `kept` newest crash reports in `dir`.
+fn prune(dir: &Path, kept: usize) -> io::Result<()> {
+    let mut reports = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let name = entry?.file_name();
+        if name.to_str().is_some_and(|name| name.starts_with("crash-") && name.ends_with(".txt")) {
+            reports.push(name);
+        }
+    }
+    reports.sort();
+    let excess = reports.len().saturating_sub(kept);
+    for name in &reports[..excess] {
+        fs::remove_file(dir.join(name))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    /// Write uniquely named reports and keep only the newest.
+    #[test]
+    fn write_crash_report() {
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        let dir = app_dir.crash_dir().unwrap();
+        let first = app_dir.write_crash_report(b"first").unwrap();
+        assert_eq!(Some(&*dir), first.parent());
+        assert_eq!("first", fs::read_to_string(&first).unwrap());
+
+        fs::write(dir.join("notes"), "").unwrap();
+        for _ in 0..super::KEPT_REPORTS {
+            app_dir.write_crash_report(b"again").unwrap();
+        }
+        assert!(!first.exists());
+        assert!(dir.join("notes").exists());
+        assert_eq!(super::KEPT_REPORTS + 1, fs::read_dir(&dir).unwrap().count());
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use ensure;
+use {AppDir, Env};
+
+impl AppDir {
+    /// The custom directory `name`, registered with
+    /// `AppDirBuilder::custom_dir`, e.g. `custom("screenshots")`. `None` if
+    /// `name` is unknown or its base directory cannot be resolved.
+    pub fn custom(&self, name: &str) -> Option<PathBuf> {
+        self.try_custom(name).ok()
+    }
+
+    /// Like `custom`, but say why the directory could not be resolved.
+    pub fn try_custom(&self, name: &str) -> io::Result<PathBuf> {
+        self.resolve_custom(name, &Env::capture_for(self))
+    }
+
+    /// Every custom directory paired with its resolved path, by name.
+    pub fn custom_dirs(&self) -> Vec<(&str, Option<PathBuf>)> {
+        self.custom_dirs.keys().map(|name| (&name[..], self.custom(name))).collect()
+    }
+
+    /// Create the custom directory `name` and its parents if missing.
+    pub fn ensure_custom(&self, name: &str) -> io::Result<PathBuf> {
+        let dir = self.try_custom(name)?;
+        fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// Like `ensure_custom`, but give the directory itself exactly `mode`,
+    /// as `ensure_xdg_dir_with_mode` does.
+    pub fn ensure_custom_with_mode(&self, name: &str, mode: u32) -> io::Result<PathBuf> {
+        let dir = self.try_custom(name)?;
+        ensure::create_dir_with_mode(&dir, mode)?;
+        Ok(dir)
+    }
+
+    fn resolve_custom(&self, name: &str, env: &Env) -> io::Result<PathBuf> {
+        let &(base, ref subpath) = self.custom_dirs.get(name).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound,
+                           format!("unknown directory kind: {}", name))
+        })?;
+        self.join_file(self.resolve_in(base, env)?, subpath)
+    }
+}
+
+/// The custom directories of `app_dir`, for `AppDir::resolve_all`.
+pub fn resolve_all(app_dir: &AppDir, env: &Env) -> BTreeMap<String, Option<PathBuf>> {
+    app_dir.custom_dirs
+        .keys()
+        .map(|name| (name.clone(), app_dir.resolve_custom(name, env).ok()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    /// Resolve custom directories under their base kind.
+    #[test]
+    fn custom() {
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::builder("s_app_dir")
+            .custom_dir("screenshots", ::XdgDir::Data, "media/screenshots")
+            .custom_dir("models", ::XdgDir::Cache, "models")
+            .build();
+        let data = app_dir.xdg_dir(::XdgDir::Data).unwrap();
+        assert_eq!(Some(data.join("media/screenshots")), app_dir.custom("screenshots"));
+        assert_eq!(io::ErrorKind::NotFound, app_dir.try_custom("nowhere").unwrap_err().kind());
+
+        let models = app_dir.ensure_custom("models").unwrap();
+        assert!(models.is_dir());
+        assert_eq!(Some(&Some(models)), app_dir.resolve_all().custom.get("models"));
+        assert_eq!(vec!["models", "screenshots"],
+                   app_dir.custom_dirs().into_iter().map(|(name, _)| name).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,81 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::vec;
+
+use long_path;
+use AppDir;
+
+impl AppDir {
+    /// Every existing copy of the relative file `name` in the data search
+    /// path, in order of precedence: the first one is the user's override,
+    /// if any, and the rest are the system copies it overrides.
+    ///
+    /// A directory listed more than once, e.g. through a symlink or a
+    /// repeated `XDG_DATA_DIRS` entry, is only visited once.
+    pub fn data_files(&self, name: &str) -> io::Result<DataFiles> {
+        Ok(DataFiles {
+            dirs: self.data_search_path().into_iter(),
+            name: long_path::clean_relative(name)?,
+            seen: Vec::new(),
+        })
+    }
+}
+
+/// The iterator returned by `AppDir::data_files`.
+#[derive(Debug)]
+pub struct DataFiles {
+    dirs: vec::IntoIter<PathBuf>,
+    name: PathBuf,
+    seen: Vec<PathBuf>,
+}
+
+impl Iterator for DataFiles {
+    type Item = PathBuf;
+
+    fn next(&mut self) -> Option<PathBuf> {
+        for dir in &mut self.dirs {
+            let path = dir.join(&self.name);
+            if !path.exists() {
+                continue;
+            }
+            let canonical = fs::canonicalize(&dir).unwrap_or(dir);
+            if self.seen.contains(&canonical) {
+                continue;
+            }
+            self.seen.push(canonical);
+            return Some(path);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    /// Yield the user copy before system copies and skip a repeated directory.
+    #[cfg(unix)]
+    #[test]
+    fn data_files() {
+        use std::env;
+        use std::fs;
+        use std::os::unix::fs::symlink;
+
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        let system = guard.home().join("system");
+        let link = guard.home().join("link");
+        symlink(&system, &link).unwrap();
+        guard.set("XDG_DATA_DIRS", env::join_paths([&system, &link, &system]).unwrap());
+        let app_dir = ::AppDir::new("s_app_dir");
+        assert_eq!(0, app_dir.data_files("palettes/default.gpl").unwrap().count());
+
+        let user = app_dir.ensure_xdg_dir(::XdgDir::Data).unwrap();
+        for dir in &[user.clone(), system.join("s_app_dir")] {
+            fs::create_dir_all(dir.join("palettes")).unwrap();
+            fs::write(dir.join("palettes/default.gpl"), "").unwrap();
+        }
+        assert_eq!(vec![user.join("palettes/default.gpl"),
+                        system.join("s_app_dir/palettes/default.gpl")],
+                   app_dir.data_files("palettes/default.gpl").unwrap().collect::<Vec<_>>());
+        assert!(app_dir.data_files("../escape").is_err());
+    }
+}
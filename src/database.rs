@@ -0,0 +1,68 @@
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use {AppDir, XdgDir};
+
+/// The suffixes SQLite appends to a database's name for its write-ahead
+/// log, shared memory index and rollback journal.
+const SIDECAR_SUFFIXES: [&str; 3] = ["-wal", "-shm", "-journal"];
+
+impl AppDir {
+    /// The database file `name` in the data directory, e.g.
+    /// `database_path("main.db")`, creating the directory if missing so the
+    /// database can be opened right away.
+    pub fn database_path(&self, name: &str) -> io::Result<PathBuf> {
+        let dir = self.try_xdg_dir(XdgDir::Data)?;
+        fs::create_dir_all(&dir)?;
+        self.join_file(dir, name)
+    }
+}
+
+/// The files that belong with the database at `path`, whether or not they
+/// exist: `main.db-wal`, `main.db-shm` and `main.db-journal` for `main.db`.
+/// Copy or move them along with the database, or it may lose data.
+pub fn database_sidecars<P>(path: P) -> Vec<PathBuf>
+    where P: AsRef<Path>
+{
+    let path = path.as_ref();
+    SIDECAR_SUFFIXES
+        .iter()
+        .map(|suffix| {
+            let mut name = OsString::from(path);
+            name.push(suffix);
+            PathBuf::from(name)
+        })
+        .collect()
+}
+
+/// The database `name` is a sidecar of, or `name` itself.
+pub fn main_file(name: &str) -> &str {
+    SIDECAR_SUFFIXES
+        .iter()
+        .filter_map(|suffix| name.strip_suffix(suffix))
+        .find(|main| !main.is_empty())
+        .unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    /// Create the data directory and name the sidecars.
+    #[test]
+    fn database_path() {
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        let path = app_dir.database_path("main.db").unwrap();
+        assert_eq!(app_dir.xdg_dir(::XdgDir::Data).unwrap().join("main.db"), path);
+        assert!(path.parent().unwrap().is_dir());
+        assert_eq!(vec![PathBuf::from("/a/main.db-wal"),
+                        PathBuf::from("/a/main.db-shm"),
+                        PathBuf::from("/a/main.db-journal")],
+                   ::database_sidecars("/a/main.db"));
+        assert_eq!("main.db", super::main_file("main.db-wal"));
+        assert_eq!("-shm", super::main_file("-shm"));
+    }
+}
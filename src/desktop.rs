@@ -0,0 +1,119 @@
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+
+use {resolve_base_dir, write_atomic, AppDir, Env, ResolveError, XdgDir};
+
+impl AppDir {
+    /// `$XDG_DATA_HOME/mime/packages`, where per-user MIME type definitions
+    /// are installed. Shared by all apps.
+    pub fn mime_packages_dir(&self) -> Option<PathBuf> {
+        self.shared_data_dir("mime/packages").ok()
+    }
+
+    /// Install `xml`, a shared-mime-info package defining the app's file
+    /// types, as `mime_packages_dir()/app_name.xml`, and return its path.
+    ///
+    /// The types are only picked up once `update-mime-database` has rebuilt
+    /// the MIME cache; pass `update` to run it.
+    pub fn install_mime_package(&self, xml: &[u8], update: bool) -> io::Result<PathBuf> {
+        let mime = self.shared_data_dir("mime")?;
+        let file = self.join_file(mime.join("packages"), &format!("{}.xml", self.app_name))?;
+        write_atomic(&file, xml)?;
+        if update {
+            run(Command::new("update-mime-database").arg(&mime))?;
+        }
+        Ok(file)
+    }
+
+    /// `$XDG_DATA_HOME/dbus-1/services`, where session bus services
+    /// installed for the current user are looked up. Shared by all apps.
+    pub fn dbus_services_dir(&self) -> Option<PathBuf> {
+        self.shared_data_dir("dbus-1/services").ok()
+    }
+
+    /// Install a session service activation file, so the bus starts `exec`
+    /// when a client first talks to `bus_name`, and return its path.
+    ///
+    /// The file is named `<bus_name>.service`, as the bus requires. The bus
+    /// watches the directory, so no reload is needed.
+    pub fn install_dbus_service(&self, bus_name: &str, exec: &str) -> io::Result<PathBuf> {
+        if !is_bus_name(bus_name) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      format!("not a well-known bus name: {}", bus_name)));
+        }
+        if exec.contains('\n') {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "Exec may not span several lines"));
+        }
+        let dir = self.shared_data_dir("dbus-1/services")?;
+        let file = self.join_file(dir, &format!("{}.service", bus_name))?;
+        let contents = format!("[D-BUS Service]\nName={}\nExec={}\n", bus_name, exec);
+        write_atomic(&file, contents.as_bytes())?;
+        Ok(file)
+    }
+
+    /// `relative` under the data directory shared by all apps.
+    fn shared_data_dir(&self, relative: &str) -> Result<PathBuf, ResolveError> {
+        let base = resolve_base_dir(XdgDir::Data, self.options(), &Env::capture())?;
+        Ok(self.finish(base.join(relative)))
+    }
+}
+
+/// Whether `name` is a well-known D-Bus name such as `org.example.App`.
+fn is_bus_name(name: &str) -> bool {
+    name.len() <= 255 && name.split('.').count() >= 2 &&
+    name.split('.').all(|element| {
+        element.chars().next().is_some_and(|c| !c.is_ascii_digit()) &&
+        element.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    })
+}
+
+/// Run a tool that refreshes a desktop database, failing if it can't be
+/// started or exits unsuccessfully.
+pub fn run(command: &mut Command) -> io::Result<()> {
+    let status = command.status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("{:?} failed: {}", command, status)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    /// Install the package under the data directory without running the updater.
+    #[test]
+    fn install_mime_package() {
+        let guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        let packages = guard.home().join(".local/share/mime/packages");
+        assert_eq!(Some(packages.clone()), app_dir.mime_packages_dir());
+
+        let xml = b"<?xml version=\"1.0\"?>\n<mime-info/>\n";
+        let file = app_dir.install_mime_package(xml, false).unwrap();
+        assert_eq!(packages.join("s_app_dir.xml"), file);
+        assert_eq!(&xml[..], &fs::read(&file).unwrap()[..]);
+    }
+
+    /// Write the activation file named after the bus name and reject
+    /// malformed names.
+    #[test]
+    fn install_dbus_service() {
+        let guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        let services = guard.home().join(".local/share/dbus-1/services");
+        assert_eq!(Some(services.clone()), app_dir.dbus_services_dir());
+
+        let file = app_dir.install_dbus_service("org.example.App", "/usr/bin/app --dbus").unwrap();
+        assert_eq!(services.join("org.example.App.service"), file);
+        assert_eq!("[D-BUS Service]\nName=org.example.App\nExec=/usr/bin/app --dbus\n",
+                   fs::read_to_string(&file).unwrap());
+
+        for name in &["app", "org..App", "org.1example.App", "org/example.App"] {
+            assert!(app_dir.install_dbus_service(name, "/usr/bin/app").is_err());
+        }
+    }
+}
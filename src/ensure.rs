@@ -0,0 +1,364 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature="serde")]
+use serde::{Deserialize, Serialize};
+
+use harden;
+use {AppDir, Fs, StdFs, XdgDir};
+
+impl AppDir {
+    /// Create the resolved `xdg` directory and its parents if missing.
+    pub fn ensure_xdg_dir(&self, xdg: XdgDir) -> io::Result<PathBuf> {
+        self.ensure_xdg_dir_with_fs(&StdFs, xdg)
+    }
+
+    /// Like `ensure_xdg_dir`, but create the directory through `fs`.
+    pub fn ensure_xdg_dir_with_fs<F>(&self, fs: &F, xdg: XdgDir) -> io::Result<PathBuf>
+        where F: Fs + ?Sized
+    {
+        let dir = self.try_xdg_dir(xdg)?;
+        fs.create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// Like `ensure_xdg_dir`, but give the directory itself exactly `mode`
+    /// (e.g. `0o700`), regardless of the umask. Parents are created with the
+    /// default permissions.
+    ///
+    /// On Windows, `mode` is ignored unless the `windows-acl` feature is
+    /// enabled, in which case a mode without group or other bits gives the
+    /// directory a DACL restricted to the current user.
+    ///
+    /// `XdgDir::Bin` is shared with other apps, so it is created with the
+    /// default permissions instead.
+    pub fn ensure_xdg_dir_with_mode(&self, xdg: XdgDir, mode: u32) -> io::Result<PathBuf> {
+        if xdg == XdgDir::Bin {
+            return self.ensure_xdg_dir(xdg);
+        }
+        let dir = self.try_xdg_dir(xdg)?;
+        create_dir_with_mode(&dir, mode)?;
+        Ok(dir)
+    }
+
+    /// Like `ensure_xdg_dir`, but report the permissions the directory ended
+    /// up with after the umask was applied.
+    ///
+    /// If `threshold` is given and the directory grants any permission bit
+    /// outside it, a warning is logged (with the `log` feature); callers can
+    /// also check `EnsuredDir::exceeds` themselves.
+    pub fn ensure_xdg_dir_checked(&self,
+                                  xdg: XdgDir,
+                                  threshold: Option<u32>)
+                                  -> io::Result<EnsuredDir> {
+        let dir = self.ensure_xdg_dir(xdg)?;
+        let ensured = EnsuredDir {
+            mode: StdFs.metadata(&dir)?.mode,
+            path: dir,
+        };
+        if let Some(threshold) = threshold {
+            if ensured.exceeds(threshold) {
+                warn_permissive(&ensured, threshold);
+            }
+        }
+        Ok(ensured)
+    }
+
+    /// Create the cache directory if missing and mark it with a
+    /// `CACHEDIR.TAG`, so backup and archiving tools skip it. An existing
+    /// tag is left alone. Returns the path of the tag.
+    pub fn tag_cache_dir(&self) -> io::Result<PathBuf> {
+        let tag = self.ensure_xdg_dir(XdgDir::Cache)?.join("CACHEDIR.TAG");
+        if !tag.is_file() {
+            fs::write(&tag, CACHEDIR_TAG)?;
+        }
+        Ok(tag)
+    }
+
+    /// Create the app's temp directory if missing.
+    pub fn ensure_temp_dir(&self) -> io::Result<PathBuf> {
+        self.ensure_temp_dir_with_fs(&StdFs)
+    }
+
+    /// Like `ensure_temp_dir`, but create the directory through `fs`.
+    pub fn ensure_temp_dir_with_fs<F>(&self, fs: &F) -> io::Result<PathBuf>
+        where F: Fs + ?Sized
+    {
+        let dir = self.temp_dir();
+        harden::check(self, &dir)?;
+        fs.create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// Create `user_temp_dir` with mode `0o700` if missing, and make sure
+    /// it is a directory owned by the current user rather than one another
+    /// user planted there first.
+    #[cfg(unix)]
+    pub fn ensure_user_temp_dir(&self) -> io::Result<PathBuf> {
+        let dir = self.user_temp_dir();
+        harden::check(self, &dir)?;
+        create_private_dir(&dir)?;
+        Ok(dir)
+    }
+
+    /// Create `user_temp_dir` if missing, private to the user where the
+    /// platform supports it.
+    #[cfg(not(unix))]
+    pub fn ensure_user_temp_dir(&self) -> io::Result<PathBuf> {
+        let dir = self.user_temp_dir();
+        harden::check(self, &dir)?;
+        create_dir_with_mode(&dir, 0o700)?;
+        Ok(dir)
+    }
+
+    /// Like `ensure_temp_dir`, but give the directory exactly `mode`.
+    pub fn ensure_temp_dir_with_mode(&self, mode: u32) -> io::Result<PathBuf> {
+        let dir = self.temp_dir();
+        harden::check(self, &dir)?;
+        create_dir_with_mode(&dir, mode)?;
+        Ok(dir)
+    }
+}
+
+/// The contents of a `CACHEDIR.TAG`; see <https://bford.info/cachedir/>.
+const CACHEDIR_TAG: &str = "Signature: 8a477f597d28d172789f06886806bc55\n\
+                            # This file is a cache directory tag created by s_app_dir.\n\
+                            # For information about cache directory tags, see:\n\
+                            #\thttps://bford.info/cachedir/\n";
+
+/// A directory created or found by `AppDir::ensure_xdg_dir_checked`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+pub struct EnsuredDir {
+    path: PathBuf,
+    mode: Option<u32>,
+}
+
+impl EnsuredDir {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn into_path(self) -> PathBuf {
+        self.path
+    }
+
+    /// The permission bits actually applied. `None` on Windows.
+    pub fn mode(&self) -> Option<u32> {
+        self.mode
+    }
+
+    /// Whether the directory grants any permission bit not in `threshold`.
+    pub fn exceeds(&self, threshold: u32) -> bool {
+        self.mode.is_some_and(|mode| mode & !threshold != 0)
+    }
+}
+
+#[cfg(feature="log")]
+fn warn_permissive(dir: &EnsuredDir, threshold: u32) {
+    warn!("{} has mode {:o}, more permissive than {:o}; check the umask",
+          dir.path.display(),
+          dir.mode.unwrap_or(0),
+          threshold);
+}
+
+#[cfg(not(feature="log"))]
+fn warn_permissive(_: &EnsuredDir, _: u32) {}
+
+/// Create `dir` with `mode`, tolerating another process creating it first.
+///
+/// `mkdir` applies the umask, so the mode is set again explicitly afterwards.
+/// If `dir` already exists it must be a real directory, not a symlink, and
+/// its mode is corrected as well.
+#[cfg(unix)]
+pub fn create_dir_with_mode(dir: &Path, mode: u32) -> io::Result<()> {
+    use std::fs::{DirBuilder, Permissions};
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    if let Some(parent) = dir.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    match DirBuilder::new().mode(mode).create(dir) {
+        Ok(()) => {}
+        Err(ref err) if err.kind() == io::ErrorKind::AlreadyExists => {
+            if !fs::symlink_metadata(dir)?.is_dir() {
+                return Err(io::Error::new(io::ErrorKind::AlreadyExists,
+                                          format!("not a directory: {}", dir.display())));
+            }
+        }
+        Err(err) => return Err(err),
+    }
+    fs::set_permissions(dir, Permissions::from_mode(mode))
+}
+
+#[cfg(all(windows, feature="windows-acl"))]
+pub fn create_dir_with_mode(dir: &Path, mode: u32) -> io::Result<()> {
+    use acl;
+
+    if mode & 0o077 == 0 {
+        acl::create_private_dir(dir)
+    } else {
+        fs::create_dir_all(dir)
+    }
+}
+
+#[cfg(not(any(unix, all(windows, feature="windows-acl"))))]
+pub fn create_dir_with_mode(dir: &Path, _: u32) -> io::Result<()> {
+    fs::create_dir_all(dir)
+}
+
+/// Create `dir` with mode `0o700` and make sure it is a directory owned by
+/// the effective user, since `/tmp` is writable by everyone.
+#[cfg(unix)]
+pub fn create_private_dir(dir: &Path) -> io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    create_dir_with_mode(dir, 0o700)?;
+    if fs::symlink_metadata(dir)?.uid() != unsafe { ::libc::geteuid() } {
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied,
+                                  format!("owned by another user: {}", dir.display())));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    /// Create the directory with exactly the requested mode and accept it existing.
+    #[cfg(unix)]
+    #[test]
+    fn create_dir_with_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let base = env::temp_dir().join("s_app_dir-create_dir_with_mode");
+        let _ = fs::remove_dir_all(&base);
+        let dir = base.join("nested").join("private");
+
+        super::create_dir_with_mode(&dir, 0o700).unwrap();
+        assert_eq!(0o700, fs::metadata(&dir).unwrap().permissions().mode() & 0o777);
+        super::create_dir_with_mode(&dir, 0o750).unwrap();
+        assert_eq!(0o750, fs::metadata(&dir).unwrap().permissions().mode() & 0o777);
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    /// Refuse to treat a symlink as the directory.
+    #[cfg(unix)]
+    #[test]
+    fn create_dir_with_mode_symlink() {
+        use std::os::unix::fs::symlink;
+
+        let base = env::temp_dir().join("s_app_dir-create_dir_with_mode_symlink");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(base.join("target")).unwrap();
+        symlink(base.join("target"), base.join("link")).unwrap();
+
+        assert!(super::create_dir_with_mode(&base.join("link"), 0o700).is_err());
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    /// Leave the mode of the shared bin directory alone.
+    #[cfg(unix)]
+    #[test]
+    fn ensure_bin_with_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        let bin = guard.home().join("bin");
+        fs::create_dir(&bin).unwrap();
+        fs::set_permissions(&bin, fs::Permissions::from_mode(0o755)).unwrap();
+        guard.set("XDG_BIN_HOME", &bin);
+        let app_dir = ::AppDir::new("s_app_dir");
+        assert_eq!(bin, app_dir.ensure_xdg_dir_with_mode(::XdgDir::Bin, 0o700).unwrap());
+        assert_eq!(0o755, fs::metadata(&bin).unwrap().permissions().mode() & 0o777);
+    }
+
+    /// Report the applied mode and whether it exceeds a threshold.
+    #[cfg(unix)]
+    #[test]
+    fn ensure_xdg_dir_checked() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+
+        let ensured = app_dir.ensure_xdg_dir_checked(::XdgDir::Data, Some(0o700)).unwrap();
+        let mode = fs::metadata(ensured.path()).unwrap().permissions().mode() & 0o7777;
+        assert_eq!(Some(mode), ensured.mode());
+        assert_eq!(mode & 0o077 != 0, ensured.exceeds(0o700));
+        assert!(!ensured.exceeds(0o7777));
+    }
+
+    /// Create the directory through the given filesystem.
+    #[test]
+    fn ensure_xdg_dir_with_fs() {
+        use std::cell::RefCell;
+
+        let created = RefCell::new(Vec::new());
+        let fs = ::filesystem::PatchedFs::default().create_dir_all(|path| {
+            created.borrow_mut().push(path.to_path_buf());
+            Ok(())
+        });
+
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        let dir = app_dir.ensure_xdg_dir_with_fs(&fs, ::XdgDir::Cache).unwrap();
+        assert_eq!(app_dir.xdg_dir(::XdgDir::Cache), Some(dir.clone()));
+        drop(fs);
+        assert_eq!(vec![dir.clone()], created.into_inner());
+        assert!(!dir.exists());
+    }
+
+    /// Write the tag once, with the signature first.
+    #[test]
+    fn tag_cache_dir() {
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        let tag = app_dir.tag_cache_dir().unwrap();
+        assert_eq!(app_dir.xdg_dir(::XdgDir::Cache).unwrap().join("CACHEDIR.TAG"), tag);
+        let contents = fs::read_to_string(&tag).unwrap();
+        assert!(contents.starts_with("Signature: 8a477f597d28d172789f06886806bc55"));
+
+        let custom = "Signature: 8a477f597d28d172789f06886806bc55\n";
+        fs::write(&tag, custom).unwrap();
+        app_dir.tag_cache_dir().unwrap();
+        assert_eq!(custom, fs::read_to_string(&tag).unwrap());
+    }
+
+    /// Create a private temp directory and refuse one owned by someone else.
+    #[cfg(unix)]
+    #[test]
+    fn ensure_user_temp_dir() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let app_dir = ::AppDir::new("s_app_dir-ensure_user_temp_dir");
+        let dir = app_dir.ensure_user_temp_dir().unwrap();
+        let mode = fs::metadata(&dir).unwrap().permissions().mode();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(env::temp_dir().join(format!("s_app_dir-ensure_user_temp_dir-{}",
+                                                unsafe { ::libc::geteuid() })),
+                   dir);
+        assert_eq!(0o700, mode & 0o777);
+        assert_ne!(app_dir.temp_dir(), dir);
+        if unsafe { ::libc::geteuid() } == 0 {
+            let root_owned = ::AppDir::new("s_app_dir-ensure_user_temp_dir-other");
+            let dir = root_owned.user_temp_dir();
+            fs::create_dir_all(&dir).unwrap();
+            ::std::os::unix::fs::chown(&dir, Some(1), Some(1)).unwrap();
+            let result = root_owned.ensure_user_temp_dir();
+            let _ = fs::remove_dir_all(&dir);
+            assert!(result.is_err());
+        }
+    }
+
+    /// Create the temp directory.
+    #[test]
+    fn ensure_temp_dir() {
+        let app_dir = ::AppDir::new("s_app_dir-ensure_temp_dir");
+        let dir = app_dir.ensure_temp_dir().unwrap();
+        assert!(dir.is_dir());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
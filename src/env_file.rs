@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+use std::env;
+
+use config::{self, ConfigError};
+use AppDir;
+
+impl AppDir {
+    /// Read the `.env`-style file `.env` from the config search path into a
+    /// map, without touching the process environment.
+    ///
+    /// Each line is `KEY=value`, optionally preceded by `export`. Values may
+    /// be unquoted, with a ` #` comment after them, single-quoted and taken
+    /// literally, or double-quoted with `\n`, `\t`, `\"` and `\\` escapes.
+    /// Blank lines and lines starting with `#` are ignored.
+    pub fn load_env_file(&self) -> Result<BTreeMap<String, String>, ConfigError> {
+        config::load_file(self.find_config(".env")?, parse_env)
+    }
+
+    /// Like `load_env_file`, but also set the variables in the process
+    /// environment. Variables that are already set are left alone unless
+    /// `overwrite` is on.
+    ///
+    /// Changing the environment is only sound while no other thread reads
+    /// or writes it, so call this early in `main`.
+    pub fn apply_env_file(&self, overwrite: bool) -> Result<BTreeMap<String, String>, ConfigError> {
+        let vars = self.load_env_file()?;
+        for (key, value) in &vars {
+            if overwrite || env::var_os(key).is_none() {
+                env::set_var(key, value);
+            }
+        }
+        Ok(vars)
+    }
+}
+
+fn parse_env(contents: &str) -> Result<BTreeMap<String, String>, String> {
+    let mut vars = BTreeMap::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").map(str::trim_start).unwrap_or(line);
+        let (key, value) = line.split_once('=')
+            .ok_or_else(|| format!("line {}: expected KEY=value", i + 1))?;
+        let key = key.trim();
+        let valid = key.chars().next().is_some_and(|c| c == '_' || c.is_ascii_alphabetic()) &&
+                    key.chars().all(|c| c == '_' || c.is_ascii_alphanumeric());
+        if !valid {
+            return Err(format!("line {}: invalid variable name: {}", i + 1, key));
+        }
+        let value = parse_value(value.trim()).map_err(|err| format!("line {}: {}", i + 1, err))?;
+        vars.insert(key.to_string(), value);
+    }
+    Ok(vars)
+}
+
+fn parse_value(value: &str) -> Result<String, &'static str> {
+    if let Some(quoted) = value.strip_prefix('\'') {
+        return match quoted.find('\'') {
+            Some(end) => Ok(quoted[..end].to_string()),
+            None => Err("unterminated single quote"),
+        };
+    }
+    if let Some(quoted) = value.strip_prefix('"') {
+        let mut unescaped = String::new();
+        let mut chars = quoted.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return Ok(unescaped),
+                '\\' => {
+                    match chars.next() {
+                        Some('n') => unescaped.push('\n'),
+                        Some('t') => unescaped.push('\t'),
+                        Some(c) => unescaped.push(c),
+                        None => break,
+                    }
+                }
+                c => unescaped.push(c),
+            }
+        }
+        return Err("unterminated double quote");
+    }
+    let value = match value.find(" #") {
+        Some(comment) => &value[..comment],
+        None => value,
+    };
+    Ok(value.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    /// Parse the supported quoting styles, comments and `export`.
+    #[test]
+    fn parse_env() {
+        let contents = "# comment\n\
+                        \n\
+                        export PLAIN = value # trailing\n\
+                        SINGLE='a \"b\" $c'\n\
+                        DOUBLE=\"line\\nnext \\\"quoted\\\"\"\n\
+                        EMPTY=\n";
+        let vars = super::parse_env(contents).unwrap();
+        assert_eq!(Some("value"), vars.get("PLAIN").map(|s| &s[..]));
+        assert_eq!(Some("a \"b\" $c"), vars.get("SINGLE").map(|s| &s[..]));
+        assert_eq!(Some("line\nnext \"quoted\""), vars.get("DOUBLE").map(|s| &s[..]));
+        assert_eq!(Some(""), vars.get("EMPTY").map(|s| &s[..]));
+        assert!(super::parse_env("1BAD=x").is_err());
+        assert!(super::parse_env("OPEN=\"x").is_err());
+    }
+
+    /// Apply the user's env file without overwriting variables already set.
+    #[test]
+    fn apply_env_file() {
+        use std::env;
+        use std::fs;
+
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        guard.set("S_APP_DIR_ENV_KEPT", "original");
+        guard.remove("S_APP_DIR_ENV_NEW");
+        let app_dir = ::AppDir::new("s_app_dir");
+        let dir = app_dir.ensure_xdg_dir(::XdgDir::Config).unwrap();
+        fs::write(dir.join(".env"), "S_APP_DIR_ENV_KEPT=file\nS_APP_DIR_ENV_NEW=file\n").unwrap();
+
+        let vars = app_dir.apply_env_file(false).unwrap();
+        assert_eq!(2, vars.len());
+        assert_eq!(Some("original".to_string()), env::var("S_APP_DIR_ENV_KEPT").ok());
+        assert_eq!(Some("file".to_string()), env::var("S_APP_DIR_ENV_NEW").ok());
+    }
+}
@@ -0,0 +1,222 @@
+use std::env;
+use std::error;
+use std::ffi::OsString;
+use std::fmt::{self, Display, Formatter};
+use std::path::{self, PathBuf};
+
+use {xdg_base_dir, XdgDir};
+
+/// Errors from expanding a user-supplied path.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ExpandError {
+    /// The path refers to an environment variable that is not set.
+    NotPresent(String),
+    /// The path contains an unterminated `${`.
+    Unterminated(String),
+    /// The home directory could not be resolved.
+    NoHome,
+    /// The user named in `~user` does not exist.
+    UnknownUser(String),
+}
+
+impl Display for ExpandError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            ExpandError::NotPresent(ref name) => {
+                write!(f, "environment variable not set: {}", name)
+            }
+            ExpandError::Unterminated(ref path) => write!(f, "unterminated `${{` in {}", path),
+            ExpandError::NoHome => write!(f, "cannot resolve the home directory"),
+            ExpandError::UnknownUser(ref user) => write!(f, "unknown user: {}", user),
+        }
+    }
+}
+
+impl error::Error for ExpandError {}
+
+/// Expand `$VAR` and `${VAR}` (and `%VAR%` on Windows) in `path`.
+///
+/// Unset `XDG_*_HOME` variables expand to the same defaults the rest of the
+/// crate falls back to, so `$XDG_DATA_HOME/foo` works without the variable.
+pub fn expand_path(path: &str) -> Result<PathBuf, ExpandError> {
+    let mut expanded = OsString::new();
+    let mut rest = path;
+    while let Some(i) = rest.find(is_var_start) {
+        expanded.push(&rest[..i]);
+        let (name, len) = match var_name(&rest[i..]) {
+            Some(found) => found,
+            None if rest[i..].starts_with("${") => {
+                return Err(ExpandError::Unterminated(path.to_string()))
+            }
+            None => {
+                expanded.push(&rest[i..i + 1]);
+                rest = &rest[i + 1..];
+                continue;
+            }
+        };
+        expanded.push(lookup(name)?);
+        rest = &rest[i + len..];
+    }
+    expanded.push(rest);
+    Ok(PathBuf::from(expanded))
+}
+
+/// Expand a leading `~` or `~user` (Unix only) in `path` to a home directory.
+pub fn expand_tilde(path: &str) -> Result<PathBuf, ExpandError> {
+    if !path.starts_with('~') {
+        return Ok(PathBuf::from(path));
+    }
+    let (user, rest) = match path.find(path::is_separator) {
+        Some(i) => (&path[1..i], &path[i + 1..]),
+        None => (&path[1..], ""),
+    };
+    let home = if user.is_empty() {
+        env::home_dir().ok_or(ExpandError::NoHome)?
+    } else {
+        user_home(user)?
+    };
+    Ok(if rest.is_empty() { home } else { home.join(rest) })
+}
+
+#[cfg(unix)]
+fn user_home(user: &str) -> Result<PathBuf, ExpandError> {
+    use libc;
+    use std::ffi::{CStr, CString, OsStr};
+    use std::mem;
+    use std::os::unix::ffi::OsStrExt;
+    use std::ptr;
+
+    let unknown = || ExpandError::UnknownUser(user.to_string());
+    let name = CString::new(user).map_err(|_| unknown())?;
+    let mut buf = vec![0 as libc::c_char; 1024];
+    let mut pwd: libc::passwd = unsafe { mem::zeroed() };
+    let mut result = ptr::null_mut();
+    loop {
+        let ret = unsafe {
+            libc::getpwnam_r(name.as_ptr(), &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result)
+        };
+        if ret != libc::ERANGE {
+            break;
+        }
+        let len = buf.len() * 2;
+        buf.resize(len, 0);
+    }
+    if result.is_null() || pwd.pw_dir.is_null() {
+        return Err(unknown());
+    }
+    let dir = unsafe { CStr::from_ptr(pwd.pw_dir) };
+    Ok(PathBuf::from(OsStr::from_bytes(dir.to_bytes())))
+}
+
+#[cfg(not(unix))]
+fn user_home(user: &str) -> Result<PathBuf, ExpandError> {
+    Err(ExpandError::UnknownUser(user.to_string()))
+}
+
+#[cfg(not(windows))]
+fn is_var_start(c: char) -> bool {
+    c == '$'
+}
+
+#[cfg(windows)]
+fn is_var_start(c: char) -> bool {
+    c == '$' || c == '%'
+}
+
+/// The variable name at the start of `s` and the length of its reference.
+fn var_name(s: &str) -> Option<(&str, usize)> {
+    if s.starts_with("${") {
+        return s.find('}').map(|end| (&s[2..end], end + 1));
+    }
+    if let Some(quoted) = s.strip_prefix('%') {
+        return quoted.find('%').and_then(|end| {
+            if end == 0 {
+                None
+            } else {
+                Some((&quoted[..end], end + 2))
+            }
+        });
+    }
+    let first = s[1..].chars().next();
+    if !first.is_some_and(|c| c == '_' || c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let end = s[1..].find(|c: char| c != '_' && !c.is_ascii_alphanumeric()).unwrap_or(s.len() - 1);
+    Some((&s[1..end + 1], end + 1))
+}
+
+fn lookup(name: &str) -> Result<OsString, ExpandError> {
+    if let Some(value) = ::env_var_os(name) {
+        return Ok(value);
+    }
+    let xdg = match name {
+        "XDG_DATA_HOME" => Some(XdgDir::Data),
+        "XDG_CONFIG_HOME" => Some(XdgDir::Config),
+        "XDG_CACHE_HOME" => Some(XdgDir::Cache),
+        "XDG_BIN_HOME" => Some(XdgDir::Bin),
+        _ => None,
+    };
+    xdg.and_then(xdg_base_dir)
+        .map(|dir| dir.into_os_string())
+        .ok_or_else(|| ExpandError::NotPresent(name.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    /// Expand both `$VAR` and `${VAR}` and keep a lone `$` as is.
+    #[test]
+    fn expand_path() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        guard.set("S_APP_DIR_EXPAND_HOST", "example");
+        let value = ::expand_path("/srv/$S_APP_DIR_EXPAND_HOST/${S_APP_DIR_EXPAND_HOST}.db$");
+        assert_eq!(Ok(PathBuf::from("/srv/example/example.db$")), value);
+    }
+
+    /// Return `ExpandError::NotPresent` for an unset variable.
+    #[test]
+    fn expand_path_not_present() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        guard.remove("S_APP_DIR_EXPAND_UNSET");
+        let value = ::expand_path("$S_APP_DIR_EXPAND_UNSET/foo");
+        assert_eq!(Err(::ExpandError::NotPresent("S_APP_DIR_EXPAND_UNSET".to_string())),
+                   value);
+    }
+
+    /// Return `ExpandError::Unterminated` for a missing `}`.
+    #[test]
+    fn expand_path_unterminated() {
+        let value = ::expand_path("${HOME/foo");
+        assert_eq!(Err(::ExpandError::Unterminated("${HOME/foo".to_string())), value);
+    }
+
+    /// Expand a leading `~` to the home directory and leave other paths alone.
+    #[test]
+    fn expand_tilde() {
+        let guard = ::TempEnvGuard::new().unwrap();
+        assert_eq!(Ok(guard.home().join("projects")), ::expand_tilde("~/projects"));
+        assert_eq!(Ok(PathBuf::from("a/~/b")), ::expand_tilde("a/~/b"));
+    }
+
+    /// Expand `~user` through the password database.
+    #[cfg(unix)]
+    #[test]
+    fn expand_tilde_user() {
+        let value = ::expand_tilde("~root/projects").unwrap();
+        assert!(value.is_absolute());
+        assert!(value.ends_with("projects"));
+        assert_eq!(Err(::ExpandError::UnknownUser("no such user".to_string())),
+                   ::expand_tilde("~no such user/projects"));
+    }
+
+    /// Expand `%VAR%` on Windows.
+    #[cfg(windows)]
+    #[test]
+    fn expand_path_percent() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        guard.set("S_APP_DIR_EXPAND_DIR", "C:\\data");
+        let value = ::expand_path("%S_APP_DIR_EXPAND_DIR%\\foo 100%");
+        assert_eq!(Ok(PathBuf::from("C:\\data\\foo 100%")), value);
+    }
+}
@@ -0,0 +1,149 @@
+//! The C interface, with the `ffi` feature. Build the C library with
+//! `cargo rustc --lib --release --features ffi --crate-type cdylib`.
+//!
+//! Each `s_app_dir_<kind>_dir(app_name, buf, len)` resolves a directory of
+//! the NUL-terminated UTF-8 `app_name` and writes it to `buf` as a
+//! NUL-terminated UTF-8 string, like `snprintf`: it returns the length of
+//! the path without the NUL, and only writes if that is less than `len`, so
+//! callers can pass a null `buf` to size their buffer. It returns -1 if an
+//! argument is invalid or the directory cannot be resolved. On Windows, the
+//! `_w` variants take and write NUL-terminated UTF-16 instead.
+
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::path::PathBuf;
+use std::ptr;
+
+use {AppDir, XdgDir};
+
+/// Resolve `dir` for the app named by the C string `app_name`.
+unsafe fn resolve<F>(app_name: *const c_char, dir: F) -> Option<PathBuf>
+    where F: FnOnce(&AppDir) -> Option<PathBuf>
+{
+    if app_name.is_null() {
+        return None;
+    }
+    let app_name = CStr::from_ptr(app_name).to_str().ok()?;
+    dir(&AppDir::new(app_name))
+}
+
+/// Copy `units` and a NUL terminator to `buf` if they fit in `len` units.
+unsafe fn write_units<T>(units: &[T], nul: T, buf: *mut T, len: usize) -> isize
+    where T: Copy
+{
+    if !buf.is_null() && units.len() < len {
+        ptr::copy_nonoverlapping(units.as_ptr(), buf, units.len());
+        *buf.add(units.len()) = nul;
+    }
+    units.len() as isize
+}
+
+unsafe fn write_utf8(path: Option<PathBuf>, buf: *mut c_char, len: usize) -> isize {
+    match path.as_ref().and_then(|path| path.to_str()) {
+        Some(path) => write_units(path.as_bytes(), 0, buf as *mut u8, len),
+        None => -1,
+    }
+}
+
+#[cfg(windows)]
+unsafe fn resolve_wide<F>(app_name: *const u16, dir: F) -> Option<PathBuf>
+    where F: FnOnce(&AppDir) -> Option<PathBuf>
+{
+    if app_name.is_null() {
+        return None;
+    }
+    let len = (0..).take_while(|&i| *app_name.add(i) != 0).count();
+    let app_name = String::from_utf16(::std::slice::from_raw_parts(app_name, len)).ok()?;
+    dir(&AppDir::new(&app_name))
+}
+
+#[cfg(windows)]
+unsafe fn write_wide(path: Option<PathBuf>, buf: *mut u16, len: usize) -> isize {
+    use std::os::windows::ffi::OsStrExt;
+
+    match path {
+        Some(path) => write_units(&path.as_os_str().encode_wide().collect::<Vec<_>>(), 0, buf, len),
+        None => -1,
+    }
+}
+
+macro_rules! ffi_dir {
+    ($name:ident, $wide:ident, $what:expr, $dir:expr) => {
+        #[doc = concat!("Write the ", $what, " of `app_name` to `buf`; see the module docs.")]
+        ///
+        /// # Safety
+        ///
+        /// `app_name` must be null or a NUL-terminated string, and `buf`
+        /// null or valid for writes of `len` bytes.
+        #[no_mangle]
+        pub unsafe extern "C" fn $name(app_name: *const c_char, buf: *mut c_char, len: usize)
+                                       -> isize {
+            write_utf8(resolve(app_name, $dir), buf, len)
+        }
+
+        #[doc = concat!("Like `", stringify!($name), "`, but in UTF-16.")]
+        ///
+        /// # Safety
+        ///
+        /// `app_name` must be null or a NUL-terminated UTF-16 string, and
+        /// `buf` null or valid for writes of `len` units.
+        #[cfg(windows)]
+        #[no_mangle]
+        pub unsafe extern "C" fn $wide(app_name: *const u16, buf: *mut u16, len: usize) -> isize {
+            write_wide(resolve_wide(app_name, $dir), buf, len)
+        }
+    };
+}
+
+ffi_dir!(s_app_dir_data_dir,
+         s_app_dir_data_dir_w,
+         "data directory",
+         |app_dir: &AppDir| app_dir.xdg_dir(XdgDir::Data));
+ffi_dir!(s_app_dir_config_dir,
+         s_app_dir_config_dir_w,
+         "config directory",
+         |app_dir: &AppDir| app_dir.xdg_dir(XdgDir::Config));
+ffi_dir!(s_app_dir_cache_dir,
+         s_app_dir_cache_dir_w,
+         "cache directory",
+         |app_dir: &AppDir| app_dir.xdg_dir(XdgDir::Cache));
+ffi_dir!(s_app_dir_bin_dir,
+         s_app_dir_bin_dir_w,
+         "bin directory",
+         |app_dir: &AppDir| app_dir.xdg_dir(XdgDir::Bin));
+ffi_dir!(s_app_dir_temp_dir,
+         s_app_dir_temp_dir_w,
+         "temp directory",
+         |app_dir: &AppDir| Some(app_dir.temp_dir()));
+
+#[cfg(test)]
+mod tests {
+    use std::os::raw::c_char;
+    use std::ptr;
+
+    /// Report the needed length, write only when it fits, and reject a
+    /// null app name.
+    #[test]
+    fn config_dir() {
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let expect = ::AppDir::new("s_app_dir").xdg_dir(::XdgDir::Config).unwrap();
+        let expect = expect.to_str().unwrap();
+        let app_name = b"s_app_dir\0".as_ptr() as *const c_char;
+
+        unsafe {
+            let len = super::s_app_dir_config_dir(app_name, ptr::null_mut(), 0);
+            assert_eq!(expect.len() as isize, len);
+
+            let mut small = [1 as c_char; 4];
+            assert_eq!(len, super::s_app_dir_config_dir(app_name, small.as_mut_ptr(), 4));
+            assert_eq!([1; 4], small);
+
+            let mut buf = vec![1 as c_char; expect.len() + 1];
+            assert_eq!(len, super::s_app_dir_config_dir(app_name, buf.as_mut_ptr(), buf.len()));
+            let bytes = buf.iter().map(|&c| c as u8).collect::<Vec<_>>();
+            assert_eq!(format!("{}\0", expect).as_bytes(), &bytes[..]);
+
+            assert_eq!(-1, super::s_app_dir_config_dir(ptr::null(), ptr::null_mut(), 0));
+        }
+    }
+}
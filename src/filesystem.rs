@@ -0,0 +1,184 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// The filesystem operations performed by helpers such as
+/// `AppDir::ensure_xdg_dir_with_fs` and `AppDir::clean_cache_with_fs`, so
+/// they can be tested against a fake.
+pub trait Fs {
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+
+    /// Remove the empty directory `path`.
+    fn remove_dir(&self, path: &Path) -> io::Result<()>;
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    /// The paths of the entries in `path`, in no particular order.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+
+    /// Like `fs::metadata`, following symlinks.
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata>;
+
+    /// Like `fs::symlink_metadata`, describing a symlink itself.
+    fn symlink_metadata(&self, path: &Path) -> io::Result<FsMetadata>;
+}
+
+/// The parts of `fs::Metadata` the helpers rely on.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FsMetadata {
+    pub is_dir: bool,
+    pub len: u64,
+    pub modified: Option<SystemTime>,
+    /// Permission bits, on Unix.
+    pub mode: Option<u32>,
+}
+
+impl From<fs::Metadata> for FsMetadata {
+    fn from(metadata: fs::Metadata) -> FsMetadata {
+        FsMetadata {
+            is_dir: metadata.is_dir(),
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+            mode: mode(&metadata),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn mode(metadata: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+
+    Some(metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn mode(_: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+/// The real filesystem, through `std::fs`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct StdFs;
+
+impl Fs for StdFs {
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir_all(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::read_dir(path)?.map(|entry| entry.map(|entry| entry.path())).collect()
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        fs::metadata(path).map(FsMetadata::from)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        fs::symlink_metadata(path).map(FsMetadata::from)
+    }
+}
+
+#[cfg(test)]
+type PathHook<'a, T> = Box<dyn Fn(&Path) -> io::Result<T> + 'a>;
+
+#[cfg(test)]
+type RenameHook<'a> = Box<dyn Fn(&Path, &Path) -> io::Result<()> + 'a>;
+
+/// `StdFs` with some calls replaced, for tests that fake only part of the
+/// filesystem. The calls not replaced go to `StdFs`.
+#[cfg(test)]
+#[derive(Default)]
+pub struct PatchedFs<'a> {
+    create_dir_all: Option<PathHook<'a, ()>>,
+    rename: Option<RenameHook<'a>>,
+    symlink_metadata: Option<PathHook<'a, FsMetadata>>,
+}
+
+#[cfg(test)]
+impl<'a> PatchedFs<'a> {
+    pub fn create_dir_all<F>(mut self, f: F) -> PatchedFs<'a>
+        where F: Fn(&Path) -> io::Result<()> + 'a
+    {
+        self.create_dir_all = Some(Box::new(f));
+        self
+    }
+
+    pub fn rename<F>(mut self, f: F) -> PatchedFs<'a>
+        where F: Fn(&Path, &Path) -> io::Result<()> + 'a
+    {
+        self.rename = Some(Box::new(f));
+        self
+    }
+
+    pub fn symlink_metadata<F>(mut self, f: F) -> PatchedFs<'a>
+        where F: Fn(&Path) -> io::Result<FsMetadata> + 'a
+    {
+        self.symlink_metadata = Some(Box::new(f));
+        self
+    }
+}
+
+#[cfg(test)]
+impl<'a> Fs for PatchedFs<'a> {
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        match self.create_dir_all {
+            Some(ref f) => f(path),
+            None => StdFs.create_dir_all(path),
+        }
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        match self.rename {
+            Some(ref f) => f(from, to),
+            None => StdFs.rename(from, to),
+        }
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        StdFs.remove_file(path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        StdFs.remove_dir(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        StdFs.remove_dir_all(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        StdFs.read_dir(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        StdFs.metadata(path)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        match self.symlink_metadata {
+            Some(ref f) => f(path),
+            None => StdFs.symlink_metadata(path),
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use {AppDir, ResolveError};
+
+impl AppDir {
+    /// The directory for fonts installed for the current user only, shared
+    /// by all apps: `$XDG_DATA_HOME/fonts`, `~/Library/Fonts` on macOS, or
+    /// `%LOCALAPPDATA%\Microsoft\Windows\Fonts` on Windows.
+    pub fn user_font_dir(&self) -> Option<PathBuf> {
+        self.resolve_font_dir().ok()
+    }
+
+    /// Copy the font file `font` into `user_font_dir()` and return the
+    /// installed path.
+    ///
+    /// On Linux, pass `refresh_cache` to run `fc-cache` so running apps see
+    /// the font right away; it is ignored elsewhere. On Windows the font is
+    /// only loaded automatically once it is also registered under
+    /// `HKCU\Software\Microsoft\Windows NT\CurrentVersion\Fonts`.
+    pub fn install_font<P>(&self, font: P, refresh_cache: bool) -> io::Result<PathBuf>
+        where P: AsRef<Path>
+    {
+        let font = font.as_ref();
+        let name = font.file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput,
+                               format!("not a font file name: {}", font.display()))
+            })?;
+        let dir = self.resolve_font_dir()?;
+        let installed = self.join_file(&dir, name)?;
+        fs::create_dir_all(&dir)?;
+        fs::copy(font, &installed)?;
+        if refresh_cache {
+            refresh_font_cache(&dir)?;
+        }
+        Ok(installed)
+    }
+
+    #[cfg(not(any(windows, target_os = "macos")))]
+    fn resolve_font_dir(&self) -> Result<PathBuf, ResolveError> {
+        use {resolve_base_dir, Env, XdgDir};
+
+        let data = resolve_base_dir(XdgDir::Data, self.options(), &Env::capture())?;
+        Ok(self.finish(data.join("fonts")))
+    }
+
+    #[cfg(target_os = "macos")]
+    fn resolve_font_dir(&self) -> Result<PathBuf, ResolveError> {
+        use Env;
+
+        let env = Env::capture();
+        let home = env.home.clone().ok_or_else(|| ResolveError::NoHome(env.report()))?;
+        Ok(self.finish(home.join("Library").join("Fonts")))
+    }
+
+    #[cfg(windows)]
+    fn resolve_font_dir(&self) -> Result<PathBuf, ResolveError> {
+        use Env;
+
+        let env = Env::capture();
+        let local = env.var_os("LOCALAPPDATA").ok_or_else(|| env.not_set("LOCALAPPDATA"))?;
+        Ok(self.finish(PathBuf::from(local).join("Microsoft").join("Windows").join("Fonts")))
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn refresh_font_cache(dir: &Path) -> io::Result<()> {
+    use desktop;
+    use std::process::Command;
+
+    desktop::run(Command::new("fc-cache").arg(dir))
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn refresh_font_cache(_: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    /// Copy the font into the user font directory.
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn install_font() {
+        use std::fs;
+
+        let guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        let fonts = guard.home().join(".local/share/fonts");
+        assert_eq!(Some(fonts.clone()), app_dir.user_font_dir());
+
+        let font = guard.home().join("Example.ttf");
+        fs::write(&font, b"font").unwrap();
+        let installed = app_dir.install_font(&font, false).unwrap();
+        assert_eq!(fonts.join("Example.ttf"), installed);
+        assert_eq!(b"font".to_vec(), fs::read(&installed).unwrap());
+    }
+}
@@ -0,0 +1,177 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use {AppDir, XdgDir};
+
+impl AppDir {
+    /// The files in the resolved `xdg` directory matching `pattern`, sorted
+    /// by path.
+    ///
+    /// `pattern` is a glob: `*` matches any run of characters and `?` any
+    /// one character, neither crossing `/`; `[abc]`, `[a-z]` and `[!abc]`
+    /// match one character of a set; and a `**` component matches any number
+    /// of directories. A pattern without `/` is matched against file names,
+    /// one with `/` against paths relative to the directory, always with `/`
+    /// as the separator.
+    ///
+    /// Subdirectories are only descended into if `recursive` is set, and
+    /// symlinks to directories never are. A missing directory yields no
+    /// files.
+    pub fn list(&self, xdg: XdgDir, pattern: &str, recursive: bool) -> io::Result<Vec<PathBuf>> {
+        glob(&self.try_xdg_dir(xdg)?, pattern, recursive)
+    }
+}
+
+/// The files under `dir` matching `pattern`; see `AppDir::list`.
+pub fn glob(dir: &Path, pattern: &str, recursive: bool) -> io::Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    walk(dir, "", pattern, recursive, &mut found)?;
+    found.sort();
+    Ok(found.into_iter().map(|(_, path)| path).collect())
+}
+
+fn walk(dir: &Path,
+        prefix: &str,
+        pattern: &str,
+        recursive: bool,
+        found: &mut Vec<(String, PathBuf)>)
+        -> io::Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    for entry in entries {
+        let entry = entry?;
+        let name = match entry.file_name().into_string() {
+            Ok(name) => name,
+            Err(_) => continue,
+        };
+        let relative = format!("{}{}", prefix, name);
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            if recursive {
+                walk(&entry.path(), &format!("{}/", relative), pattern, recursive, found)?;
+            }
+            continue;
+        }
+        if file_type.is_symlink() && !entry.path().is_file() {
+            continue;
+        }
+        let subject = if pattern.contains('/') { &relative } else { &name };
+        if matches(pattern, subject) {
+            found.push((relative, entry.path()));
+        }
+    }
+    Ok(())
+}
+
+/// Whether the `/`-separated `path` matches the glob `pattern`.
+pub fn matches(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.split('/').collect::<Vec<_>>();
+    let path = path.split('/').collect::<Vec<_>>();
+    match_components(&pattern, &path)
+}
+
+fn match_components(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| match_components(rest, &path[skip..])),
+        Some((first, rest)) => {
+            path.split_first().is_some_and(|(component, path_rest)| {
+                match_component(&first.chars().collect::<Vec<_>>(),
+                                &component.chars().collect::<Vec<_>>()) &&
+                match_components(rest, path_rest)
+            })
+        }
+    }
+}
+
+fn match_component(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((&'*', rest)) => (0..=name.len()).any(|skip| match_component(rest, &name[skip..])),
+        Some((&'?', rest)) => !name.is_empty() && match_component(rest, &name[1..]),
+        Some((&'[', rest)) => {
+            match (name.split_first(), class(rest)) {
+                (Some((&c, name_rest)), Some((matched, len))) => {
+                    matched(c) && match_component(&rest[len..], name_rest)
+                }
+                // An unterminated class is a literal `[`.
+                (Some((&'[', name_rest)), None) => match_component(rest, name_rest),
+                _ => false,
+            }
+        }
+        Some((&c, rest)) => name.first() == Some(&c) && match_component(rest, &name[1..]),
+    }
+}
+
+/// Parse the character class after a `[`, returning a matcher and the
+/// length of the class including the closing `]`.
+fn class(pattern: &[char]) -> Option<(impl Fn(char) -> bool + '_, usize)> {
+    let negated = pattern.first() == Some(&'!');
+    let start = if negated { 1 } else { 0 };
+    // A `]` right after the `[` or `[!` is part of the set.
+    let end = start + 1 + pattern.get(start + 1..)?.iter().position(|&c| c == ']')?;
+    let set = &pattern[start..end];
+    let matched = move |c: char| {
+        let mut i = 0;
+        let mut found = false;
+        while i < set.len() {
+            if i + 2 < set.len() && set[i + 1] == '-' {
+                found |= set[i] <= c && c <= set[i + 2];
+                i += 3;
+            } else {
+                found |= set[i] == c;
+                i += 1;
+            }
+        }
+        found != negated
+    };
+    Some((matched, end + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    /// Match wildcards, classes and `**` without crossing separators.
+    #[test]
+    fn matches() {
+        use super::matches;
+
+        assert!(matches("*.toml", "app.toml"));
+        assert!(!matches("*.toml", "conf.d/app.toml"));
+        assert!(matches("conf.d/*.toml", "conf.d/app.toml"));
+        assert!(matches("**/*.toml", "app.toml"));
+        assert!(matches("**/*.toml", "a/b/app.toml"));
+        assert!(matches("a/**/c", "a/c"));
+        assert!(matches("log-??.txt", "log-01.txt"));
+        assert!(!matches("log-??.txt", "log-1.txt"));
+        assert!(matches("[a-c]x[!0-9]", "bxy"));
+        assert!(!matches("[a-c]x[!0-9]", "bx1"));
+        assert!(matches("[]]", "]"));
+        assert!(matches("[x", "[x"));
+    }
+
+    /// List matching files in sorted order, descending only when recursive.
+    #[test]
+    fn list() {
+        use std::fs;
+
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        assert!(app_dir.list(::XdgDir::Config, "*", true).unwrap().is_empty());
+
+        let dir = app_dir.ensure_xdg_dir(::XdgDir::Config).unwrap();
+        fs::create_dir_all(dir.join("conf.d")).unwrap();
+        for name in &["b.toml", "a.toml", "notes.txt", "conf.d/c.toml"] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+        assert_eq!(vec![dir.join("a.toml"), dir.join("b.toml")],
+                   app_dir.list(::XdgDir::Config, "*.toml", false).unwrap());
+        assert_eq!(vec![dir.join("a.toml"), dir.join("b.toml"), dir.join("conf.d/c.toml")],
+                   app_dir.list(::XdgDir::Config, "*.toml", true).unwrap());
+        assert_eq!(vec![dir.join("conf.d/c.toml")],
+                   app_dir.list(::XdgDir::Config, "conf.d/*.toml", true).unwrap());
+    }
+}
@@ -0,0 +1,66 @@
+use std::sync::OnceLock;
+
+use AppDir;
+
+static GLOBAL: OnceLock<AppDir> = OnceLock::new();
+
+/// Set the process-wide `AppDir` returned by `global`.
+///
+/// Only the first call succeeds; later ones get their `app_dir` back, boxed.
+pub fn init_global(app_dir: AppDir) -> Result<&'static AppDir, Box<AppDir>> {
+    GLOBAL.set(app_dir).map_err(Box::new)?;
+    Ok(global().unwrap())
+}
+
+/// The process-wide `AppDir`, if `init_global` has been called.
+pub fn global() -> Option<&'static AppDir> {
+    GLOBAL.get()
+}
+
+/// A `&'static AppDir` for `app_name`, created on first use.
+///
+/// Each invocation site has its own instance, so use it from one accessor
+/// function rather than repeating it across a codebase.
+///
+/// ```
+/// #[macro_use]
+/// extern crate s_app_dir;
+///
+/// fn app_dir() -> &'static s_app_dir::AppDir {
+///     static_app_dir!("foo-bar-app")
+/// }
+///
+/// fn main() {
+///     assert_eq!("foo-bar-app", app_dir().app_name());
+/// }
+/// ```
+#[macro_export]
+macro_rules! static_app_dir {
+    ($app_name:expr) => {{
+        static APP_DIR: ::std::sync::OnceLock<$crate::AppDir> = ::std::sync::OnceLock::new();
+        APP_DIR.get_or_init(|| $crate::AppDir::new($app_name))
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    /// Keep the first global app dir.
+    #[test]
+    fn init_global() {
+        assert_eq!(None, super::global());
+        let first = super::init_global(::AppDir::new("first")).unwrap();
+        let second = ::AppDir::new("second");
+        assert_eq!(Err(Box::new(second.clone())), super::init_global(second));
+        assert_eq!(Some(first), super::global());
+    }
+
+    /// Create the app dir once per invocation site.
+    #[test]
+    fn static_app_dir() {
+        fn app_dir() -> &'static ::AppDir {
+            static_app_dir!("s_app_dir")
+        }
+        assert!(::std::ptr::eq(app_dir(), app_dir()));
+        assert_eq!("s_app_dir", app_dir().app_name());
+    }
+}
@@ -0,0 +1,125 @@
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use AppDir;
+
+/// Why a directory in a shared location such as `/tmp` is unsafe to use,
+/// found when `AppDirBuilder::check_shared_dirs` is enabled.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum InsecureDir {
+    /// The parent is a symlink owned by another user, who could point it
+    /// elsewhere.
+    ForeignSymlink(PathBuf),
+    /// The parent is writable by everyone but lacks the sticky bit, so
+    /// anyone may replace what is in it.
+    NotSticky(PathBuf),
+    /// The directory already exists and belongs to another user, who may
+    /// have created it first to read or plant files.
+    ForeignOwner(PathBuf),
+}
+
+impl InsecureDir {
+    /// The offending directory.
+    pub fn path(&self) -> &Path {
+        match *self {
+            InsecureDir::ForeignSymlink(ref path) |
+            InsecureDir::NotSticky(ref path) |
+            InsecureDir::ForeignOwner(ref path) => path,
+        }
+    }
+}
+
+impl Display for InsecureDir {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let path = self.path().display();
+        match *self {
+            InsecureDir::ForeignSymlink(_) => {
+                write!(f, "{} is a symlink owned by another user", path)
+            }
+            InsecureDir::NotSticky(_) => {
+                write!(f, "{} is world-writable without the sticky bit", path)
+            }
+            InsecureDir::ForeignOwner(_) => write!(f, "{} is owned by another user", path),
+        }
+    }
+}
+
+impl error::Error for InsecureDir {}
+
+impl From<InsecureDir> for io::Error {
+    fn from(err: InsecureDir) -> io::Error {
+        io::Error::new(io::ErrorKind::PermissionDenied, err)
+    }
+}
+
+/// Check `dir` and its parent if `app_dir` checks shared directories.
+pub fn check(app_dir: &AppDir, dir: &Path) -> io::Result<()> {
+    if !app_dir.check_shared_dirs {
+        return Ok(());
+    }
+    insecure(dir).map_or(Ok(()), |err| Err(err.into()))
+}
+
+#[cfg(unix)]
+fn insecure(dir: &Path) -> Option<InsecureDir> {
+    use std::fs;
+    use std::os::unix::fs::MetadataExt;
+
+    let trusted = |uid: u32| uid == 0 || uid == unsafe { ::libc::geteuid() };
+    if let Some(parent) = dir.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        if let Ok(link) = fs::symlink_metadata(parent) {
+            if link.file_type().is_symlink() && !trusted(link.uid()) {
+                return Some(InsecureDir::ForeignSymlink(parent.to_path_buf()));
+            }
+        }
+        if let Ok(meta) = fs::metadata(parent) {
+            if meta.mode() & 0o002 != 0 && meta.mode() & 0o1000 == 0 {
+                return Some(InsecureDir::NotSticky(parent.to_path_buf()));
+            }
+        }
+    }
+    match fs::symlink_metadata(dir) {
+        Ok(ref meta) if !trusted(meta.uid()) => Some(InsecureDir::ForeignOwner(dir.to_path_buf())),
+        _ => None,
+    }
+}
+
+/// Windows temp directories are per-user, so there is nothing to check.
+#[cfg(not(unix))]
+fn insecure(_: &Path) -> Option<InsecureDir> {
+    None
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    use InsecureDir;
+
+    /// Reject world-writable parents without the sticky bit, and accept
+    /// them with it.
+    #[test]
+    fn not_sticky() {
+        let guard = ::TempEnvGuard::new().unwrap();
+        let shared = guard.home().join("shared");
+        fs::create_dir(&shared).unwrap();
+        fs::set_permissions(&shared, fs::Permissions::from_mode(0o777)).unwrap();
+        let dir = shared.join("s_app_dir");
+        assert_eq!(Some(InsecureDir::NotSticky(shared.clone())), super::insecure(&dir));
+
+        fs::set_permissions(&shared, fs::Permissions::from_mode(0o1777)).unwrap();
+        assert_eq!(None, super::insecure(&dir));
+        let app_dir = ::AppDir::builder("s_app_dir").check_shared_dirs(true).build();
+        assert!(super::check(&app_dir, &dir).is_ok());
+        if unsafe { ::libc::geteuid() } == 0 {
+            fs::create_dir(&dir).unwrap();
+            ::std::os::unix::fs::chown(&dir, Some(1), Some(1)).unwrap();
+            let err = super::check(&app_dir, &dir).unwrap_err();
+            assert_eq!(format!("{} is owned by another user", dir.display()), err.to_string());
+            assert!(super::check(&::AppDir::new("s_app_dir"), &dir).is_ok());
+        }
+    }
+}
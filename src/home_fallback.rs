@@ -0,0 +1,112 @@
+use std::path::PathBuf;
+
+use s_app_dir_core::Platform;
+
+#[cfg(feature="serde")]
+use serde::{Deserialize, Serialize};
+
+use writable::can_write;
+use {overrides, AppDir, Env, XdgDir};
+
+/// Where a directory resolved by `AppDir::xdg_dir` came from.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(rename_all = "snake_case"))]
+pub enum Provenance {
+    /// An override, relocation, custom resolver or fallback chain.
+    Custom,
+    /// This XDG variable.
+    Var(String),
+    /// The platform default, under the home directory or `%APPDATA%`.
+    Default,
+    /// The root set with `AppDirBuilder::home_fallback`, standing in for the
+    /// home directory.
+    HomeFallback(HomeIssue),
+}
+
+/// Why the home directory could not be used.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(rename_all = "snake_case"))]
+pub enum HomeIssue {
+    /// It is unknown or does not exist.
+    Missing,
+    /// The process cannot write to it.
+    ReadOnly,
+}
+
+impl AppDir {
+    /// Where the `xdg` directory comes from, or `None` if it cannot be
+    /// resolved. Reports whether the home fallback stood in for the home
+    /// directory, and why.
+    pub fn provenance(&self, xdg: XdgDir) -> Option<Provenance> {
+        let env = Env::capture_for(self);
+        self.resolve_in(xdg, &env).ok()?;
+        if overrides::get(xdg).is_some() || self.relocations.contains_key(&xdg) ||
+           self.resolver.get().is_some() || self.chains.contains_key(&xdg) {
+            return Some(Provenance::Custom);
+        }
+        let key = xdg.kind().var();
+        if let Ok(Some(_)) = s_app_dir_core::var_dir(key, self.options(), &env) {
+            return Some(Provenance::Var(key.to_string()));
+        }
+        Some(issue(self, xdg, &env).map_or(Provenance::Default, Provenance::HomeFallback))
+    }
+}
+
+/// Why `app_dir` should resolve `xdg` under its home fallback instead of the
+/// home directory, if it has one and would otherwise use the home directory.
+pub fn issue(app_dir: &AppDir, xdg: XdgDir, env: &Env) -> Option<HomeIssue> {
+    app_dir.home_fallback.as_ref()?;
+    if Platform::current() != Platform::Unix || app_dir.pure_env ||
+       overrides::get(xdg).is_some() {
+        return None;
+    }
+    match s_app_dir_core::var_dir(xdg.kind().var(), app_dir.options(), env) {
+        Ok(None) => {}
+        _ => return None,
+    }
+    match env.home {
+        Some(ref home) if home.is_dir() && can_write(home) => None,
+        Some(ref home) if home.is_dir() => Some(HomeIssue::ReadOnly),
+        _ => Some(HomeIssue::Missing),
+    }
+}
+
+/// `env` with the home directory replaced by `root`.
+pub fn env(env: &Env, root: PathBuf) -> Env {
+    Env {
+        vars: env.vars.clone(),
+        home: Some(root),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {HomeIssue, Provenance, XdgDir};
+
+    /// Stand in for a missing home directory, and say so.
+    #[cfg(unix)]
+    #[test]
+    fn missing_home() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        let root = guard.home().join("fallback");
+        let app_dir = ::AppDir::builder("s_app_dir").home_fallback(&root).build();
+        assert_eq!(Some(Provenance::Default), app_dir.provenance(XdgDir::Config));
+        assert_eq!(::AppDir::new("s_app_dir").xdg_dir(XdgDir::Config),
+                   app_dir.xdg_dir(XdgDir::Config));
+
+        let missing = guard.home().join("missing");
+        let cache = guard.home().join("cache");
+        guard.set("HOME", &missing);
+        guard.set("XDG_CACHE_HOME", &cache);
+        assert_eq!(Some(root.join(".config/s_app_dir")), app_dir.xdg_dir(XdgDir::Config));
+        assert_eq!(Some(root.join(".local/bin")), app_dir.xdg_dir(XdgDir::Bin));
+        assert_eq!(Some(Provenance::HomeFallback(HomeIssue::Missing)),
+                   app_dir.provenance(XdgDir::Config));
+        assert_eq!(Some(Provenance::Var("XDG_CACHE_HOME".to_string())),
+                   app_dir.provenance(XdgDir::Cache));
+        assert_eq!(Some(Provenance::Default),
+                   ::AppDir::new("s_app_dir").provenance(XdgDir::Config));
+    }
+}
@@ -0,0 +1,116 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use {AppDir, XdgDir};
+
+impl AppDir {
+    /// `hooks` in the config directory, where the user puts scripts the app
+    /// runs on events.
+    pub fn hooks_dir(&self) -> Option<PathBuf> {
+        self.xdg_dir(XdgDir::Config).map(|dir| dir.join("hooks"))
+    }
+
+    /// The hooks to run for `event_name`, in the order to run them.
+    ///
+    /// These are an executable named `event_name` in `hooks_dir()`, as with
+    /// git hooks, followed by every executable in `<event_name>.d`, sorted by
+    /// file name. On Unix a file is executable if it has an execute bit set;
+    /// on Windows if its extension is listed in `PATHEXT`, and the extension
+    /// is ignored when matching `event_name`.
+    ///
+    /// Missing directories yield no hooks rather than an error.
+    pub fn list_hooks(&self, event_name: &str) -> io::Result<Vec<PathBuf>> {
+        let dir = match self.hooks_dir() {
+            Some(dir) => dir,
+            None => return Ok(Vec::new()),
+        };
+        let mut hooks = executables(&dir)?
+            .into_iter()
+            .filter(|path| hook_name(path) == Some(event_name))
+            .collect::<Vec<_>>();
+        hooks.extend(executables(&dir.join(format!("{}.d", event_name)))?);
+        Ok(hooks)
+    }
+}
+
+/// The executable files in `dir`, sorted by file name.
+fn executables(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut files = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if is_executable(&path) {
+            files.push(path);
+        }
+    }
+    files.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+    Ok(files)
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    use std::env;
+
+    let extension = match path.extension().and_then(|ext| ext.to_str()) {
+        Some(extension) => format!(".{}", extension),
+        None => return false,
+    };
+    let pathext = env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string());
+    path.is_file() && pathext.split(';').any(|ext| ext.eq_ignore_ascii_case(&extension))
+}
+
+#[cfg(unix)]
+fn hook_name(path: &Path) -> Option<&str> {
+    path.file_name().and_then(|name| name.to_str())
+}
+
+#[cfg(not(unix))]
+fn hook_name(path: &Path) -> Option<&str> {
+    path.file_stem().and_then(|name| name.to_str())
+}
+
+#[cfg(test)]
+mod tests {
+    /// List the named hook first, then the executables in its `.d` directory
+    /// by name, skipping files without an execute bit.
+    #[cfg(unix)]
+    #[test]
+    fn list_hooks() {
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+        use std::path::Path;
+
+        fn write(path: &Path, mode: u32) {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, b"#!/bin/sh\n").unwrap();
+            fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+        }
+
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        assert!(app_dir.list_hooks("pre-save").unwrap().is_empty());
+
+        let hooks = app_dir.hooks_dir().unwrap();
+        write(&hooks.join("pre-save"), 0o755);
+        write(&hooks.join("post-save"), 0o755);
+        write(&hooks.join("pre-save.d/20-lint"), 0o700);
+        write(&hooks.join("pre-save.d/10-format"), 0o755);
+        write(&hooks.join("pre-save.d/30-disabled"), 0o644);
+        assert_eq!(vec![hooks.join("pre-save"),
+                        hooks.join("pre-save.d/10-format"),
+                        hooks.join("pre-save.d/20-lint")],
+                   app_dir.list_hooks("pre-save").unwrap());
+    }
+}
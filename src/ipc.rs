@@ -0,0 +1,307 @@
+use std::fmt::{self, Display, Formatter};
+use std::io;
+#[cfg(unix)]
+use std::path::Path;
+use std::path::PathBuf;
+
+#[cfg(feature="serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(unix)]
+use ensure::{create_dir_with_mode, create_private_dir};
+#[cfg(unix)]
+use harden;
+use AppDir;
+
+impl AppDir {
+    /// `$XDG_RUNTIME_DIR/app_name`, the per-user directory for sockets and
+    /// other runtime files, or `None` if the variable is unset or relative.
+    pub fn runtime_dir(&self) -> Option<PathBuf> {
+        ::env_var_os("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .map(|dir| self.finish(dir.join(&self.app_name)))
+    }
+
+    /// The path to bind the Unix domain socket `name` to, with its directory
+    /// created with mode `0o700`: `<name>.sock` in `runtime_dir`, or in
+    /// `temp_dir` without one.
+    ///
+    /// If that path does not fit in `sun_path`, as with long user names or
+    /// deep container paths, a short path named by its hash in a private
+    /// per-user directory under `/tmp` is returned instead. The variant
+    /// tells which was chosen.
+    #[cfg(unix)]
+    pub fn socket_path(&self, name: &str) -> io::Result<SocketPath> {
+        let runtime = self.runtime_dir();
+        let in_runtime = runtime.is_some();
+        let dir = runtime.unwrap_or_else(|| self.temp_dir());
+        let path = self.join_file(dir, &format!("{}.sock", name))?;
+        if path.as_os_str().len() < SUN_PATH_LEN {
+            harden::check(self, path.parent().expect("joined a file name"))?;
+            create_dir_with_mode(path.parent().expect("joined a file name"), 0o700)?;
+            return Ok(if in_runtime { SocketPath::Runtime(path) } else { SocketPath::Temp(path) });
+        }
+        let dir = short_socket_dir();
+        harden::check(self, &dir)?;
+        create_private_dir(&dir)?;
+        let hash = ::fnv1a(path.as_os_str().to_string_lossy().as_bytes());
+        Ok(SocketPath::Short(dir.join(format!("{:016x}.sock", hash))))
+    }
+
+    /// The endpoint to serve or connect to the channel `name` on: the
+    /// `socket_path` on Unix, or the `pipe_name` on Windows.
+    pub fn ipc_endpoint(&self, name: &str) -> io::Result<IpcEndpoint> {
+        #[cfg(unix)]
+        return Ok(IpcEndpoint::UnixSocket(self.socket_path(name)?.into_path()));
+        #[cfg(windows)]
+        return Ok(IpcEndpoint::WindowsPipe(self.pipe_name(name)));
+        #[cfg(not(any(unix, windows)))]
+        return Err(io::Error::new(io::ErrorKind::Unsupported,
+                                  format!("no IPC endpoint on this platform: {}", name)));
+    }
+
+    /// The Windows named pipe `name` of the app for the current user, e.g.
+    /// `\\.\pipe\foo-alice-control`; the counterpart of `socket_path`.
+    pub fn pipe_name(&self, name: &str) -> String {
+        self.pipe_name_scoped(name, IpcScope::User)
+    }
+
+    /// Like `pipe_name`, but visible within `scope`.
+    pub fn pipe_name_scoped(&self, name: &str, scope: IpcScope) -> String {
+        format!(r"\\.\pipe\{}", scoped_name(&self.app_name, name, scope).replace('\\', "_"))
+    }
+
+    /// The name of the shared memory or other named object `key` of the app
+    /// for the current user: `/foo-1000-key` for `shm_open` on Unix, or
+    /// `Local\foo-alice-key` for `CreateFileMapping`, `CreateMutex` and the
+    /// like on Windows.
+    pub fn object_name(&self, key: &str) -> String {
+        self.object_name_scoped(key, IpcScope::User)
+    }
+
+    /// Like `object_name`, but visible within `scope`. On Windows,
+    /// `IpcScope::Global` objects go in the `Global\` namespace.
+    #[cfg(windows)]
+    pub fn object_name_scoped(&self, key: &str, scope: IpcScope) -> String {
+        let namespace = if scope == IpcScope::Global { "Global" } else { "Local" };
+        format!(r"{}\{}", namespace, scoped_name(&self.app_name, key, scope).replace('\\', "_"))
+    }
+
+    /// Like `object_name`, but visible within `scope`. Names too long for
+    /// the platform, such as macOS's 31 bytes, are replaced by their hash.
+    #[cfg(not(windows))]
+    pub fn object_name_scoped(&self, key: &str, scope: IpcScope) -> String {
+        let name = format!("/{}", scoped_name(&self.app_name, key, scope).replace('/', "_"));
+        if name.len() <= SHM_NAME_MAX {
+            return name;
+        }
+        format!("/{:016x}", ::fnv1a(name.as_bytes()))
+    }
+}
+
+/// A platform's IPC endpoint, returned by `AppDir::ipc_endpoint`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum IpcEndpoint {
+    /// The path of a Unix domain socket.
+    UnixSocket(PathBuf),
+    /// The name of a Windows named pipe.
+    WindowsPipe(String),
+}
+
+impl Display for IpcEndpoint {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            IpcEndpoint::UnixSocket(ref path) => write!(f, "{}", path.display()),
+            IpcEndpoint::WindowsPipe(ref name) => f.write_str(name),
+        }
+    }
+}
+
+/// Which processes can share a named IPC object, such as a pipe.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(rename_all = "snake_case"))]
+pub enum IpcScope {
+    /// The current user's processes.
+    #[default]
+    User,
+    /// The current user's processes in the same login session.
+    Session,
+    /// Every process on the machine.
+    Global,
+}
+
+/// `<app>-<user>[-<session>]-<name>` for `scope`, or `<app>-<name>` for
+/// `IpcScope::Global`.
+fn scoped_name(app_name: &str, name: &str, scope: IpcScope) -> String {
+    match scope {
+        IpcScope::User => format!("{}-{}-{}", app_name, user_tag(), name),
+        IpcScope::Session => format!("{}-{}-{}-{}", app_name, user_tag(), session_id(), name),
+        IpcScope::Global => format!("{}-{}", app_name, name),
+    }
+}
+
+/// The user ID on Unix; `USERNAME` on Windows.
+#[cfg(unix)]
+fn user_tag() -> String {
+    unsafe { ::libc::geteuid() }.to_string()
+}
+
+/// The user ID on Unix; `USERNAME` on Windows.
+#[cfg(not(unix))]
+fn user_tag() -> String {
+    ::env_var_os("USERNAME")
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "user".to_string())
+}
+
+#[cfg(unix)]
+fn session_id() -> u32 {
+    unsafe { ::libc::getsid(0) as u32 }
+}
+
+#[cfg(windows)]
+fn session_id() -> u32 {
+    use std::process;
+    use windows_sys::Win32::System::RemoteDesktop::ProcessIdToSessionId;
+
+    let mut session = 0;
+    unsafe { ProcessIdToSessionId(process::id(), &mut session) };
+    session
+}
+
+#[cfg(not(any(unix, windows)))]
+fn session_id() -> u32 {
+    0
+}
+
+/// The private per-user directory for socket paths that are too long,
+/// shared by every app using this crate.
+#[cfg(unix)]
+fn short_socket_dir() -> PathBuf {
+    PathBuf::from(format!("/tmp/s_app_dir-{}", unsafe { ::libc::geteuid() }))
+}
+
+/// A socket path returned by `AppDir::socket_path`.
+#[cfg(unix)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum SocketPath {
+    /// The path in the runtime directory.
+    Runtime(PathBuf),
+    /// The path in the temp directory, since there is no runtime directory.
+    Temp(PathBuf),
+    /// The short path under `/tmp`, since the other one was too long.
+    Short(PathBuf),
+}
+
+#[cfg(unix)]
+impl SocketPath {
+    pub fn path(&self) -> &Path {
+        match *self {
+            SocketPath::Runtime(ref path) | SocketPath::Temp(ref path) |
+            SocketPath::Short(ref path) => path,
+        }
+    }
+
+    pub fn into_path(self) -> PathBuf {
+        match self {
+            SocketPath::Runtime(path) | SocketPath::Temp(path) | SocketPath::Short(path) => path,
+        }
+    }
+}
+
+/// The size of `sockaddr_un.sun_path`, including the terminating NUL.
+#[cfg(all(unix, any(target_os = "linux", target_os = "android")))]
+const SUN_PATH_LEN: usize = 108;
+
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "android"))))]
+const SUN_PATH_LEN: usize = 104;
+
+/// The longest name `shm_open` accepts, including the leading `/`.
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+const SHM_NAME_MAX: usize = 31;
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "ios")))]
+const SHM_NAME_MAX: usize = 255;
+
+#[cfg(test)]
+mod tests {
+    use IpcScope;
+
+    /// Namespace pipe names by app and scope, without stray backslashes.
+    #[test]
+    fn pipe_name() {
+        let app_dir = ::AppDir::new("s_app_dir");
+        let user = super::user_tag();
+        assert_eq!(format!(r"\\.\pipe\s_app_dir-{}-control", user), app_dir.pipe_name("control"));
+        assert_eq!(format!(r"\\.\pipe\s_app_dir-{}-{}-a_b", user, super::session_id()),
+                   app_dir.pipe_name_scoped(r"a\b", IpcScope::Session));
+        assert_eq!(r"\\.\pipe\s_app_dir-control",
+                   app_dir.pipe_name_scoped("control", IpcScope::Global));
+    }
+
+    /// Name objects by app, user and key, shortening names that are too long.
+    #[cfg(not(windows))]
+    #[test]
+    fn object_name() {
+        let app_dir = ::AppDir::new("s_app_dir");
+        assert_eq!(format!("/s_app_dir-{}-a_b", super::user_tag()), app_dir.object_name("a/b"));
+        assert_eq!("/s_app_dir-state", app_dir.object_name_scoped("state", IpcScope::Global));
+        let long = app_dir.object_name(&"k".repeat(300));
+        assert_eq!(17, long.len());
+        assert_ne!(long, app_dir.object_name(&"k".repeat(301)));
+    }
+}
+
+#[cfg(all(test, unix))]
+mod unix_tests {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    use {IpcEndpoint, SocketPath};
+
+    /// Use the runtime directory when the path fits, the temp directory
+    /// without one, and a short path under `/tmp` otherwise.
+    #[test]
+    fn socket_path() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir-socket_path");
+        let temp = app_dir.socket_path("control").unwrap();
+        assert_eq!(SocketPath::Temp(app_dir.temp_dir().join("control.sock")), temp);
+        fs::remove_dir(app_dir.temp_dir()).unwrap();
+
+        let app_dir = ::AppDir::new("s_app_dir");
+        let runtime = guard.home().join("runtime");
+        guard.set("XDG_RUNTIME_DIR", &runtime);
+        let expect = runtime.join("s_app_dir").join("control.sock");
+        assert_eq!(SocketPath::Runtime(expect), app_dir.socket_path("control").unwrap());
+        let mode = fs::metadata(runtime.join("s_app_dir")).unwrap().permissions().mode();
+        assert_eq!(0o700, mode & 0o777);
+
+        let deep = runtime.join("d".repeat(120));
+        guard.set("XDG_RUNTIME_DIR", &deep);
+        let existed = super::short_socket_dir().exists();
+        let short = app_dir.socket_path("control").unwrap();
+        assert!(matches!(short, SocketPath::Short(_)));
+        assert!(short.path().as_os_str().len() < super::SUN_PATH_LEN);
+        assert_eq!(super::short_socket_dir(), short.path().parent().unwrap());
+        assert!(super::short_socket_dir().is_dir());
+        assert!(!deep.exists());
+        if !existed {
+            let _ = fs::remove_dir(super::short_socket_dir());
+        }
+    }
+
+    /// Hand out the socket path as the endpoint on Unix.
+    #[test]
+    fn ipc_endpoint() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        let runtime = guard.home().join("runtime");
+        guard.set("XDG_RUNTIME_DIR", &runtime);
+        let endpoint = ::AppDir::new("s_app_dir").ipc_endpoint("control").unwrap();
+        let socket = runtime.join("s_app_dir/control.sock");
+        assert_eq!(socket.display().to_string(), endpoint.to_string());
+        assert_eq!(IpcEndpoint::UnixSocket(socket), endpoint);
+    }
+}
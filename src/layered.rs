@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+use std::error;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature="serde")]
+use serde::{Deserialize, Serialize};
+
+use config::{self, ConfigError};
+use {write_atomic, AppDir, XdgDir};
+
+/// The values read from one file of a layered config.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+pub struct ConfigLayer<V> {
+    pub path: PathBuf,
+    pub values: BTreeMap<String, V>,
+}
+
+/// A config file merged across the config search path.
+///
+/// Lookups see the highest-precedence layer defining a key, while
+/// modifications only ever touch the user layer.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LayeredConfig<V> {
+    layers: Vec<ConfigLayer<V>>,
+}
+
+impl<V> LayeredConfig<V> {
+    /// All layers in order of precedence, user first.
+    pub fn layers(&self) -> &[ConfigLayer<V>] {
+        &self.layers
+    }
+
+    /// The effective value of `key_path`.
+    pub fn get(&self, key_path: &str) -> Option<&V> {
+        self.layers.iter().filter_map(|layer| layer.values.get(key_path)).next()
+    }
+
+    /// The file the effective value of `key_path` comes from.
+    pub fn effective_source(&self, key_path: &str) -> Option<&Path> {
+        self.layers
+            .iter()
+            .find(|layer| layer.values.contains_key(key_path))
+            .map(|layer| layer.path.as_path())
+    }
+
+    /// Set `key_path` in the user layer.
+    pub fn set(&mut self, key_path: &str, value: V) {
+        self.layers[0].values.insert(key_path.to_string(), value);
+    }
+
+    /// Remove `key_path` from the user layer, revealing any system value.
+    pub fn remove(&mut self, key_path: &str) -> Option<V> {
+        self.layers[0].values.remove(key_path)
+    }
+
+    /// Write the user layer back to its file, creating the directory if needed.
+    pub fn save<F, E>(&self, serialize: F) -> Result<(), ConfigError>
+        where F: FnOnce(&BTreeMap<String, V>) -> Result<String, E>,
+              E: Into<Box<dyn error::Error + Send + Sync>>
+    {
+        let user = &self.layers[0];
+        let contents = match serialize(&user.values) {
+            Ok(contents) => contents,
+            Err(err) => return Err(ConfigError::Serialize(user.path.clone(), err.into())),
+        };
+        write_atomic(&user.path, contents.as_bytes()).map_err(ConfigError::Write)
+    }
+}
+
+impl AppDir {
+    /// Load the config file `name` from every directory of the config search path.
+    ///
+    /// `parse` turns a file into a flat map keyed by key path (e.g. `"ui.theme"`).
+    /// Missing files are skipped, except that the user layer is always present
+    /// so that it can be written to.
+    pub fn load_layered_config<V, F, E>(&self,
+                                        name: &str,
+                                        parse: F)
+                                        -> Result<LayeredConfig<V>, ConfigError>
+        where F: Fn(&str) -> Result<BTreeMap<String, V>, E>,
+              E: Into<Box<dyn error::Error + Send + Sync>>
+    {
+        if self.xdg_dir(XdgDir::Config).is_none() {
+            return Err(ConfigError::NoConfigDir);
+        }
+        load_layers(self.config_search_path().into_iter().map(|dir| dir.join(name)).collect(),
+                    parse)
+    }
+}
+
+fn load_layers<V, F, E>(paths: Vec<PathBuf>, parse: F) -> Result<LayeredConfig<V>, ConfigError>
+    where F: Fn(&str) -> Result<BTreeMap<String, V>, E>,
+          E: Into<Box<dyn error::Error + Send + Sync>>
+{
+    // The first path is the user layer; it is kept even if missing so that
+    // writes never fall through to a system layer.
+    let mut layers = Vec::new();
+    for (i, path) in paths.into_iter().enumerate() {
+        if path.is_file() {
+            let values = config::load_file(path.clone(), &parse)?;
+            layers.push(ConfigLayer { path, values });
+        } else if i == 0 {
+            layers.push(ConfigLayer {
+                path,
+                values: BTreeMap::new(),
+            });
+        }
+    }
+    if layers.is_empty() {
+        return Err(ConfigError::NoConfigDir);
+    }
+    Ok(LayeredConfig { layers })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::env;
+    use std::fs;
+
+    fn parse(s: &str) -> Result<BTreeMap<String, String>, String> {
+        s.lines()
+            .map(|line| {
+                let mut kv = line.splitn(2, '=');
+                match (kv.next(), kv.next()) {
+                    (Some(k), Some(v)) => Ok((k.to_string(), v.to_string())),
+                    _ => Err(format!("bad line: {}", line)),
+                }
+            })
+            .collect()
+    }
+
+    fn serialize(values: &BTreeMap<String, String>) -> Result<String, String> {
+        Ok(values.iter().map(|(k, v)| format!("{}={}\n", k, v)).collect())
+    }
+
+    /// Report the system layer as the source and write changes to the user layer only.
+    #[test]
+    fn layered_config_write_back() {
+        let base = env::temp_dir().join("s_app_dir-layered_config_write_back");
+        let _ = fs::remove_dir_all(&base);
+        let user = base.join("user").join("config");
+        let system = base.join("system").join("config");
+        fs::create_dir_all(system.parent().unwrap()).unwrap();
+        fs::write(&system, "theme=dark\nfont=mono").unwrap();
+
+        let mut config = super::load_layers(vec![user.clone(), system.clone()], parse).unwrap();
+        assert_eq!(Some(&"dark".to_string()), config.get("theme"));
+        assert_eq!(Some(system.as_path()), config.effective_source("theme"));
+
+        config.set("theme", "light".to_string());
+        assert_eq!(Some(user.as_path()), config.effective_source("theme"));
+        config.save(serialize).unwrap();
+
+        assert_eq!("theme=light\n", fs::read_to_string(&user).unwrap());
+        assert_eq!("theme=dark\nfont=mono", fs::read_to_string(&system).unwrap());
+        assert!(config.effective_source("size").is_none());
+        let _ = fs::remove_dir_all(&base);
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature="serde")]
+use serde::{Deserialize, Serialize};
+
+use custom;
+use long_path;
+use writable::can_write;
+use {AppDir, Env, XdgDir};
+
+/// Every directory of an app, as returned by `AppDir::resolve_all`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+pub struct AppDirLayout {
+    pub data: Option<PathBuf>,
+    pub config: Option<PathBuf>,
+    pub cache: Option<PathBuf>,
+    pub bin: Option<PathBuf>,
+    pub temp: PathBuf,
+    /// The custom directories, by name; see `AppDirBuilder::custom_dir`.
+    #[cfg_attr(feature="serde", serde(default))]
+    pub custom: BTreeMap<String, Option<PathBuf>>,
+}
+
+impl AppDirLayout {
+    /// The directory of kind `xdg`, as `AppDir::xdg_dir` would return it.
+    pub fn get(&self, xdg: XdgDir) -> Option<&Path> {
+        let dir = match xdg {
+            XdgDir::Data => &self.data,
+            XdgDir::Config => &self.config,
+            XdgDir::Cache => &self.cache,
+            XdgDir::Bin => &self.bin,
+        };
+        dir.as_deref()
+    }
+
+    /// Write the path of `name` in the `xdg` directory into `buf`.
+    ///
+    /// `buf` is cleared first and its allocation reused, so repeated lookups
+    /// don't allocate once it is large enough. `name` is checked like in
+    /// `AppDir::join_file`, but not normalized.
+    pub fn path_into(&self, xdg: XdgDir, name: &str, buf: &mut PathBuf) -> io::Result<()> {
+        long_path::check_relative(name)?;
+        let dir = self.get(xdg).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound,
+                           format!("cannot resolve the {} directory", xdg))
+        })?;
+        buf.as_mut_os_string().clear();
+        buf.push(dir);
+        buf.push(name);
+        Ok(())
+    }
+
+    /// `path_into` for an entry of the cache directory.
+    pub fn cache_path_into(&self, key: &str, buf: &mut PathBuf) -> io::Result<()> {
+        self.path_into(XdgDir::Cache, key, buf)
+    }
+}
+
+/// An aligned table of each directory's kind, path, whether it exists and
+/// whether the process may write to it, for a `--print-dirs` option. A
+/// missing directory is writable if it could be created.
+impl Display for AppDirLayout {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut rows = vec![("KIND".to_string(), "PATH".to_string(), "EXISTS", "WRITABLE")];
+        let dirs = XdgDir::all()
+            .iter()
+            .map(|&xdg| (xdg.to_string(), self.get(xdg)))
+            .chain(Some(("temp".to_string(), Some(&*self.temp))))
+            .chain(self.custom.iter().map(|(name, dir)| (name.clone(), dir.as_deref())));
+        for (kind, dir) in dirs {
+            rows.push(match dir {
+                Some(dir) => {
+                    let writable = dir.ancestors().find(|p| p.exists()).is_some_and(can_write);
+                    (kind, dir.display().to_string(), yes_no(dir.exists()), yes_no(writable))
+                }
+                None => (kind, "(unresolved)".to_string(), "-", "-"),
+            });
+        }
+        let kind_width = rows.iter().map(|row| row.0.chars().count()).max().unwrap_or(0);
+        let path_width = rows.iter().map(|row| row.1.chars().count()).max().unwrap_or(0);
+        for (kind, path, exists, writable) in rows {
+            writeln!(f,
+                     "{:kind_width$}  {:path_width$}  {:6}  {}",
+                     kind,
+                     path,
+                     exists,
+                     writable,
+                     kind_width = kind_width,
+                     path_width = path_width)?;
+        }
+        Ok(())
+    }
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value { "yes" } else { "no" }
+}
+
+impl AppDir {
+    /// Resolve every directory kind, reading the environment only once.
+    pub fn resolve_all(&self) -> AppDirLayout {
+        let env = Env::capture_for(self);
+        AppDirLayout {
+            data: self.resolve_in(XdgDir::Data, &env).ok(),
+            config: self.resolve_in(XdgDir::Config, &env).ok(),
+            cache: self.resolve_in(XdgDir::Cache, &env).ok(),
+            bin: self.resolve_in(XdgDir::Bin, &env).ok(),
+            temp: self.temp_dir(),
+            custom: custom::resolve_all(self, &env),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    /// Resolve the same paths as the per-kind methods.
+    #[test]
+    fn resolve_all() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        guard.set("XDG_CACHE_HOME", guard.home().join("cache"));
+        let app_dir = ::AppDir::new("s_app_dir");
+
+        let layout = app_dir.resolve_all();
+        for &xdg in ::XdgDir::all() {
+            assert_eq!(app_dir.xdg_dir(xdg).as_deref(), layout.get(xdg));
+        }
+        assert_eq!(app_dir.temp_dir(), layout.temp);
+    }
+
+    /// Reuse the buffer for repeated lookups.
+    #[test]
+    fn cache_path_into() {
+        let layout = ::AppDirLayout {
+            data: None,
+            config: None,
+            cache: Some(PathBuf::from("/cache/s_app_dir")),
+            bin: None,
+            temp: PathBuf::from("/tmp/s_app_dir"),
+            custom: Default::default(),
+        };
+        let mut buf = PathBuf::with_capacity(64);
+        let capacity = buf.capacity();
+        for key in &["a", "bb/c"] {
+            layout.cache_path_into(key, &mut buf).unwrap();
+            assert_eq!(PathBuf::from("/cache/s_app_dir").join(key), buf);
+            assert_eq!(capacity, buf.capacity());
+        }
+        assert!(layout.cache_path_into("../a", &mut buf).is_err());
+        assert!(layout.path_into(::XdgDir::Data, "a", &mut buf).is_err());
+    }
+
+    /// Align the table columns.
+    #[test]
+    fn display() {
+        let guard = ::TempEnvGuard::new().unwrap();
+        let home = guard.home().display().to_string();
+        let mut layout = ::AppDirLayout {
+            data: Some(guard.home().to_path_buf()),
+            config: None,
+            cache: None,
+            bin: None,
+            temp: guard.home().join("t"),
+            custom: Default::default(),
+        };
+        layout.custom.insert("screenshots".to_string(), None);
+        let width = home.len() + 2;
+        let expect = format!("KIND         {:w$}  EXISTS  WRITABLE\n\
+                              data         {:w$}  yes     yes\n\
+                              config       {:w$}  -       -\n\
+                              cache        {:w$}  -       -\n\
+                              bin          {:w$}  -       -\n\
+                              temp         {:w$}  no      yes\n\
+                              screenshots  {:w$}  -       -\n",
+                             "PATH",
+                             home,
+                             "(unresolved)",
+                             "(unresolved)",
+                             "(unresolved)",
+                             layout.temp.display(),
+                             "(unresolved)",
+                             w = width);
+        assert_eq!(expect, layout.to_string());
+    }
+}
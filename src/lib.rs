@@ -54,67 +54,725 @@
 #![cfg_attr(feature="sorty", plugin(sorty))]
 #![cfg_attr(feature="sorty", warn(unsorted_declarations))]
 
+#[cfg(feature="arc-swap")]
+extern crate arc_swap;
+#[cfg(feature="clap")]
+extern crate clap;
+#[cfg(unix)]
+extern crate libc;
+#[cfg(feature="log")]
+#[macro_use]
+extern crate log;
+#[cfg(feature="notify")]
+extern crate notify;
+#[cfg(all(target_os = "macos", feature="objc"))]
+extern crate objc2_foundation;
+#[cfg(feature="plist")]
+extern crate plist;
+// The pyo3 macros refer to `::core`, which the 2015 edition only resolves
+// to a crate declared at the root.
+#[cfg(feature="python")]
+extern crate core;
+#[cfg(feature="python")]
+extern crate pyo3;
+extern crate s_app_dir_core;
+#[cfg(feature="serde")]
+extern crate serde;
+#[cfg(all(test, feature="serde"))]
+extern crate serde_json;
+#[cfg(feature="tokio")]
+extern crate tokio;
+#[cfg(feature="unicode-normalization")]
+extern crate unicode_normalization;
+#[cfg(windows)]
+extern crate windows_sys;
+
+use std::collections::BTreeMap;
 use std::env;
+use std::error;
+use std::ffi::OsString;
 use std::fmt::{Display, Error, Formatter};
+use std::hash::{Hash, Hasher};
+use std::io;
 use std::path;
+use std::str::FromStr;
+
+use s_app_dir_core::{EnvSource, Kind, Options, Platform};
+#[cfg(feature="serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(all(windows, feature="windows-acl"))]
+mod acl;
+#[cfg(feature="async")]
+mod async_io;
+mod atomic;
+mod builder;
+mod case;
+mod chain;
+#[cfg(feature="clap")]
+mod clap_value;
+mod clean;
+mod config;
+mod crash;
+mod custom;
+mod data_files;
+mod database;
+#[cfg(all(unix, not(target_os = "macos")))]
+mod desktop;
+mod ensure;
+mod env_file;
+mod expand;
+#[cfg(feature="ffi")]
+pub mod ffi;
+mod filesystem;
+mod fonts;
+mod glob;
+#[cfg(feature="global")]
+#[macro_use]
+mod global;
+mod harden;
+mod home_fallback;
+mod hooks;
+mod ipc;
+mod layered;
+mod layout;
+mod locale;
+mod logs;
+mod long_path;
+mod migrate;
+mod network;
+mod normalize;
+mod os_path;
+mod overrides;
+mod partial;
+#[cfg(all(unix, not(target_os = "macos")))]
+mod polkit;
+#[cfg(any(target_os = "macos", feature="plist"))]
+mod preferences;
+mod project;
+#[cfg(feature="python")]
+pub mod python;
+#[cfg(all(windows, feature="windows-registry"))]
+mod registry;
+#[cfg(feature="reload")]
+mod reload;
+mod relocation;
+mod resolver;
+mod sandbox;
+#[cfg(all(target_os = "macos", feature="objc"))]
+mod search_path;
+mod secure;
+mod slug;
+mod spool;
+mod system;
+#[cfg(all(unix, not(target_os = "macos")))]
+mod systemd;
+#[cfg(any(test, feature="test-util"))]
+mod test_util;
+mod themes;
+#[cfg(all(unix, not(target_os = "macos")))]
+mod thumbnails;
+mod timestamp;
+mod unicode;
+mod update;
+mod user_dirs;
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+mod wasm;
+#[cfg(feature="notify")]
+mod watch;
+mod writable;
+
+#[cfg(feature="async")]
+pub use async_io::{write_atomic_async, Blocking};
+pub use atomic::{write_atomic, WriteError};
+pub use builder::AppDirBuilder;
+pub use chain::ResolveStep;
+pub use clean::CleanReport;
+pub use config::{ConfigError, ConfigFormat, ConfigMatch, ConfigScope, FoundConfig};
+pub use data_files::DataFiles;
+pub use database::database_sidecars;
+pub use ensure::EnsuredDir;
+pub use expand::{expand_path, expand_tilde, ExpandError};
+pub use filesystem::{Fs, FsMetadata, StdFs};
+#[cfg(feature="global")]
+pub use global::{global, init_global};
+pub use harden::InsecureDir;
+pub use home_fallback::{HomeIssue, Provenance};
+pub use ipc::{IpcEndpoint, IpcScope};
+#[cfg(unix)]
+pub use ipc::SocketPath;
+pub use layered::{ConfigLayer, LayeredConfig};
+pub use layout::AppDirLayout;
+pub use long_path::PathTooLong;
+pub use migrate::Migration;
+pub use network::is_network_path;
+pub use normalize::{LinkPolicy, PathForm};
+pub use partial::PartialDownload;
+#[cfg(feature="plist")]
+pub use preferences::{load_plist, save_plist};
+pub use project::ProjectSearch;
+#[cfg(feature="reload")]
+pub use reload::ReloadableConfig;
+pub use resolver::{AppDataResolver, PathResolver, PlatformResolver, XdgResolver};
+pub use sandbox::sandbox_container;
+pub use slug::SlugPolicy;
+pub use spool::SpoolBatch;
+pub use system::{ElevationRequired, SystemScope};
+#[cfg(any(test, feature="test-util"))]
+pub use test_util::TempEnvGuard;
+#[cfg(all(unix, not(target_os = "macos")))]
+pub use thumbnails::{file_uri, thumbnail_name, ThumbnailSize};
+pub use unicode::{names_equivalent, paths_equivalent};
+pub use update::StagedUpdate;
+pub use user_dirs::UserDir;
+#[cfg(all(unix, not(target_os = "macos")))]
+pub use user_dirs::set_user_dir;
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub use wasm::set_virtual_root;
+#[cfg(feature="notify")]
+pub use watch::ConfigWatcher;
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(rename_all = "lowercase"))]
 pub enum XdgDir {
     Data,
     Config,
     Cache,
+    /// User executables (`XDG_BIN_HOME`). Unlike the other kinds this is shared
+    /// by all apps, so no app subdirectory is added, except for the
+    /// `%APPDATA%\app_name\bin` fallback on Windows.
+    Bin,
+}
+
+impl XdgDir {
+    /// Every directory kind, in declaration order.
+    pub fn all() -> &'static [XdgDir] {
+        static ALL: [XdgDir; 4] = [XdgDir::Data, XdgDir::Config, XdgDir::Cache, XdgDir::Bin];
+        &ALL
+    }
+
+    /// The lowercase name used by `Display` and `FromStr`.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            XdgDir::Data => "data",
+            XdgDir::Config => "config",
+            XdgDir::Cache => "cache",
+            XdgDir::Bin => "bin",
+        }
+    }
+
+    fn kind(&self) -> Kind {
+        match *self {
+            XdgDir::Data => Kind::Data,
+            XdgDir::Config => Kind::Config,
+            XdgDir::Cache => Kind::Cache,
+            XdgDir::Bin => Kind::Bin,
+        }
+    }
+}
+
+impl Display for XdgDir {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for XdgDir {
+    type Err = ParseXdgDirError;
+
+    /// Parse a name such as `"config"`, ignoring ASCII case.
+    fn from_str(s: &str) -> Result<XdgDir, ParseXdgDirError> {
+        XdgDir::all()
+            .iter()
+            .find(|xdg| xdg.as_str().eq_ignore_ascii_case(s))
+            .cloned()
+            .ok_or_else(|| ParseXdgDirError { input: s.to_string() })
+    }
+}
+
+/// The error returned when parsing an unknown `XdgDir` name.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ParseXdgDirError {
+    input: String,
+}
+
+impl ParseXdgDirError {
+    /// The string that failed to parse.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+impl Display for ParseXdgDirError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(f, "unknown directory kind: {}", self.input)
+    }
+}
+
+impl error::Error for ParseXdgDirError {}
+
+/// Why a directory could not be resolved.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ResolveError {
+    /// The home directory could not be determined.
+    NoHome(EnvReport),
+    /// A variable the fallback depends on, such as `APPDATA`, is not set.
+    NotSet(String, EnvReport),
+    /// In strict mode, a variable is set to a relative or non-Unicode path.
+    Invalid(String, OsString, EnvReport),
+    /// With `LinkPolicy::Reject`, the directory lies behind this link.
+    Link(path::PathBuf, EnvReport),
+}
+
+impl Display for ResolveError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        let report = match *self {
+            ResolveError::NoHome(ref report) => {
+                write!(f, "cannot determine the home directory")?;
+                report
+            }
+            ResolveError::NotSet(ref key, ref report) => {
+                write!(f, "{} is not set", key)?;
+                report
+            }
+            ResolveError::Invalid(ref key, ref value, ref report) => {
+                write!(f, "{} is not an absolute path: {:?}", key, value)?;
+                report
+            }
+            ResolveError::Link(ref link, ref report) => {
+                write!(f, "{} is a symlink, junction or other reparse point", link.display())?;
+                report
+            }
+        };
+        #[cfg(feature="verbose-errors")]
+        report.write(f)?;
+        #[cfg(not(feature="verbose-errors"))]
+        let _ = report;
+        Ok(())
+    }
+}
+
+/// The home directory, the variables and the `AppDir::with_overrides`
+/// directories in effect when a `ResolveError` was created. With the
+/// `verbose-errors` feature they are listed at the end of its message;
+/// otherwise the report is empty.
+///
+/// Reports compare equal to each other, so errors compare by their cause.
+#[derive(Clone, Debug, Default)]
+pub struct EnvReport {
+    #[cfg(feature="verbose-errors")]
+    vars: Vec<(String, Option<OsString>)>,
+}
+
+#[cfg(feature="verbose-errors")]
+impl EnvReport {
+    /// Append the entries with their values, or with `{:#}` only whether
+    /// each is set.
+    fn write(&self, f: &mut Formatter) -> Result<(), Error> {
+        if self.vars.is_empty() {
+            return Ok(());
+        }
+        write!(f, " (")?;
+        for (i, (key, value)) in self.vars.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            match *value {
+                None => write!(f, "{} unset", key)?,
+                Some(_) if f.alternate() => write!(f, "{} set", key)?,
+                Some(ref value) => write!(f, "{}={:?}", key, value)?,
+            }
+        }
+        write!(f, ")")
+    }
+}
+
+impl PartialEq for EnvReport {
+    fn eq(&self, _: &EnvReport) -> bool {
+        true
+    }
+}
+
+impl Eq for EnvReport {}
+
+impl Hash for EnvReport {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
+impl ResolveError {
+    /// What the user can do about the error, as a sentence to show them,
+    /// e.g. "Set HOME to your home directory."
+    pub fn hint(&self) -> String {
+        match *self {
+            ResolveError::NoHome(_) if cfg!(windows) => {
+                r"Set USERPROFILE to your profile folder, usually C:\Users\<name>.".to_string()
+            }
+            ResolveError::NoHome(_) => "Set HOME to your home directory.".to_string(),
+            ResolveError::NotSet(ref key, _) if key == "APPDATA" || key == "LOCALAPPDATA" => {
+                let folder = if key == "APPDATA" { "Roaming" } else { "Local" };
+                format!(r"Set {} to %USERPROFILE%\AppData\{} and create that folder.",
+                        key,
+                        folder)
+            }
+            ResolveError::NotSet(ref key, _) => format!("Set {}.", key),
+            ResolveError::Invalid(ref key, ..) => {
+                format!("Set {} to an absolute path, or unset it to use the default.", key)
+            }
+            ResolveError::Link(ref link, _) => {
+                format!("Set the directory's variable to the path {} points to.", link.display())
+            }
+        }
+    }
+}
+
+impl error::Error for ResolveError {}
+
+impl From<ResolveError> for io::Error {
+    fn from(err: ResolveError) -> io::Error {
+        io::Error::new(io::ErrorKind::NotFound, err)
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
 pub struct AppDir {
+    #[cfg_attr(feature="serde", serde(deserialize_with = "unicode::deserialize_nfc"))]
     app_name: String,
+    #[cfg_attr(feature="serde",
+               serde(default, deserialize_with = "unicode::deserialize_nfc_option"))]
+    organization: Option<String>,
+    #[cfg_attr(feature="serde", serde(default))]
+    path_form: PathForm,
+    #[cfg_attr(feature="serde", serde(default))]
+    link_policy: LinkPolicy,
+    #[cfg_attr(feature="serde", serde(default))]
+    long_paths: bool,
+    #[cfg_attr(feature="serde", serde(default))]
+    strict: bool,
+    #[cfg_attr(feature="serde", serde(default))]
+    pure_env: bool,
+    #[cfg_attr(feature="serde", serde(default))]
+    chains: BTreeMap<XdgDir, Vec<ResolveStep>>,
+    #[cfg_attr(feature="serde", serde(default))]
+    custom_dirs: BTreeMap<String, (XdgDir, String)>,
+    #[cfg_attr(feature="serde", serde(default))]
+    relocations: BTreeMap<XdgDir, path::PathBuf>,
+    #[cfg_attr(feature="serde", serde(default))]
+    home_fallback: Option<path::PathBuf>,
+    #[cfg_attr(feature="serde", serde(default))]
+    check_shared_dirs: bool,
+    #[cfg_attr(feature="serde", serde(default))]
+    prefer_runtime_dir: bool,
+    #[cfg_attr(feature="serde", serde(skip))]
+    resolver: resolver::Resolver,
 }
 
 impl AppDir {
     pub fn new(app_name: &str) -> AppDir {
-        AppDir { app_name: app_name.to_string() }
+        AppDir {
+            app_name: unicode::nfc(app_name),
+            organization: None,
+            path_form: PathForm::AsIs,
+            link_policy: LinkPolicy::Follow,
+            long_paths: false,
+            strict: false,
+            pure_env: false,
+            chains: BTreeMap::new(),
+            custom_dirs: BTreeMap::new(),
+            relocations: BTreeMap::new(),
+            home_fallback: None,
+            check_shared_dirs: false,
+            prefer_runtime_dir: false,
+            resolver: resolver::Resolver::default(),
+        }
     }
 
-    #[cfg(unix)]
-    fn xdg_dir_with_fallback<P>(&self, key: &str, fallback: P) -> Option<path::PathBuf>
-        where P: AsRef<path::Path>
-    {
-        result_to_option(env::var(key))
-            .map(|dir| path::PathBuf::new().join(&dir))
-            .or(env::home_dir().map(|p| p.join(fallback)))
+    pub fn builder(app_name: &str) -> AppDirBuilder {
+        AppDirBuilder::new(app_name)
     }
 
-    #[cfg(windows)]
-    fn xdg_dir_with_fallback<P>(&self, key: &str, _: P) -> Option<path::PathBuf>
+    pub fn app_name(&self) -> &str {
+        &self.app_name
+    }
+
+    pub fn set_app_name(&mut self, app_name: &str) {
+        self.app_name = unicode::nfc(app_name);
+    }
+
+    pub fn organization(&self) -> Option<&str> {
+        self.organization.as_deref()
+    }
+
+    /// A copy of this `AppDir` with the same options but another app name.
+    pub fn with_app_name(&self, app_name: &str) -> AppDir {
+        AppDir { app_name: unicode::nfc(app_name), ..self.clone() }
+    }
+
+    /// The settings of `s_app_dir_core` resolution.
+    fn options(&self) -> Options {
+        Options {
+            strict: self.strict,
+            pure_env: self.pure_env,
+        }
+    }
+
+    fn finish(&self, path: path::PathBuf) -> path::PathBuf {
+        let path = self.path_form.apply(path);
+        if self.long_paths {
+            long_path::extend_if_long(path, long_path::MAX_DIR_PATH)
+        } else {
+            path
+        }
+    }
+
+    /// Append the relative file name `name` to the resolved directory `dir`.
+    ///
+    /// `name` may not contain `..` or (on Windows) reserved characters. With
+    /// long paths enabled, the result is returned in extended-length form if
+    /// it exceeds `MAX_PATH`; otherwise such a path fails on Windows with a
+    /// `PathTooLong` error inside the `io::Error`.
+    pub fn join_file<P>(&self, dir: P, name: &str) -> io::Result<path::PathBuf>
         where P: AsRef<path::Path>
     {
-        result_to_option(env::var(key))
-            .map(|dir| path::PathBuf::new().join(&dir))
-            .or(result_to_option(env::var("APPDATA")).map(|dir| path::PathBuf::new().join(&dir)))
+        let path = dir.as_ref().join(long_path::clean_relative(name)?);
+        if self.long_paths {
+            Ok(long_path::extend_if_long(path, long_path::MAX_PATH))
+        } else {
+            if cfg!(windows) {
+                long_path::check_length(&path)?;
+            }
+            Ok(path)
+        }
     }
 
     pub fn xdg_dir(&self, xdg: XdgDir) -> Option<path::PathBuf> {
-        let xdg_dir = match xdg {
-            XdgDir::Data => self.xdg_dir_with_fallback("XDG_DATA_HOME", ".local/share"),
-            XdgDir::Config => self.xdg_dir_with_fallback("XDG_CONFIG_HOME", ".config"),
-            XdgDir::Cache => self.xdg_dir_with_fallback("XDG_CACHE_HOME", ".cache"),
+        self.try_xdg_dir(xdg).ok()
+    }
+
+    /// Like `xdg_dir`, but say why the directory could not be resolved.
+    pub fn try_xdg_dir(&self, xdg: XdgDir) -> Result<path::PathBuf, ResolveError> {
+        self.resolve_in(xdg, &Env::capture_for(self))
+    }
+
+    /// The links on the way to the directory of `xdg`, outermost first:
+    /// symlinks and, on Windows, junctions and other reparse points, as
+    /// `LinkPolicy::Report` would log them. Unlike `LinkPolicy`, this
+    /// applies to every path form.
+    pub fn links(&self, xdg: XdgDir) -> Result<Vec<path::PathBuf>, ResolveError> {
+        Ok(normalize::links(&self.resolve_unfinished(xdg, &Env::capture_for(self))?))
+    }
+
+    fn resolve_in(&self, xdg: XdgDir, env: &Env) -> Result<path::PathBuf, ResolveError> {
+        let xdg_dir = self.resolve_unfinished(xdg, env)?;
+        if self.path_form == PathForm::Canonical {
+            normalize::check_links(self.link_policy, &xdg_dir)
+                .map_err(|link| ResolveError::Link(link, env.report()))?;
+        }
+        Ok(self.finish(xdg_dir))
+    }
+
+    /// `xdg` as resolved, before links are checked and `finish`.
+    fn resolve_unfinished(&self, xdg: XdgDir, env: &Env) -> Result<path::PathBuf, ResolveError> {
+        let relocated = self.relocations.get(&xdg).filter(|_| overrides::get(xdg).is_none());
+        match (relocated, self.resolver.get()) {
+            (Some(dir), _) => Ok(dir.clone()),
+            (None, Some(resolver)) => resolver.resolve(self, xdg),
+            (None, None) => self.resolve_platform(xdg, env),
+        }
+    }
+
+    /// `xdg` as `PlatformResolver` resolves it, before `finish`.
+    fn resolve_platform(&self, xdg: XdgDir, env: &Env) -> Result<path::PathBuf, ResolveError> {
+        #[cfg(all(windows, feature="windows-registry"))]
+        {
+            if overrides::get(xdg).is_none() && !self.pure_env {
+                let organization = self.organization.as_deref();
+                if let Some(dir) = registry::directory(organization, &self.app_name, xdg) {
+                    return Ok(dir);
+                }
+            }
+        }
+        #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+        {
+            if overrides::get(xdg).is_none() && !self.chains.contains_key(&xdg) {
+                return Ok(wasm::virtual_dir(&self.app_name, xdg.as_str()));
+            }
+        }
+        match self.chains.get(&xdg) {
+            Some(steps) if overrides::get(xdg).is_none() => chain::resolve(self, xdg, steps, env),
+            _ => self.resolve_default(xdg, env),
+        }
+    }
+
+    /// `xdg` as resolved without a custom chain, before `finish`.
+    fn resolve_default(&self, xdg: XdgDir, env: &Env) -> Result<path::PathBuf, ResolveError> {
+        let fallback;
+        let env = match (home_fallback::issue(self, xdg, env), &self.home_fallback) {
+            (Some(_), Some(root)) => {
+                fallback = home_fallback::env(env, root.clone());
+                &fallback
+            }
+            _ => env,
         };
-        xdg_dir.map(|base| path::PathBuf::new().join(&base).join(&self.app_name))
+        match xdg {
+            XdgDir::Bin => bin_dir(&self.app_name, self.options(), env),
+            _ => Ok(resolve_base_dir(xdg, self.options(), env)?.join(&self.app_name)),
+        }
+    }
+
+    /// Every directory kind paired with its resolved path.
+    pub fn all_dirs(&self) -> Vec<(XdgDir, Option<path::PathBuf>)> {
+        XdgDir::all().iter().map(|&xdg| (xdg, self.xdg_dir(xdg))).collect()
+    }
+
+    /// Like `xdg_dir`, but only if the directory already exists.
+    pub fn existing_xdg_dir(&self, xdg: XdgDir) -> Option<path::PathBuf> {
+        self.xdg_dir(xdg).filter(|dir| dir.is_dir())
     }
 
+    /// Config directories in order of precedence: the user's, those in the
+    /// app's search-path variable (see `path_var`), then the system ones.
+    /// Each directory is listed once, without trailing separators or `.`.
     #[cfg(unix)]
+    pub fn config_search_path(&self) -> Vec<path::PathBuf> {
+        let system = env_var_os("XDG_CONFIG_DIRS").unwrap_or_else(|| "/etc/xdg".into());
+        normalize::search_path(self.xdg_dir(XdgDir::Config)
+            .into_iter()
+            .chain(self.path_var_dirs())
+            .chain(env::split_paths(&system)
+                .filter(|dir| dir.is_absolute())
+                .map(|dir| self.finish(dir.join(&self.app_name)))))
+    }
+
+    /// Config directories in order of precedence: the user's, then those in
+    /// the app's search-path variable (see `path_var`).
+    /// Each directory is listed once, without trailing separators or `.`.
+    #[cfg(not(unix))]
+    pub fn config_search_path(&self) -> Vec<path::PathBuf> {
+        normalize::search_path(self.xdg_dir(XdgDir::Config).into_iter().chain(self.path_var_dirs()))
+    }
+
+    /// Data directories in order of precedence: the user's, those in the
+    /// app's search-path variable (see `path_var`), then the system ones.
+    /// Each directory is listed once, without trailing separators or `.`.
+    #[cfg(unix)]
+    pub fn data_search_path(&self) -> Vec<path::PathBuf> {
+        normalize::search_path(self.xdg_dir(XdgDir::Data)
+            .into_iter()
+            .chain(self.path_var_dirs())
+            .chain(system_data_dirs().into_iter().map(|dir| self.finish(dir.join(&self.app_name)))))
+    }
+
+    /// Data directories in order of precedence: the user's, then those in
+    /// the app's search-path variable (see `path_var`).
+    /// Each directory is listed once, without trailing separators or `.`.
+    #[cfg(not(unix))]
+    pub fn data_search_path(&self) -> Vec<path::PathBuf> {
+        normalize::search_path(self.xdg_dir(XdgDir::Data).into_iter().chain(self.path_var_dirs()))
+    }
+
+    /// The app's search-path variable, e.g. `FOO_BAR_PATH` for `foo-bar`;
+    /// see `variable_name`. Its directories, separated by `:` (`;` on
+    /// Windows), are searched for config and data files as they are,
+    /// without the app name appended; relative ones are ignored.
+    pub fn path_var(&self) -> String {
+        format!("{}_PATH", variable_name(&self.app_name))
+    }
+
+    fn path_var_dirs(&self) -> Vec<path::PathBuf> {
+        env_var_os(&self.path_var())
+            .map(|dirs| {
+                env::split_paths(&dirs)
+                    .filter(|dir| dir.is_absolute())
+                    .map(|dir| self.finish(dir))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    #[cfg(not(windows))]
     pub fn user_data_dir(&self) -> Option<path::PathBuf> {
-        env::home_dir().map(|p| p.join(".".to_string() + &self.app_name))
+        env::home_dir().map(|p| self.finish(p.join(".".to_string() + &self.app_name)))
     }
 
     #[cfg(windows)]
     pub fn user_data_dir(&self) -> Option<path::PathBuf> {
-        result_to_option(env::var("APPDATA"))
-            .map(|v| path::PathBuf::new().join(v).join(&self.app_name))
+        env_var_os("APPDATA")
+            .map(|v| self.finish(path::PathBuf::new().join(v).join(&self.app_name)))
+    }
+
+    /// Data that should follow the user across machines, such as settings:
+    /// the data directory, which is in the roaming profile on Windows.
+    pub fn data_roaming_dir(&self) -> Option<path::PathBuf> {
+        self.xdg_dir(XdgDir::Data)
+    }
+
+    /// Data that is large or specific to this machine:
+    /// `%LOCALAPPDATA%\app_name`, or the data directory on other platforms.
+    #[cfg(not(windows))]
+    pub fn data_local_dir(&self) -> Option<path::PathBuf> {
+        self.xdg_dir(XdgDir::Data)
+    }
+
+    /// Data that is large or specific to this machine:
+    /// `%LOCALAPPDATA%\app_name`, or the data directory on other platforms.
+    #[cfg(windows)]
+    pub fn data_local_dir(&self) -> Option<path::PathBuf> {
+        if let Some(dir) = overrides::get(XdgDir::Data) {
+            return Some(self.finish(dir.join(&self.app_name)));
+        }
+        env_var_os("LOCALAPPDATA")
+            .map(|v| self.finish(path::PathBuf::from(v).join(&self.app_name)))
     }
 
+    /// `<temp>/<app_name>-<uid>` on Unix. Unlike `temp_dir`, which is shared
+    /// by every user of the machine, other users cannot collide with it;
+    /// create it with `ensure_user_temp_dir`.
+    /// With `AppDirBuilder::prefer_runtime_dir`, the same as `temp_dir`.
+    #[cfg(unix)]
+    pub fn user_temp_dir(&self) -> path::PathBuf {
+        if let Some(dir) = self.runtime_temp_dir() {
+            return dir;
+        }
+        let name = format!("{}-{}", self.app_name, unsafe { libc::geteuid() });
+        self.finish(env::temp_dir().join(name))
+    }
+
+    /// `temp_dir`, which is already per-user on Windows.
+    #[cfg(not(unix))]
+    pub fn user_temp_dir(&self) -> path::PathBuf {
+        self.temp_dir()
+    }
+
+    /// `<temp>/<app_name>`, or `tmp` in `runtime_dir` with
+    /// `AppDirBuilder::prefer_runtime_dir` on Linux.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn temp_dir(&self) -> path::PathBuf {
+        if let Some(dir) = self.runtime_temp_dir() {
+            return dir;
+        }
+        self.finish(env::temp_dir().join(&self.app_name))
+    }
+
+    /// `tmp` in `runtime_dir`, if preferred and available. Not the runtime
+    /// directory itself, so `clean_temp` spares sockets there.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn runtime_temp_dir(&self) -> Option<path::PathBuf> {
+        self.runtime_dir().filter(|_| self.prefer_runtime_dir).map(|dir| dir.join("tmp"))
+    }
+
+    #[cfg(not(any(target_os = "linux",
+                  target_os = "android",
+                  all(target_arch = "wasm32", target_os = "unknown"))))]
+    fn runtime_temp_dir(&self) -> Option<path::PathBuf> {
+        None
+    }
+
+    /// The virtual `idb://app_name/temp`; see `set_virtual_root`.
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
     pub fn temp_dir(&self) -> path::PathBuf {
-        env::temp_dir().join(&self.app_name)
+        self.finish(wasm::virtual_dir(&self.app_name, "temp"))
     }
 }
 
@@ -124,6 +782,201 @@ impl Display for AppDir {
     }
 }
 
+impl AsRef<str> for AppDir {
+    fn as_ref(&self) -> &str {
+        &self.app_name
+    }
+}
+
+impl From<AppDir> for String {
+    fn from(app_dir: AppDir) -> String {
+        app_dir.app_name
+    }
+}
+
+/// The variables directory resolution reads, captured in one pass.
+struct Env {
+    vars: Vec<(String, Option<OsString>)>,
+    home: Option<path::PathBuf>,
+}
+
+impl Env {
+    /// The variables the platform rules read, and the home directory.
+    fn capture() -> Env {
+        Env::capture_with(Vec::new())
+    }
+
+    /// Like `capture`, but also the variables the fallback chains of
+    /// `app_dir` read, and its search-path variable.
+    fn capture_for(app_dir: &AppDir) -> Env {
+        let keys = app_dir.chains
+            .values()
+            .flatten()
+            .filter_map(|step| match *step {
+                ResolveStep::Var(ref key) => Some(key.clone()),
+                _ => None,
+            })
+            .chain(Some(app_dir.path_var()))
+            .collect();
+        Env::capture_with(keys)
+    }
+
+    fn capture_with(keys: Vec<String>) -> Env {
+        let base = ["XDG_DATA_HOME", "XDG_CONFIG_HOME", "XDG_CACHE_HOME", "XDG_BIN_HOME",
+                    "XDG_RUNTIME_DIR", "APPDATA", "LOCALAPPDATA"];
+        let mut vars = Vec::<(String, Option<OsString>)>::new();
+        for key in base.iter().map(|key| key.to_string()).chain(keys) {
+            if !vars.iter().any(|(captured, _)| *captured == key) {
+                let value = env_var_os(&key);
+                vars.push((key, value));
+            }
+        }
+        let home = match sandbox::sandbox_container() {
+            Some(container) => {
+                for (_, value) in &mut vars {
+                    if value.as_ref().is_some_and(|v| !path::Path::new(v).starts_with(&container)) {
+                        *value = None;
+                    }
+                }
+                Some(container)
+            }
+            None => env::home_dir(),
+        };
+        Env { vars, home }
+    }
+
+    fn var_os(&self, key: &str) -> Option<&OsString> {
+        self.vars.iter().find(|&(k, _)| k == key).and_then(|(_, value)| value.as_ref())
+    }
+
+    /// `err` as a `ResolveError`, with the offending value.
+    fn resolve_error(&self, err: s_app_dir_core::Error) -> ResolveError {
+        match err {
+            s_app_dir_core::Error::NoHome => ResolveError::NoHome(self.report()),
+            s_app_dir_core::Error::NotSet(key) => self.not_set(key),
+            s_app_dir_core::Error::Invalid(key) => self.invalid(key),
+        }
+    }
+
+    /// `ResolveError::NotSet` for `key`.
+    fn not_set(&self, key: &str) -> ResolveError {
+        ResolveError::NotSet(key.to_string(), self.report())
+    }
+
+    /// `ResolveError::Invalid` for `key`, with its value.
+    fn invalid(&self, key: &str) -> ResolveError {
+        let value = self.var_os(key).cloned().unwrap_or_default();
+        ResolveError::Invalid(key.to_string(), value, self.report())
+    }
+
+    /// The home directory, the variables and the overrides on this thread,
+    /// for a `ResolveError`.
+    #[cfg(feature="verbose-errors")]
+    fn report(&self) -> EnvReport {
+        let home = ("home".to_string(), self.home.clone().map(OsString::from));
+        let overrides = XdgDir::all().iter().filter_map(|&xdg| {
+            overrides::get(xdg).map(|dir| (format!("{} override", xdg), Some(dir.into())))
+        });
+        let vars = Some(home).into_iter().chain(self.vars.clone()).chain(overrides).collect();
+        EnvReport { vars }
+    }
+
+    #[cfg(not(feature="verbose-errors"))]
+    fn report(&self) -> EnvReport {
+        EnvReport::default()
+    }
+}
+
+/// Values that are not valid Unicode cannot be paths.
+impl EnvSource for Env {
+    type Path = path::PathBuf;
+
+    fn var(&self, key: &str) -> Option<Option<path::PathBuf>> {
+        self.var_os(key).map(|value| value.to_str().map(path::PathBuf::from))
+    }
+
+    fn home(&self) -> Option<path::PathBuf> {
+        self.home.clone()
+    }
+
+    fn is_absolute(&self, path: &path::PathBuf) -> bool {
+        path.is_absolute()
+    }
+
+    fn join(&self, base: path::PathBuf, relative: &str) -> path::PathBuf {
+        base.join(relative)
+    }
+}
+
+/// The base directory of `xdg`, shared by all apps.
+fn xdg_base_dir(xdg: XdgDir) -> Option<path::PathBuf> {
+    resolve_base_dir(xdg, Options::default(), &Env::capture()).ok()
+}
+
+fn resolve_base_dir(xdg: XdgDir,
+                    options: Options,
+                    env: &Env)
+                    -> Result<path::PathBuf, ResolveError> {
+    if let Some(dir) = overrides::get(xdg) {
+        return Ok(dir);
+    }
+    #[cfg(all(target_os = "macos", feature="objc"))]
+    {
+        let var = s_app_dir_core::var_dir(xdg.kind().var(), options, env);
+        if var.map_err(|err| env.resolve_error(err))?.is_none() && !options.pure_env {
+            if let Some(dir) = search_path::base_dir(xdg) {
+                return Ok(dir);
+            }
+        }
+    }
+    s_app_dir_core::base_dir(xdg.kind(), Platform::current(), options, env)
+        .map_err(|err| env.resolve_error(err))
+}
+
+/// The system-wide base data directories from `XDG_DATA_DIRS`, shared by
+/// all apps.
+#[cfg(unix)]
+fn system_data_dirs() -> Vec<path::PathBuf> {
+    let system = env_var_os("XDG_DATA_DIRS")
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+    env::split_paths(&system).filter(|dir| dir.is_absolute()).collect()
+}
+
+fn bin_dir(app_name: &str, options: Options, env: &Env) -> Result<path::PathBuf, ResolveError> {
+    if let Some(dir) = overrides::get(XdgDir::Bin) {
+        return Ok(dir);
+    }
+    s_app_dir_core::bin_dir(app_name, Platform::current(), options, env)
+        .map_err(|err| env.resolve_error(err))
+}
+
+/// `name` uppercased, with anything but letters and digits replaced by `_`,
+/// so it is a valid variable name, e.g. `FOO_BAR` for `foo-bar`.
+pub fn variable_name(name: &str) -> String {
+    let variable = name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect::<String>();
+    if variable.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", variable)
+    } else {
+        variable
+    }
+}
+
+/// The environment variable `key`, with empty and whitespace-only values
+/// treated as unset, as the XDG spec requires.
+fn env_var_os(key: &str) -> Option<OsString> {
+    env::var_os(key).filter(|value| !value.to_str().is_some_and(|value| value.trim().is_empty()))
+}
+
+/// 64-bit FNV-1a, to name files after longer keys. Not for security use.
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(all(test, windows))]
 fn result_to_option<T, E>(result: Result<T, E>) -> Option<T> {
     match result {
         Ok(v) => Some(v),
@@ -134,6 +987,7 @@ fn result_to_option<T, E>(result: Result<T, E>) -> Option<T> {
 #[cfg(test)]
 mod tests {
     use std::env;
+    use std::fs;
     use std::path::PathBuf;
 
     static APP_NAME: &'static str = "s_app_dir";
@@ -142,7 +996,7 @@ mod tests {
     #[cfg(unix)]
     #[test]
     fn default_data_home() {
-        env::remove_var("XDG_DATA_HOME");
+        let _guard = ::TempEnvGuard::new().unwrap();
         let expect = env::home_dir().map(|p| p.join(".local/share").join(APP_NAME));
         let value = ::AppDir::new(APP_NAME).xdg_dir(::XdgDir::Data);
         assert_eq!(expect, value);
@@ -152,18 +1006,76 @@ mod tests {
     #[cfg(windows)]
     #[test]
     fn default_data_home() {
-        env::remove_var("XDG_DATA_HOME");
+        let _guard = ::TempEnvGuard::new().unwrap();
         let expect = ::result_to_option(env::var("APPDATA"))
                          .map(|dir| PathBuf::new().join(&dir).join(APP_NAME));
         let value = ::AppDir::new(APP_NAME).xdg_dir(::XdgDir::Data);
         assert_eq!(expect, value);
     }
 
+    /// Treat empty and whitespace-only XDG variables as unset.
+    #[test]
+    fn blank_env() {
+        let mut guard = ::TempEnvGuard::for_app(&::AppDir::new(APP_NAME)).unwrap();
+        let expect = ::AppDir::new(APP_NAME).all_dirs();
+        guard.set("XDG_DATA_HOME", "");
+        guard.set("XDG_CONFIG_HOME", " \t");
+        guard.set("XDG_CACHE_HOME", "\n");
+        let app_dir = ::AppDir::builder(APP_NAME).strict(true).build();
+        assert_eq!(expect, app_dir.all_dirs());
+
+        guard.set("XDG_DATA_DIRS", " ");
+        #[cfg(unix)]
+        assert_eq!(3, ::AppDir::new(APP_NAME).data_search_path().len());
+    }
+
+    /// Suggest how to fix each error.
+    #[test]
+    fn resolve_error_hint() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        guard.set("XDG_CONFIG_HOME", "relative");
+        let err = ::AppDir::builder(APP_NAME).strict(true).build().try_xdg_dir(::XdgDir::Config);
+        assert_eq!("Set XDG_CONFIG_HOME to an absolute path, or unset it to use the default.",
+                   err.unwrap_err().hint());
+        assert_eq!(r"Set APPDATA to %USERPROFILE%\AppData\Roaming and create that folder.",
+                   ::ResolveError::NotSet("APPDATA".to_string(), Default::default()).hint());
+        #[cfg(unix)]
+        let err = ::ResolveError::NoHome(Default::default());
+        assert_eq!("Set HOME to your home directory.", err.hint());
+    }
+
+    /// List the variables read when the error was created, with or without
+    /// their values.
+    #[cfg(feature="verbose-errors")]
+    #[test]
+    fn verbose_errors() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        guard.set("XDG_CONFIG_HOME", "relative");
+        guard.remove("XDG_DATA_HOME");
+        guard.set("S_APP_DIR_PATH", "/opt/s_app_dir");
+        let app_dir = ::AppDir::builder(APP_NAME).strict(true).build();
+        let cache = PathBuf::from("/tmp/s_app_dir-verbose_errors");
+        let err = ::AppDir::with_overrides(vec![(::XdgDir::Cache, cache)], || {
+            app_dir.try_xdg_dir(::XdgDir::Config).unwrap_err()
+        });
+        guard.set("XDG_CONFIG_HOME", "changed");
+        let message = err.to_string();
+        assert!(message.starts_with("XDG_CONFIG_HOME is not an absolute path: \"relative\" (home="));
+        assert!(message.contains(", XDG_DATA_HOME unset, XDG_CONFIG_HOME=\"relative\","));
+        assert!(message.contains(", S_APP_DIR_PATH=\"/opt/s_app_dir\","));
+        assert!(message.ends_with(", cache override=\"/tmp/s_app_dir-verbose_errors\")"));
+        assert!(format!("{:#}", err).contains(", XDG_CONFIG_HOME set,"));
+
+        let err = ::ResolveError::NotSet("APPDATA".to_string(), Default::default());
+        assert_eq!("APPDATA is not set", err.to_string());
+    }
+
     /// Return `$XDG_DATA_HOME/app_name` if `XDG_DATA_HOME` is set.
     #[test]
     fn env_data_home() {
-        let xdg_data_home = PathBuf::new().join("/home/s_app_dir/.path/to/xdg_data_home");
-        env::set_var("XDG_DATA_HOME", &xdg_data_home);
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        let xdg_data_home = PathBuf::from("/home/s_app_dir/.path/to/xdg_data_home");
+        guard.set("XDG_DATA_HOME", &xdg_data_home);
 
         let expect = Some(xdg_data_home.join(APP_NAME));
         let value = ::AppDir::new(APP_NAME).xdg_dir(::XdgDir::Data);
@@ -174,7 +1086,7 @@ mod tests {
     #[cfg(unix)]
     #[test]
     fn default_config_home() {
-        env::remove_var("XDG_CONFIG_HOME");
+        let _guard = ::TempEnvGuard::new().unwrap();
         let expect = env::home_dir().map(|p| p.join(".config").join(APP_NAME));
         let value = ::AppDir::new(APP_NAME).xdg_dir(::XdgDir::Config);
         assert_eq!(expect, value);
@@ -183,7 +1095,7 @@ mod tests {
     #[cfg(windows)]
     #[test]
     fn default_config_home() {
-        env::remove_var("XDG_CONFIG_HOME");
+        let _guard = ::TempEnvGuard::new().unwrap();
         let expect = ::result_to_option(env::var("APPDATA"))
                          .map(|dir| PathBuf::new().join(&dir).join(APP_NAME));
         let value = ::AppDir::new(APP_NAME).xdg_dir(::XdgDir::Config);
@@ -193,8 +1105,9 @@ mod tests {
     /// Return `$XDG_CONFIG_HOME/app_name` if `XDG_CONFIG_HOME` is set.
     #[test]
     fn env_config_home() {
-        let xdg_config_home = PathBuf::new().join("/home/s_app_dir/.path/to/xdg_config_home");
-        env::set_var("XDG_CONFIG_HOME", &xdg_config_home);
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        let xdg_config_home = PathBuf::from("/home/s_app_dir/.path/to/xdg_config_home");
+        guard.set("XDG_CONFIG_HOME", &xdg_config_home);
 
         let expect = Some(xdg_config_home.join(APP_NAME));
         let value = ::AppDir::new(APP_NAME).xdg_dir(::XdgDir::Config);
@@ -205,7 +1118,7 @@ mod tests {
     #[cfg(unix)]
     #[test]
     fn default_cache_home() {
-        env::remove_var("XDG_CACHE_HOME");
+        let _guard = ::TempEnvGuard::new().unwrap();
         let expect = env::home_dir().map(|p| p.join(".cache").join(APP_NAME));
         let value = ::AppDir::new(APP_NAME).xdg_dir(::XdgDir::Cache);
         assert_eq!(expect, value);
@@ -214,7 +1127,7 @@ mod tests {
     #[cfg(windows)]
     #[test]
     fn default_cache_home() {
-        env::remove_var("XDG_CACHE_HOME");
+        let _guard = ::TempEnvGuard::new().unwrap();
         let expect = ::result_to_option(env::var("APPDATA"))
                          .map(|dir| PathBuf::new().join(dir).join(APP_NAME));
         let value = ::AppDir::new(APP_NAME).xdg_dir(::XdgDir::Cache);
@@ -224,18 +1137,177 @@ mod tests {
     /// Return `$XDG_CACHE_HOME` if `XDG_CACHE_HOME` is set.
     #[test]
     fn env_cache_home() {
-        let xdg_cache_home = PathBuf::new().join("/home/s_app_dir/.path/to/xdg_cache_home");
-        env::set_var("XDG_CACHE_HOME", &xdg_cache_home);
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        let xdg_cache_home = PathBuf::from("/home/s_app_dir/.path/to/xdg_cache_home");
+        guard.set("XDG_CACHE_HOME", &xdg_cache_home);
 
         let expect = Some(xdg_cache_home.join(APP_NAME));
         let value = ::AppDir::new(APP_NAME).xdg_dir(::XdgDir::Cache);
         assert_eq!(expect, value);
     }
 
+    /// Round-trip every kind through `Display` and `FromStr`.
+    #[test]
+    fn xdg_dir_from_str() {
+        for &xdg in ::XdgDir::all() {
+            assert_eq!(Ok(xdg), xdg.to_string().parse());
+        }
+        assert_eq!(Ok(::XdgDir::Config), "CONFIG".parse());
+        let err = "state".parse::<::XdgDir>().unwrap_err();
+        assert_eq!("state", err.input());
+    }
+
+    /// Serialize kinds by name and fill in defaults when deserializing an `AppDir`.
+    #[cfg(feature="serde")]
+    #[test]
+    fn serde() {
+        use serde_json;
+
+        assert_eq!("\"cache\"", serde_json::to_string(&::XdgDir::Cache).unwrap());
+        let app_dir = ::AppDir::builder(APP_NAME).path_form(::PathForm::Lexical).build();
+        let json = serde_json::to_string(&app_dir).unwrap();
+        assert_eq!(app_dir, serde_json::from_str(&json).unwrap());
+        let value: ::AppDir = serde_json::from_str("{\"app_name\": \"s_app_dir\"}").unwrap();
+        assert_eq!(::AppDir::new(APP_NAME), value);
+    }
+
+    /// Use kinds and app dirs as ordered and hashed map keys.
+    #[test]
+    fn map_keys() {
+        use std::collections::{BTreeSet, HashSet};
+
+        let sorted = ::XdgDir::all().iter().rev().cloned().collect::<BTreeSet<_>>();
+        assert_eq!(::XdgDir::all().to_vec(), sorted.into_iter().collect::<Vec<_>>());
+        let app_dirs = [::AppDir::new("b"), ::AppDir::new("a"), ::AppDir::new("a")];
+        assert_eq!(2, app_dirs.iter().cloned().collect::<HashSet<_>>().len());
+        assert_eq!(Some(&::AppDir::new("a")), app_dirs.iter().min());
+    }
+
+    /// Rename an app dir while keeping its options.
+    #[test]
+    fn app_name() {
+        let mut app_dir = ::AppDir::builder("a").long_paths(true).build();
+        assert_eq!("a", app_dir.app_name());
+        let renamed = app_dir.with_app_name("b");
+        assert_eq!(::AppDir::builder("b").long_paths(true).build(), renamed);
+        app_dir.set_app_name("b");
+        assert_eq!(renamed, app_dir);
+        assert_eq!("b", app_dir.as_ref());
+        assert_eq!("b".to_string(), String::from(app_dir));
+    }
+
+    /// Pair every kind with the same path as `xdg_dir`.
+    #[test]
+    fn all_dirs() {
+        let app_dir = ::AppDir::new(APP_NAME);
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let value = app_dir.all_dirs();
+        let expect = ::XdgDir::all()
+                         .iter()
+                         .map(|&xdg| (xdg, app_dir.xdg_dir(xdg)))
+                         .collect::<Vec<_>>();
+        assert_eq!(4, value.len());
+        assert_eq!(expect, value);
+    }
+
+    /// Return `None` until the directory is created.
+    #[test]
+    fn existing_xdg_dir() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        let base = guard.home().join("cache");
+        guard.set("XDG_CACHE_HOME", &base);
+        let app_dir = ::AppDir::new(APP_NAME);
+
+        assert_eq!(None, app_dir.existing_xdg_dir(::XdgDir::Cache));
+        fs::create_dir_all(base.join(APP_NAME)).unwrap();
+        assert_eq!(Some(base.join(APP_NAME)), app_dir.existing_xdg_dir(::XdgDir::Cache));
+    }
+
+    /// Return `None` or `$HOME/.local/bin` without the app name if `XDG_BIN_HOME` is empty.
+    #[cfg(unix)]
+    #[test]
+    fn default_bin_home() {
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let expect = env::home_dir().map(|p| p.join(".local/bin"));
+        let value = ::AppDir::new(APP_NAME).xdg_dir(::XdgDir::Bin);
+        assert_eq!(expect, value);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn default_bin_home() {
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let expect = ::result_to_option(env::var("APPDATA"))
+                         .map(|dir| PathBuf::new().join(dir).join(APP_NAME).join("bin"));
+        let value = ::AppDir::new(APP_NAME).xdg_dir(::XdgDir::Bin);
+        assert_eq!(expect, value);
+    }
+
+    /// Return `$XDG_BIN_HOME` if `XDG_BIN_HOME` is set.
+    #[test]
+    fn env_bin_home() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        let xdg_bin_home = PathBuf::from("/home/s_app_dir/.path/to/xdg_bin_home");
+        guard.set("XDG_BIN_HOME", &xdg_bin_home);
+
+        let expect = Some(xdg_bin_home);
+        let value = ::AppDir::new(APP_NAME).xdg_dir(::XdgDir::Bin);
+        assert_eq!(expect, value);
+    }
+
+    /// Return the user config dir followed by `$XDG_CONFIG_DIRS/app_name`.
+    #[cfg(unix)]
+    #[test]
+    fn config_search_path() {
+        let mut guard = ::TempEnvGuard::for_app(&::AppDir::new(APP_NAME)).unwrap();
+        guard.set("XDG_CONFIG_DIRS", "/etc/xdg-a:relative:/etc/xdg-b");
+        let value = ::AppDir::new(APP_NAME).config_search_path();
+        let expect = vec![PathBuf::from("/etc/xdg-a").join(APP_NAME),
+                          PathBuf::from("/etc/xdg-b").join(APP_NAME)];
+        assert_eq!(expect, value[1..].to_vec());
+    }
+
+    /// Search the directories of the app's search-path variable after the
+    /// user's.
+    #[test]
+    fn path_var() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        let extra = guard.home().join("extra");
+        let app_dir = ::AppDir::new("s-app-dir");
+        assert_eq!("S_APP_DIR_PATH", app_dir.path_var());
+        assert_eq!("_2D_PATH", ::AppDir::new("2d").path_var());
+        assert_eq!("FOO_BAR_APP", ::variable_name("foo-bar.app"));
+
+        let dirs = env::join_paths([extra.as_path(), "relative".as_ref()]).unwrap();
+        guard.set("S_APP_DIR_PATH", &dirs);
+        assert_eq!(extra, app_dir.config_search_path()[1]);
+        assert_eq!(extra, app_dir.data_search_path()[1]);
+        #[cfg(unix)]
+        assert_eq!(PathBuf::from("/usr/local/share/s-app-dir"), app_dir.data_search_path()[2]);
+    }
+
+    /// Return the user data dir followed by `$XDG_DATA_DIRS/app_name`, or the
+    /// default system dirs if it is unset.
+    #[cfg(unix)]
+    #[test]
+    fn data_search_path() {
+        let app_dir = ::AppDir::new(APP_NAME);
+        let mut guard = ::TempEnvGuard::for_app(&app_dir).unwrap();
+        let expect = vec![guard.home().join(".local/share").join(APP_NAME),
+                          PathBuf::from("/usr/local/share").join(APP_NAME),
+                          PathBuf::from("/usr/share").join(APP_NAME)];
+        assert_eq!(expect, app_dir.data_search_path());
+
+        guard.set("XDG_DATA_DIRS", "/opt/share:relative");
+        assert_eq!(PathBuf::from("/opt/share").join(APP_NAME), app_dir.data_search_path()[1]);
+        assert_eq!(2, app_dir.data_search_path().len());
+    }
+
     /// Return `None` or `$HOME/.app_name`.
     #[cfg(unix)]
     #[test]
     fn user_data_dir() {
+        let _guard = ::TempEnvGuard::new().unwrap();
         let value = ::AppDir::new(APP_NAME).user_data_dir();
         let expect = env::home_dir().map(|p| p.join(".".to_string() + APP_NAME));
         assert_eq!(expect, value);
@@ -244,12 +1316,34 @@ mod tests {
     #[cfg(windows)]
     #[test]
     fn user_data_dir() {
+        let _guard = ::TempEnvGuard::new().unwrap();
         let value = ::AppDir::new(APP_NAME).user_data_dir();
         let expect = ::result_to_option(env::var("APPDATA"))
                          .map(|dir| PathBuf::new().join(dir).join(APP_NAME));
         assert_eq!(expect, value);
     }
 
+    /// Return the data directory for both roaming and local data outside Windows.
+    #[cfg(unix)]
+    #[test]
+    fn data_local_dir() {
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new(APP_NAME);
+        assert_eq!(app_dir.xdg_dir(::XdgDir::Data), app_dir.data_roaming_dir());
+        assert_eq!(app_dir.xdg_dir(::XdgDir::Data), app_dir.data_local_dir());
+    }
+
+    /// Return `%LOCALAPPDATA%\app_name` for local data on Windows.
+    #[cfg(windows)]
+    #[test]
+    fn data_local_dir() {
+        let guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new(APP_NAME);
+        let local = guard.home().join("AppData").join("Local").join(APP_NAME);
+        assert_eq!(app_dir.xdg_dir(::XdgDir::Data), app_dir.data_roaming_dir());
+        assert_eq!(Some(local), app_dir.data_local_dir());
+    }
+
     /// Return path based `std::env::temp_dir()`.
     #[test]
     fn temp_dir() {
@@ -257,4 +1351,19 @@ mod tests {
         let expect = env::temp_dir().join(APP_NAME);
         assert_eq!(expect, value);
     }
+
+    /// Put temp files in the runtime directory if preferred and set.
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn prefer_runtime_dir() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::builder(APP_NAME).prefer_runtime_dir(true).build();
+        assert_eq!(env::temp_dir().join(APP_NAME), app_dir.temp_dir());
+
+        let runtime = guard.home().join("runtime");
+        guard.set("XDG_RUNTIME_DIR", &runtime);
+        assert_eq!(runtime.join(APP_NAME).join("tmp"), app_dir.temp_dir());
+        assert_eq!(app_dir.temp_dir(), app_dir.user_temp_dir());
+        assert_eq!(env::temp_dir().join(APP_NAME), ::AppDir::new(APP_NAME).temp_dir());
+    }
 }
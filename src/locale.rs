@@ -0,0 +1,100 @@
+use std::path::PathBuf;
+
+use {resolve_base_dir, AppDir, Env, XdgDir};
+
+impl AppDir {
+    /// Directories holding gettext message catalogs, in order of precedence:
+    /// `locale` under the user data directory and, on Unix, under each
+    /// `XDG_DATA_DIRS` entry, then `/usr/share/locale`.
+    ///
+    /// These are shared by all apps; catalogs are told apart by file name.
+    pub fn locale_dirs(&self) -> Vec<PathBuf> {
+        let user = resolve_base_dir(XdgDir::Data, self.options(), &Env::capture()).ok();
+        let mut dirs = Vec::new();
+        for base in user.into_iter().chain(system_bases()) {
+            let dir = self.finish(base.join("locale"));
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+        dirs
+    }
+
+    /// The first `<locale dir>/<lang>/LC_MESSAGES/<app_name>.mo` that exists.
+    ///
+    /// Like gettext, `lang` falls back from `de_DE.UTF-8@euro` through
+    /// `de_DE` to `de` in each directory.
+    pub fn find_translation(&self, lang: &str) -> Option<PathBuf> {
+        let file = format!("{}.mo", self.app_name);
+        let langs = lang_fallbacks(lang);
+        self.locale_dirs()
+            .into_iter()
+            .flat_map(|dir| {
+                langs.iter()
+                    .map(|lang| dir.join(lang).join("LC_MESSAGES").join(&file))
+                    .collect::<Vec<_>>()
+            })
+            .find(|path| path.is_file())
+    }
+}
+
+#[cfg(unix)]
+fn system_bases() -> Vec<PathBuf> {
+    use system_data_dirs;
+
+    let mut bases = system_data_dirs();
+    bases.push(PathBuf::from("/usr/share"));
+    bases
+}
+
+#[cfg(not(unix))]
+fn system_bases() -> Vec<PathBuf> {
+    Vec::new()
+}
+
+/// `lang` followed by the less specific names gettext would try.
+fn lang_fallbacks(lang: &str) -> Vec<&str> {
+    let mut langs = vec![lang];
+    let without_variant = lang.split(['.', '@']).next().unwrap_or(lang);
+    let language = without_variant.split('_').next().unwrap_or(without_variant);
+    for &fallback in &[without_variant, language] {
+        if !fallback.is_empty() && !langs.contains(&fallback) {
+            langs.push(fallback);
+        }
+    }
+    langs
+}
+
+#[cfg(test)]
+mod tests {
+    /// Strip the codeset, modifier and territory in turn.
+    #[test]
+    fn lang_fallbacks() {
+        assert_eq!(vec!["de_DE.UTF-8@euro", "de_DE", "de"],
+                   super::lang_fallbacks("de_DE.UTF-8@euro"));
+        assert_eq!(vec!["de"], super::lang_fallbacks("de"));
+    }
+
+    /// Find the catalog for the language's fallback in the user data dir.
+    #[cfg(unix)]
+    #[test]
+    fn find_translation() {
+        use std::fs;
+        use std::path::PathBuf;
+
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        guard.set("XDG_DATA_DIRS", "/opt/share:/usr/share");
+        let app_dir = ::AppDir::new("s_app_dir");
+        let locale = guard.home().join(".local/share/locale");
+        assert_eq!(vec![locale.clone(),
+                        PathBuf::from("/opt/share/locale"),
+                        PathBuf::from("/usr/share/locale")],
+                   app_dir.locale_dirs());
+
+        let catalog = locale.join("pt/LC_MESSAGES/s_app_dir.mo");
+        fs::create_dir_all(catalog.parent().unwrap()).unwrap();
+        fs::write(&catalog, b"").unwrap();
+        assert_eq!(Some(catalog), app_dir.find_translation("pt_BR.UTF-8"));
+        assert_eq!(None, app_dir.find_translation("xx"));
+    }
+}
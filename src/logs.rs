@@ -0,0 +1,41 @@
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use timestamp::Utc;
+use {AppDir, XdgDir};
+
+impl AppDir {
+    /// `logs` in the data directory, beside `crash_dir`.
+    pub fn log_dir(&self) -> Option<PathBuf> {
+        self.xdg_dir(XdgDir::Data).map(|dir| dir.join("logs"))
+    }
+
+    /// The log file for the UTC day of `date`, e.g.
+    /// `logs/foo-2026-10-16.log` in the data directory.
+    pub fn log_file_for(&self, date: SystemTime) -> Option<PathBuf> {
+        let name = format!("{}-{}.log", self.app_name, Utc::from(date).date());
+        self.log_dir().map(|dir| dir.join(name))
+    }
+
+    /// The log file for today, by `log_file_for`.
+    pub fn current_log_file(&self) -> Option<PathBuf> {
+        self.log_file_for(SystemTime::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    /// Name log files by app and UTC date.
+    #[test]
+    fn log_file_for() {
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        let date = UNIX_EPOCH + Duration::from_secs(1_792_108_800 + 86_399);
+        let file = app_dir.log_dir().unwrap().join("s_app_dir-2026-10-16.log");
+        assert_eq!(Some(file), app_dir.log_file_for(date));
+        assert_eq!(app_dir.log_dir(),
+                   app_dir.current_log_file().and_then(|file| file.parent().map(Into::into)));
+    }
+}
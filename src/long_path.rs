@@ -0,0 +1,243 @@
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::path::{self, Path, PathBuf};
+
+/// Longest path, in UTF-16 units and including the terminating null, that
+/// Win32 file APIs accept without the extended-length prefix.
+pub const MAX_PATH: usize = 260;
+
+/// Like `MAX_PATH`, but for directories, which must leave room for an 8.3
+/// file name.
+pub const MAX_DIR_PATH: usize = MAX_PATH - 12;
+
+/// `path` in extended-length (`\\?\`) form, if it is an absolute Windows path.
+///
+/// The prefix turns off all normalization by Windows, so `/` separators and
+/// `.`/`..` segments are resolved here first.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn extended_length(path: &str) -> Option<String> {
+    if path.starts_with(r"\\?\") || path.starts_with(r"\\.\") {
+        return Some(path.to_string());
+    }
+    let path = path.replace('/', "\\");
+    let bytes = path.as_bytes();
+    let (mut extended, rest) = if let Some(unc) = path.strip_prefix(r"\\") {
+        let mut parts = unc.splitn(3, '\\');
+        let (server, share) = match (parts.next(), parts.next()) {
+            (Some(server), Some(share)) if !server.is_empty() && !share.is_empty() => {
+                (server, share)
+            }
+            _ => return None,
+        };
+        (format!(r"\\?\UNC\{}\{}", server, share), parts.next().unwrap_or(""))
+    } else if bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' &&
+              bytes[2] == b'\\' {
+        (format!(r"\\?\{}", &path[..2]), &path[3..])
+    } else {
+        return None;
+    };
+
+    let mut segments = Vec::new();
+    for segment in rest.split('\\') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            s => segments.push(s),
+        }
+    }
+    for segment in segments {
+        extended.push('\\');
+        extended.push_str(segment);
+    }
+    Some(extended)
+}
+
+/// `path` without the extended-length prefix, if it can be written without it.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn strip_extended_length(path: &str) -> Option<String> {
+    if let Some(unc) = path.strip_prefix(r"\\?\UNC\") {
+        return Some(format!(r"\\{}", unc));
+    }
+    path.strip_prefix(r"\\?\").and_then(|rest| {
+        let bytes = rest.as_bytes();
+        if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+            Some(rest.to_string())
+        } else {
+            None
+        }
+    })
+}
+
+/// Drop the extended-length prefix that `fs::canonicalize` adds on Windows,
+/// as long as the path fits in `limit` without it.
+#[cfg(windows)]
+pub fn shorten_if_fits(path: PathBuf, limit: usize) -> PathBuf {
+    let short = match path.to_str().and_then(strip_extended_length) {
+        Some(short) => short,
+        None => return path,
+    };
+    if short.encode_utf16().count() < limit {
+        PathBuf::from(short)
+    } else {
+        path
+    }
+}
+
+#[cfg(not(windows))]
+pub fn shorten_if_fits(path: PathBuf, _: usize) -> PathBuf {
+    path
+}
+
+/// Add the extended-length prefix to `path` if it is at least `limit` long.
+#[cfg(windows)]
+pub fn extend_if_long(path: PathBuf, limit: usize) -> PathBuf {
+    use std::os::windows::ffi::OsStrExt;
+
+    if path.as_os_str().encode_wide().count() < limit {
+        return path;
+    }
+    match path.to_str().and_then(extended_length) {
+        Some(extended) => PathBuf::from(extended),
+        None => path,
+    }
+}
+
+#[cfg(not(windows))]
+pub fn extend_if_long(path: PathBuf, _: usize) -> PathBuf {
+    path
+}
+
+/// A path too long for Win32 file APIs without the extended-length prefix,
+/// returned inside an `io::Error` of kind `InvalidFilename` by
+/// `AppDir::join_file` when long paths are not enabled.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct PathTooLong {
+    path: PathBuf,
+    len: usize,
+}
+
+impl PathTooLong {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The length of the path in UTF-16 units.
+    pub fn len_utf16(&self) -> usize {
+        self.len
+    }
+}
+
+impl Display for PathTooLong {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f,
+               "{}: {} characters long, over the Windows limit of {}; enable \
+                AppDirBuilder::long_paths to use the \\\\?\\ prefix",
+               self.path.display(),
+               self.len,
+               MAX_PATH - 1)
+    }
+}
+
+impl error::Error for PathTooLong {}
+
+/// Fail with `PathTooLong` if `path` needs the extended-length prefix.
+pub fn check_length(path: &Path) -> io::Result<()> {
+    let path_str = path.to_string_lossy();
+    if path_str.starts_with(r"\\?\") {
+        return Ok(());
+    }
+    let len = path_str.encode_utf16().count();
+    if len < MAX_PATH {
+        return Ok(());
+    }
+    let err = PathTooLong {
+        path: path.to_path_buf(),
+        len,
+    };
+    Err(io::Error::new(io::ErrorKind::InvalidFilename, err))
+}
+
+/// `name` as a relative path that is safe to append to any directory,
+/// including one in extended-length form.
+pub fn clean_relative(name: &str) -> io::Result<PathBuf> {
+    check_relative(name)?;
+    Ok(segments(name).collect())
+}
+
+/// Like `clean_relative`, but only check `name`, without allocating.
+pub fn check_relative(name: &str) -> io::Result<()> {
+    let invalid = |reason: &str| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("{}: {}", reason, name))
+    };
+    for segment in segments(name) {
+        match segment {
+            ".." => return Err(invalid("file name escapes its directory")),
+            s if cfg!(windows) && s.contains(|c| "<>:\"|?*".contains(c)) => {
+                return Err(invalid("file name contains a reserved character"))
+            }
+            _ => {}
+        }
+    }
+    if name.starts_with(path::is_separator) || segments(name).next().is_none() {
+        return Err(invalid("not a relative file name"));
+    }
+    Ok(())
+}
+
+fn segments(name: &str) -> impl Iterator<Item = &str> {
+    name.split(path::is_separator).filter(|&s| !s.is_empty() && s != ".")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    /// Prefix drive and UNC paths and normalize them first.
+    #[test]
+    fn extended_length() {
+        assert_eq!(Some(r"\\?\C:\Users\a\c".to_string()),
+                   super::extended_length(r"C:\Users\a\.\b\..\c\"));
+        assert_eq!(Some(r"\\?\C:\Users\a\b".to_string()),
+                   super::extended_length("C:/Users/a/b"));
+        assert_eq!(Some(r"\\?\UNC\server\share\profile".to_string()),
+                   super::extended_length(r"\\server\share\profile"));
+        assert_eq!(Some(r"\\?\C:\x".to_string()), super::extended_length(r"\\?\C:\x"));
+        assert_eq!(None, super::extended_length(r"relative\path"));
+    }
+
+    /// Strip the prefix from drive and UNC paths only.
+    #[test]
+    fn strip_extended_length() {
+        assert_eq!(Some(r"C:\Users".to_string()),
+                   super::strip_extended_length(r"\\?\C:\Users"));
+        assert_eq!(Some(r"\\server\share\profile".to_string()),
+                   super::strip_extended_length(r"\\?\UNC\server\share\profile"));
+        assert_eq!(None, super::strip_extended_length(r"\\?\Volume{0}\x"));
+        assert_eq!(None, super::strip_extended_length(r"C:\Users"));
+    }
+
+    /// Accept plain relative names and reject ones that escape the directory.
+    #[test]
+    fn clean_relative() {
+        assert_eq!(PathBuf::from("a").join("b.toml"),
+                   super::clean_relative("./a//b.toml").unwrap());
+        assert!(super::clean_relative("../b.toml").is_err());
+        assert!(super::clean_relative("/b.toml").is_err());
+        assert!(super::clean_relative(".").is_err());
+    }
+
+    /// Reject paths of `MAX_PATH` units or more, unless already prefixed.
+    #[test]
+    fn check_length() {
+        let long = format!(r"C:\{}", "a".repeat(super::MAX_PATH - 3));
+        assert!(super::check_length(&PathBuf::from(&long[..long.len() - 1])).is_ok());
+        let err = super::check_length(&PathBuf::from(&long)).unwrap_err();
+        assert_eq!(::std::io::ErrorKind::InvalidFilename, err.kind());
+        let inner = err.get_ref().and_then(|err| err.downcast_ref::<super::PathTooLong>());
+        assert_eq!(Some(super::MAX_PATH), inner.map(|inner| inner.len_utf16()));
+        assert!(super::check_length(&PathBuf::from(format!(r"\\?\{}", long))).is_ok());
+    }
+}
@@ -0,0 +1,228 @@
+use std::io;
+use std::path::PathBuf;
+
+use {database, glob};
+use {AppDir, Fs, StdFs, XdgDir};
+
+/// Options for `AppDir::migrate_legacy`.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Migration {
+    rules: Vec<(String, XdgDir)>,
+    dry_run: bool,
+    symlink_compat: bool,
+}
+
+impl Migration {
+    /// Move everything into the data directory.
+    pub fn new() -> Migration {
+        Migration::default()
+    }
+
+    /// Move the entries whose names match the glob `pattern` into the `xdg`
+    /// directory instead. The first matching rule wins. Database sidecars
+    /// such as `main.db-wal` follow their database.
+    pub fn rule(mut self, pattern: &str, xdg: XdgDir) -> Migration {
+        self.rules.push((pattern.to_string(), xdg));
+        self
+    }
+
+    /// Only report the moves, without touching anything.
+    pub fn dry_run(mut self, enabled: bool) -> Migration {
+        self.dry_run = enabled;
+        self
+    }
+
+    /// Leave a symlink from the legacy directory to the data directory, so
+    /// older versions of the app still find their data. Ignored on Windows.
+    pub fn symlink_compat(mut self, enabled: bool) -> Migration {
+        self.symlink_compat = enabled;
+        self
+    }
+
+    fn target(&self, name: &str) -> XdgDir {
+        self.rules
+            .iter()
+            .find(|&(pattern, _)| glob::matches(pattern, database::main_file(name)))
+            .map_or(XdgDir::Data, |&(_, xdg)| xdg)
+    }
+}
+
+impl AppDir {
+    /// Move the contents of the legacy `user_data_dir` (`~/.app_name`) into
+    /// the XDG directories chosen by `migration`, then remove it. Returns
+    /// each move as `(from, to)`.
+    ///
+    /// Nothing is moved if any target already exists, and a legacy directory
+    /// that is missing, a symlink or the data directory itself has nothing
+    /// to migrate. Entries are renamed, so they must be on the same
+    /// filesystem as their targets.
+    ///
+    /// If a move fails, or the legacy directory cannot be removed, the moves
+    /// already made are renamed back before the error is returned. Should
+    /// that fail too, the error lists the entries left in their new place.
+    pub fn migrate_legacy(&self, migration: &Migration) -> io::Result<Vec<(PathBuf, PathBuf)>> {
+        self.migrate_legacy_with_fs(&StdFs, migration)
+    }
+
+    /// Like `migrate_legacy`, but go through `fs`. The compatibility symlink
+    /// is still created directly.
+    pub fn migrate_legacy_with_fs<F>(&self,
+                                     fs: &F,
+                                     migration: &Migration)
+                                     -> io::Result<Vec<(PathBuf, PathBuf)>>
+        where F: Fs + ?Sized
+    {
+        let legacy = match self.user_data_dir() {
+            Some(legacy) => legacy,
+            None => return Ok(Vec::new()),
+        };
+        let data = self.try_xdg_dir(XdgDir::Data)?;
+        let is_dir = fs.symlink_metadata(&legacy).is_ok_and(|m| m.is_dir);
+        if !is_dir || legacy == data {
+            return Ok(Vec::new());
+        }
+
+        let mut moves = Vec::new();
+        for from in fs.read_dir(&legacy)? {
+            let name = from.file_name().expect("read_dir yields named entries").to_os_string();
+            let xdg = migration.target(&name.to_string_lossy());
+            let to = self.try_xdg_dir(xdg)?.join(&name);
+            if fs.symlink_metadata(&to).is_ok() {
+                return Err(io::Error::new(io::ErrorKind::AlreadyExists,
+                                          format!("already exists: {}", to.display())));
+            }
+            moves.push((from, to));
+        }
+        moves.sort();
+        if migration.dry_run {
+            return Ok(moves);
+        }
+
+        for (done, (from, to)) in moves.iter().enumerate() {
+            let moved = match to.parent() {
+                Some(parent) => fs.create_dir_all(parent),
+                None => Ok(()),
+            };
+            if let Err(err) = moved.and_then(|()| fs.rename(from, to)) {
+                return Err(roll_back(fs, &moves[..done], err));
+            }
+        }
+        if let Err(err) = fs.remove_dir(&legacy) {
+            return Err(roll_back(fs, &moves, err));
+        }
+        if migration.symlink_compat {
+            #[cfg(unix)]
+            {
+                fs.create_dir_all(&data)?;
+                ::std::os::unix::fs::symlink(&data, &legacy)?;
+            }
+        }
+        Ok(moves)
+    }
+}
+
+/// Undo `moves`, newest first, and return `err`, naming the entries that
+/// could not be moved back.
+fn roll_back<F>(fs: &F, moves: &[(PathBuf, PathBuf)], err: io::Error) -> io::Error
+    where F: Fs + ?Sized
+{
+    let stuck = moves.iter()
+        .rev()
+        .filter(|&(from, to)| fs.rename(to, from).is_err())
+        .map(|(_, to)| to.display().to_string())
+        .collect::<Vec<_>>();
+    if stuck.is_empty() {
+        return err;
+    }
+    io::Error::new(err.kind(),
+                   format!("{}; the migration is incomplete, still moved: {}",
+                           err,
+                           stuck.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    /// Move config files by rule and the rest into the data directory,
+    /// leaving a symlink behind.
+    #[cfg(unix)]
+    #[test]
+    fn migrate_legacy() {
+        use std::fs;
+
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        let legacy = app_dir.user_data_dir().unwrap();
+        fs::create_dir_all(legacy.join("db")).unwrap();
+        fs::write(legacy.join("config.toml"), "").unwrap();
+        fs::write(legacy.join("index.sqlite"), "").unwrap();
+        fs::write(legacy.join("index.sqlite-wal"), "").unwrap();
+        let migration = ::Migration::new()
+            .rule("*.toml", ::XdgDir::Config)
+            .rule("*.sqlite", ::XdgDir::Cache)
+            .symlink_compat(true);
+
+        let data = app_dir.xdg_dir(::XdgDir::Data).unwrap();
+        let config = app_dir.xdg_dir(::XdgDir::Config).unwrap();
+        let cache = app_dir.xdg_dir(::XdgDir::Cache).unwrap();
+        let expect = vec![(legacy.join("config.toml"), config.join("config.toml")),
+                          (legacy.join("db"), data.join("db")),
+                          (legacy.join("index.sqlite"), cache.join("index.sqlite")),
+                          (legacy.join("index.sqlite-wal"), cache.join("index.sqlite-wal"))];
+        assert_eq!(expect, app_dir.migrate_legacy(&migration.clone().dry_run(true)).unwrap());
+        assert!(legacy.join("db").is_dir());
+
+        assert_eq!(expect, app_dir.migrate_legacy(&migration).unwrap());
+        assert!(config.join("config.toml").is_file());
+        assert!(data.join("db").is_dir());
+        assert_eq!(data, fs::read_link(&legacy).unwrap());
+        assert!(app_dir.migrate_legacy(&migration).unwrap().is_empty());
+    }
+
+    /// Refuse to overwrite anything.
+    #[cfg(unix)]
+    #[test]
+    fn migrate_legacy_conflict() {
+        use std::fs;
+
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        let legacy = app_dir.user_data_dir().unwrap();
+        fs::create_dir_all(&legacy).unwrap();
+        fs::write(legacy.join("state"), "old").unwrap();
+        let data = app_dir.ensure_xdg_dir(::XdgDir::Data).unwrap();
+        fs::write(data.join("state"), "new").unwrap();
+
+        assert!(app_dir.migrate_legacy(&::Migration::new()).is_err());
+        assert_eq!("old", fs::read_to_string(legacy.join("state")).unwrap());
+    }
+
+    /// Move everything back when a move fails partway.
+    #[cfg(unix)]
+    #[test]
+    fn migrate_legacy_roll_back() {
+        use std::fs;
+        use std::io;
+
+        use Fs;
+
+        // The real filesystem, except that `locked` cannot be moved.
+        let locked = ::filesystem::PatchedFs::default().rename(|from, to| {
+            if from.ends_with("locked") {
+                return Err(io::ErrorKind::PermissionDenied.into());
+            }
+            ::StdFs.rename(from, to)
+        });
+
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        let legacy = app_dir.user_data_dir().unwrap();
+        fs::create_dir_all(&legacy).unwrap();
+        fs::write(legacy.join("a"), "").unwrap();
+        fs::write(legacy.join("locked"), "").unwrap();
+
+        let err = app_dir.migrate_legacy_with_fs(&locked, &::Migration::new()).unwrap_err();
+        assert_eq!(io::ErrorKind::PermissionDenied, err.kind());
+        assert!(legacy.join("a").is_file());
+        assert!(!app_dir.xdg_dir(::XdgDir::Data).unwrap().join("a").exists());
+    }
+}
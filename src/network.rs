@@ -0,0 +1,122 @@
+use std::path::Path;
+
+/// Whether `path` is on a network share, such as a roaming profile
+/// redirected to a UNC path or an NFS/SMB mount.
+///
+/// Paths that don't exist yet are judged by their longest existing
+/// ancestor. Returns `false` when this can't be determined.
+pub fn is_network_path<P>(path: P) -> bool
+    where P: AsRef<Path>
+{
+    let path = path.as_ref();
+    if is_unc(&path.to_string_lossy()) {
+        return true;
+    }
+    path.ancestors().find(|p| p.exists()).is_some_and(is_remote_fs)
+}
+
+/// Whether `path` is a UNC path (`\\server\share` or `\\?\UNC\server\share`).
+fn is_unc(path: &str) -> bool {
+    if !cfg!(windows) {
+        return false;
+    }
+    let path = path.replace('/', "\\");
+    if path.starts_with(r"\\?\") || path.starts_with(r"\\.\") {
+        return path[4..].get(..4).is_some_and(|s| s.eq_ignore_ascii_case(r"UNC\"));
+    }
+    path.starts_with(r"\\")
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn is_remote_fs(path: &Path) -> bool {
+    use libc;
+    use std::ffi::CString;
+    use std::mem;
+    use std::os::unix::ffi::OsStrExt;
+
+    const NFS_SUPER_MAGIC: u32 = 0x6969;
+    const SMB_SUPER_MAGIC: u32 = 0x517b;
+    const CIFS_MAGIC_NUMBER: u32 = 0xff53_4d42;
+    const SMB2_MAGIC_NUMBER: u32 = 0xfe53_4d42;
+    const CODA_SUPER_MAGIC: u32 = 0x7375_7245;
+    const AFS_SUPER_MAGIC: u32 = 0x5346_414f;
+    const NCP_SUPER_MAGIC: u32 = 0x564c;
+
+    let path = match CString::new(path.as_os_str().as_bytes()) {
+        Ok(path) => path,
+        Err(_) => return false,
+    };
+    let mut buf: libc::statfs = unsafe { mem::zeroed() };
+    if unsafe { libc::statfs(path.as_ptr(), &mut buf) } != 0 {
+        return false;
+    }
+    matches!(buf.f_type as u32,
+             NFS_SUPER_MAGIC | SMB_SUPER_MAGIC | CIFS_MAGIC_NUMBER | SMB2_MAGIC_NUMBER |
+             CODA_SUPER_MAGIC | AFS_SUPER_MAGIC | NCP_SUPER_MAGIC)
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+fn is_remote_fs(path: &Path) -> bool {
+    use libc;
+    use std::ffi::{CStr, CString};
+    use std::mem;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = match CString::new(path.as_os_str().as_bytes()) {
+        Ok(path) => path,
+        Err(_) => return false,
+    };
+    let mut buf: libc::statfs = unsafe { mem::zeroed() };
+    if unsafe { libc::statfs(path.as_ptr(), &mut buf) } != 0 {
+        return false;
+    }
+    let fs_type = unsafe { CStr::from_ptr(buf.f_fstypename.as_ptr()) };
+    matches!(fs_type.to_bytes(),
+             b"nfs" | b"smbfs" | b"afpfs" | b"webdav" | b"cifs")
+}
+
+#[cfg(windows)]
+fn is_remote_fs(path: &Path) -> bool {
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::Component;
+    use windows_sys::Win32::Storage::FileSystem::GetDriveTypeW;
+
+    const DRIVE_REMOTE: u32 = 4;
+
+    let root = match path.components().next() {
+        Some(Component::Prefix(prefix)) => {
+            let mut root = prefix.as_os_str().to_os_string();
+            root.push("\\");
+            root
+        }
+        _ => return false,
+    };
+    let root = root.encode_wide().chain(Some(0)).collect::<Vec<u16>>();
+    unsafe { GetDriveTypeW(root.as_ptr()) == DRIVE_REMOTE }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos",
+              target_os = "ios", target_os = "freebsd", windows)))]
+fn is_remote_fs(_: &Path) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    /// Treat UNC paths as network paths on Windows only.
+    #[test]
+    fn is_unc() {
+        assert_eq!(cfg!(windows), super::is_unc(r"\\server\share\profile"));
+        assert_eq!(cfg!(windows), super::is_unc(r"\\?\UNC\server\share\profile"));
+        assert!(!super::is_unc(r"\\?\C:\Users"));
+        assert!(!super::is_unc(r"C:\Users"));
+    }
+
+    /// Treat the local temp directory as local.
+    #[test]
+    fn is_network_path_local() {
+        assert!(!::is_network_path(env::temp_dir().join("s_app_dir-missing")));
+    }
+}
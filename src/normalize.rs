@@ -0,0 +1,253 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+#[cfg(feature="serde")]
+use serde::{Deserialize, Serialize};
+
+use long_path;
+
+/// How resolved paths are normalized before being returned.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(rename_all = "snake_case"))]
+pub enum PathForm {
+    /// Return paths exactly as built from the environment.
+    #[default]
+    AsIs,
+    /// Remove `.` and resolve `..` without touching the filesystem.
+    ///
+    /// Fast and works for paths that don't exist yet, but `..` after a
+    /// symlink is resolved against the link rather than its target.
+    Lexical,
+    /// Resolve symlinks with `fs::canonicalize`.
+    ///
+    /// For paths that don't exist yet, the longest existing ancestor is
+    /// canonicalized and the remaining components are appended lexically.
+    /// On Windows, the `\\?\` prefix `fs::canonicalize` adds is dropped again
+    /// unless the path needs it, so UNC profiles come back as `\\server\share`.
+    Canonical,
+}
+
+impl PathForm {
+    /// Normalize `path` into this form.
+    pub fn apply(&self, path: PathBuf) -> PathBuf {
+        match *self {
+            PathForm::AsIs => path,
+            PathForm::Lexical => lexical(&path),
+            PathForm::Canonical => canonical(&path),
+        }
+    }
+}
+
+/// What to do, with `PathForm::Canonical`, when a resolved directory lies
+/// behind a symlink or, on Windows, a junction or other reparse point such
+/// as a OneDrive folder, where `fs::canonicalize` may fail or lead
+/// somewhere unexpected.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(rename_all = "snake_case"))]
+pub enum LinkPolicy {
+    /// Resolve through them.
+    #[default]
+    Follow,
+    /// Fail with `ResolveError::Link`.
+    Reject,
+    /// Resolve through them, logging a warning for each with the `log`
+    /// feature. Without it, this is the same as `Follow`; list the links
+    /// with `AppDir::links` instead.
+    Report,
+}
+
+/// Check `path` against `policy`, failing with the outermost link if it
+/// rejects links.
+pub fn check_links(policy: LinkPolicy, path: &Path) -> Result<(), PathBuf> {
+    if policy == LinkPolicy::Follow {
+        return Ok(());
+    }
+    for link in links(path) {
+        if policy == LinkPolicy::Reject {
+            return Err(link);
+        }
+        warn_link(&link, path);
+    }
+    Ok(())
+}
+
+/// The existing links among `path` and its ancestors, outermost first.
+/// `path` is taken as it is, since `..` after a link leads out of its
+/// target rather than back to where the link is.
+pub fn links(path: &Path) -> Vec<PathBuf> {
+    let mut links = path.ancestors()
+        .filter(|ancestor| fs::symlink_metadata(ancestor).is_ok_and(|m| is_link(&m)))
+        .map(Path::to_path_buf)
+        .collect::<Vec<_>>();
+    links.reverse();
+    links
+}
+
+#[cfg(windows)]
+fn is_link(metadata: &fs::Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+
+    const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x400;
+    metadata.file_attributes() & FILE_ATTRIBUTE_REPARSE_POINT != 0
+}
+
+#[cfg(not(windows))]
+fn is_link(metadata: &fs::Metadata) -> bool {
+    metadata.file_type().is_symlink()
+}
+
+#[cfg(feature="log")]
+fn warn_link(link: &Path, path: &Path) {
+    warn!("{} is reached through the link {}", path.display(), link.display());
+}
+
+#[cfg(not(feature="log"))]
+fn warn_link(_: &Path, _: &Path) {}
+
+/// `dirs` with trailing separators and `.` segments removed, keeping only
+/// the first of any duplicates. `..` is kept, since it may follow a
+/// symlink.
+pub fn search_path<I>(dirs: I) -> Vec<PathBuf>
+    where I: IntoIterator<Item = PathBuf>
+{
+    let mut search_path = Vec::<PathBuf>::new();
+    for dir in dirs {
+        let dir = dir.components().collect::<PathBuf>();
+        if !search_path.contains(&dir) {
+            search_path.push(dir);
+        }
+    }
+    search_path
+}
+
+fn lexical(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                match normalized.components().next_back() {
+                    Some(Component::Normal(_)) => {
+                        normalized.pop();
+                    }
+                    Some(Component::RootDir) | Some(Component::Prefix(_)) => {}
+                    _ => normalized.push(".."),
+                }
+            }
+            c => normalized.push(c.as_os_str()),
+        }
+    }
+    normalized
+}
+
+/// Canonicalize the longest existing prefix of `path` as is, so `..` after
+/// a link goes to the target's parent, and only clean up the missing tail
+/// lexically.
+fn canonical(path: &Path) -> PathBuf {
+    let mut existing = path;
+    loop {
+        if let Ok(resolved) = fs::canonicalize(existing) {
+            let resolved = match path.strip_prefix(existing) {
+                Ok(rest) if !rest.as_os_str().is_empty() => lexical(&resolved.join(rest)),
+                _ => resolved,
+            };
+            return long_path::shorten_if_fits(resolved, long_path::MAX_DIR_PATH);
+        }
+        match existing.parent() {
+            Some(parent) => existing = parent,
+            None => return lexical(path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::PathForm;
+
+    /// Remove `.` and resolve `..` lexically.
+    #[test]
+    fn lexical() {
+        let value = PathForm::Lexical.apply(PathBuf::from("/a/./b/../c/"));
+        assert_eq!(PathBuf::from("/a/c"), value);
+        let value = PathForm::Lexical.apply(PathBuf::from("/../a"));
+        assert_eq!(PathBuf::from("/a"), value);
+        let value = PathForm::Lexical.apply(PathBuf::from("../a/.."));
+        assert_eq!(PathBuf::from(".."), value);
+    }
+
+    /// Reject or report links only when asked to.
+    #[cfg(unix)]
+    #[test]
+    fn check_links() {
+        use std::os::unix::fs::symlink;
+
+        use super::LinkPolicy;
+
+        let guard = ::TempEnvGuard::new().unwrap();
+        fs::create_dir(guard.home().join("target")).unwrap();
+        let link = guard.home().join("link");
+        symlink(guard.home().join("target"), &link).unwrap();
+        let dir = link.join("a/b");
+        assert_eq!(Err(link.clone()), super::check_links(LinkPolicy::Reject, &dir));
+        assert_eq!(Ok(()), super::check_links(LinkPolicy::Report, &dir));
+        assert_eq!(Ok(()), super::check_links(LinkPolicy::Follow, &dir));
+        assert_eq!(Ok(()), super::check_links(LinkPolicy::Reject, &guard.home().join("a")));
+
+        let mut guard = guard;
+        guard.set("XDG_CACHE_HOME", &dir);
+        let app_dir = ::AppDir::builder("s_app_dir")
+            .path_form(::PathForm::Canonical)
+            .link_policy(LinkPolicy::Reject)
+            .build();
+        let err = ::ResolveError::Link(link.clone(), ::EnvReport::default());
+        assert_eq!(Err(err), app_dir.try_xdg_dir(::XdgDir::Cache));
+        let as_is = ::AppDir::builder("s_app_dir").link_policy(LinkPolicy::Reject).build();
+        assert_eq!(Ok(dir.join("s_app_dir")), as_is.try_xdg_dir(::XdgDir::Cache));
+        assert_eq!(Ok(vec![link.clone()]), as_is.links(::XdgDir::Cache));
+
+        fs::create_dir(guard.home().join("target/b")).unwrap();
+        let escape = link.join("../target/b");
+        assert_eq!(vec![link.clone()], super::links(&escape));
+        assert_eq!(Err(link), super::check_links(LinkPolicy::Reject, &escape));
+    }
+
+    /// Clean up and deduplicate search paths, keeping the first occurrence.
+    #[cfg(unix)]
+    #[test]
+    fn search_path() {
+        let dirs = ["/etc/xdg/a/", "/usr/./share/a", "/etc/xdg//a", "/opt/../a", "/usr/share/a"];
+        let value = super::search_path(dirs.iter().map(PathBuf::from));
+        let expect = vec![PathBuf::from("/etc/xdg/a"),
+                          PathBuf::from("/usr/share/a"),
+                          PathBuf::from("/opt/../a")];
+        assert_eq!(expect, value);
+    }
+
+    /// Resolve symlinks in the existing part of a path, `..` after a link
+    /// included, and keep the missing tail.
+    #[cfg(unix)]
+    #[test]
+    fn canonical() {
+        use std::os::unix::fs::symlink;
+
+        let base = env::temp_dir().join("s_app_dir-canonical");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(base.join("deep/target")).unwrap();
+        symlink(base.join("deep/target"), base.join("link")).unwrap();
+
+        let value = PathForm::Canonical.apply(base.join("link").join("missing").join("."));
+        let target = fs::canonicalize(base.join("deep/target")).unwrap();
+        assert_eq!(target.join("missing"), value);
+        let value = PathForm::Canonical.apply(base.join("link/.."));
+        assert_eq!(fs::canonicalize(base.join("deep")).unwrap(), value);
+        let value = PathForm::Canonical.apply(base.join("link/../missing/../a"));
+        assert_eq!(fs::canonicalize(base.join("deep")).unwrap().join("a"), value);
+        let _ = fs::remove_dir_all(&base);
+    }
+}
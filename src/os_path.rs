@@ -0,0 +1,52 @@
+#[cfg(unix)]
+use std::ffi::CString;
+use std::ffi::OsString;
+
+use {AppDir, XdgDir};
+
+impl AppDir {
+    /// `xdg_dir` as an `OsString`, keeping paths that are not valid Unicode
+    /// intact.
+    pub fn xdg_dir_os_string(&self, xdg: XdgDir) -> Option<OsString> {
+        self.xdg_dir(xdg).map(|dir| dir.into_os_string())
+    }
+
+    /// `xdg_dir` as the NUL-terminated bytes C libraries take, unchanged
+    /// even if they are not UTF-8.
+    #[cfg(unix)]
+    pub fn xdg_dir_c_string(&self, xdg: XdgDir) -> Option<CString> {
+        use std::os::unix::ffi::OsStringExt;
+
+        CString::new(self.xdg_dir_os_string(xdg)?.into_vec()).ok()
+    }
+
+    /// `xdg_dir` as the NUL-terminated UTF-16 Win32 APIs take, including
+    /// unpaired surrogates.
+    #[cfg(windows)]
+    pub fn xdg_dir_wide(&self, xdg: XdgDir) -> Option<Vec<u16>> {
+        use std::os::windows::ffi::OsStrExt;
+
+        let dir = self.xdg_dir_os_string(xdg)?;
+        Some(dir.encode_wide().chain(Some(0)).collect())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    /// Keep non-UTF-8 bytes in every form.
+    #[test]
+    fn xdg_dir_c_string() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        let home = guard.home().join(OsStr::from_bytes(b"home\xff"));
+        guard.set("HOME", &home);
+        let app_dir = ::AppDir::new("s_app_dir");
+        let dir = home.join(".config/s_app_dir");
+        assert_eq!(Some(dir.clone().into_os_string()),
+                   app_dir.xdg_dir_os_string(::XdgDir::Config));
+        let c_string = app_dir.xdg_dir_c_string(::XdgDir::Config).unwrap();
+        assert_eq!(dir.as_os_str().as_bytes(), c_string.as_bytes());
+    }
+}
@@ -0,0 +1,86 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::mem;
+use std::path::PathBuf;
+
+use {AppDir, XdgDir};
+
+thread_local! {
+    static OVERRIDES: RefCell<BTreeMap<XdgDir, PathBuf>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+impl AppDir {
+    /// Run `f` with the base directory of each kind in `overrides` replaced,
+    /// as if `XDG_CONFIG_HOME` and friends were set, without touching the
+    /// process environment.
+    ///
+    /// Overrides apply to the current thread only and nest; the previous
+    /// ones are restored when `f` returns or panics.
+    pub fn with_overrides<I, F, T>(overrides: I, f: F) -> T
+        where I: IntoIterator<Item = (XdgDir, PathBuf)>,
+              F: FnOnce() -> T
+    {
+        let saved = OVERRIDES.with(|current| {
+            let saved = current.borrow().clone();
+            current.borrow_mut().extend(overrides);
+            saved
+        });
+        let _restore = Restore(saved);
+        f()
+    }
+}
+
+/// Puts back the overrides that were active before `with_overrides`.
+struct Restore(BTreeMap<XdgDir, PathBuf>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let saved = mem::take(&mut self.0);
+        // The thread-local may already be gone if the thread is exiting.
+        let _ = OVERRIDES.try_with(|current| *current.borrow_mut() = saved);
+    }
+}
+
+/// The overridden base directory of `xdg` on this thread, if any.
+pub fn get(xdg: XdgDir) -> Option<PathBuf> {
+    OVERRIDES.try_with(|current| current.borrow().get(&xdg).cloned()).ok().and_then(|dir| dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic;
+    use std::path::PathBuf;
+    use std::thread;
+
+    /// Redirect resolution inside the closure only, on this thread only.
+    #[test]
+    fn with_overrides() {
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        let before = app_dir.xdg_dir(::XdgDir::Config);
+        let base = PathBuf::from("/tmp/s_app_dir-with_overrides");
+
+        ::AppDir::with_overrides(vec![(::XdgDir::Config, base.clone())], || {
+            assert_eq!(Some(base.join("s_app_dir")), app_dir.xdg_dir(::XdgDir::Config));
+            ::AppDir::with_overrides(vec![(::XdgDir::Cache, base.join("cache"))], || {
+                assert_eq!(Some(base.join("s_app_dir")), app_dir.xdg_dir(::XdgDir::Config));
+                assert_eq!(Some(base.join("cache").join("s_app_dir")),
+                           app_dir.xdg_dir(::XdgDir::Cache));
+            });
+            assert_eq!(None, super::get(::XdgDir::Cache));
+            assert_eq!(None, thread::spawn(|| super::get(::XdgDir::Config)).join().unwrap());
+        });
+        assert_eq!(before, app_dir.xdg_dir(::XdgDir::Config));
+    }
+
+    /// Restore the previous overrides when the closure panics.
+    #[test]
+    fn with_overrides_panic() {
+        let base = PathBuf::from("/tmp/s_app_dir-with_overrides_panic");
+        let result = panic::catch_unwind(|| {
+            ::AppDir::with_overrides(vec![(::XdgDir::Data, base)], || panic!("test"))
+        });
+        assert!(result.is_err());
+        assert_eq!(None, super::get(::XdgDir::Data));
+    }
+}
@@ -0,0 +1,173 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use {AppDir, XdgDir};
+
+impl AppDir {
+    /// `partial` in the cache directory, where interrupted downloads wait to
+    /// be resumed.
+    pub fn partial_dir(&self) -> Option<PathBuf> {
+        self.xdg_dir(XdgDir::Cache).map(|dir| dir.join("partial"))
+    }
+
+    /// Open the partial download of `url` for appending, creating it if
+    /// missing. It is named after a hash of `url`, so a download restarted
+    /// later picks up the same file; ask the server for the bytes from
+    /// `PartialDownload::offset` on.
+    ///
+    /// The URL is kept in a `.url` file next to it, so another URL with the
+    /// same hash gets a file of its own instead of appending to this one.
+    pub fn partial_download(&self, url: &str) -> io::Result<PartialDownload> {
+        let cache = self.try_xdg_dir(XdgDir::Cache)?;
+        let dir = cache.join("partial");
+        fs::create_dir_all(&dir)?;
+        let hash = ::fnv1a(url.as_bytes());
+        for n in 0.. {
+            let stem = match n {
+                0 => format!("{:016x}", hash),
+                n => format!("{:016x}-{}", hash, n),
+            };
+            let (path, url_path) = (dir.join(format!("{}.part", stem)), dir.join(stem + ".url"));
+            match claim_url(&url_path, url)? {
+                Slot::Taken => continue,
+                Slot::New => {
+                    File::create(&path)?;
+                }
+                Slot::Ours => {}
+            }
+            let file = OpenOptions::new().create(true).append(true).open(&path)?;
+            let offset = file.metadata()?.len();
+            return Ok(PartialDownload {
+                path,
+                url_path,
+                cache,
+                app_dir: self.clone(),
+                file,
+                offset,
+            });
+        }
+        unreachable!("the slots are unbounded")
+    }
+}
+
+/// Whether the `.url` file of a slot names the URL.
+enum Slot {
+    /// It does.
+    Ours,
+    /// It did not exist, and now does.
+    New,
+    /// It names another URL.
+    Taken,
+}
+
+fn claim_url(url_path: &Path, url: &str) -> io::Result<Slot> {
+    match OpenOptions::new().write(true).create_new(true).open(url_path) {
+        Ok(mut file) => {
+            file.write_all(url.as_bytes())?;
+            return Ok(Slot::New);
+        }
+        Err(ref err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+        Err(err) => return Err(err),
+    }
+    if fs::read(url_path)? == url.as_bytes() {
+        Ok(Slot::Ours)
+    } else {
+        Ok(Slot::Taken)
+    }
+}
+
+/// A resumable download, returned by `AppDir::partial_download`. Unlike a
+/// staged update it is kept when dropped, so it can be resumed.
+#[derive(Debug)]
+pub struct PartialDownload {
+    path: PathBuf,
+    url_path: PathBuf,
+    cache: PathBuf,
+    app_dir: AppDir,
+    file: File,
+    offset: u64,
+}
+
+impl PartialDownload {
+    /// The `.part` file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// How many bytes were downloaded so far.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Move the finished download to `name` in the cache directory, e.g.
+    /// `models/base.bin`, creating its parent directories, and return its
+    /// path. An existing file there is replaced. Like `AppDir::join_file`,
+    /// `name` may not be absolute or contain `..`.
+    pub fn promote(self, name: &str) -> io::Result<PathBuf> {
+        let PartialDownload { path, url_path, cache, app_dir, file, .. } = self;
+        let target = app_dir.join_file(&cache, name)?;
+        // Close the file first, since Windows cannot rename open files.
+        file.sync_all()?;
+        drop(file);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(&path, &target)?;
+        fs::remove_file(&url_path)?;
+        Ok(target)
+    }
+}
+
+impl Write for PartialDownload {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.file.write(buf)?;
+        self.offset += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Write;
+
+    /// Resume downloads by URL and promote them into the cache.
+    #[test]
+    fn partial_download() {
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        let url = "https://example.com/base.bin";
+        let mut part = app_dir.partial_download(url).unwrap();
+        assert_eq!(0, part.offset());
+        part.write_all(b"abc").unwrap();
+        drop(part);
+
+        let mut part = app_dir.partial_download(url).unwrap();
+        assert_eq!(3, part.offset());
+        assert_eq!(app_dir.partial_dir().as_deref(), part.path().parent());
+        assert_ne!(part.path(), app_dir.partial_download("https://example.com/b").unwrap().path());
+        part.write_all(b"def").unwrap();
+        assert_eq!(6, part.offset());
+
+        let url_file = part.path().with_extension("url");
+        fs::write(&url_file, "https://example.com/other.bin").unwrap();
+        let other = app_dir.partial_download(url).unwrap();
+        assert_eq!(0, other.offset());
+        assert_ne!(part.path(), other.path());
+        fs::write(&url_file, url).unwrap();
+
+        let part = part.promote("../escape.bin").unwrap_err();
+        assert_eq!(::std::io::ErrorKind::InvalidInput, part.kind());
+        let part = app_dir.partial_download(url).unwrap();
+        assert_eq!(6, part.offset());
+        let path = part.promote("models/base.bin").unwrap();
+        assert_eq!(app_dir.xdg_dir(::XdgDir::Cache).unwrap().join("models/base.bin"), path);
+        assert_eq!("abcdef", fs::read_to_string(path).unwrap());
+        assert_eq!(0, app_dir.partial_download(url).unwrap().offset());
+    }
+}
@@ -0,0 +1,69 @@
+use std::io;
+use std::path::PathBuf;
+
+use system::require_writable;
+use {write_atomic, AppDir};
+
+impl AppDir {
+    /// `/usr/share/polkit-1/actions`, where polkit reads action definitions
+    /// (`.policy` files) for privileged helpers.
+    pub fn polkit_actions_dir(&self) -> PathBuf {
+        self.finish(PathBuf::from("/usr/share/polkit-1/actions"))
+    }
+
+    /// `/etc/polkit-1/rules.d`, where the administrator's local rules live.
+    /// They take precedence over the packaged rules in
+    /// `/usr/share/polkit-1/rules.d`.
+    pub fn polkit_rules_dir(&self) -> PathBuf {
+        self.finish(PathBuf::from("/etc/polkit-1/rules.d"))
+    }
+
+    /// Whether installing into `polkit_actions_dir()` needs root.
+    pub fn polkit_requires_elevation(&self) -> bool {
+        require_writable(&self.polkit_actions_dir()).is_err()
+    }
+
+    /// Write the action definition `xml` into `polkit_actions_dir()` as
+    /// `<id>.policy`, where `id` is the action id prefix, e.g.
+    /// `org.example.app`, and return its path.
+    ///
+    /// Without root, this fails with an error of kind `PermissionDenied`
+    /// wrapping an `ElevationRequired`.
+    pub fn install_polkit_action(&self, id: &str, xml: &[u8]) -> io::Result<PathBuf> {
+        self.install_polkit_file(self.polkit_actions_dir(), &format!("{}.policy", id), xml)
+    }
+
+    /// Write the JavaScript rules file `name`, e.g. `50-app.rules`, into
+    /// `polkit_rules_dir()`. Fails like `install_polkit_action` without root.
+    pub fn install_polkit_rules(&self, name: &str, rules: &[u8]) -> io::Result<PathBuf> {
+        self.install_polkit_file(self.polkit_rules_dir(), name, rules)
+    }
+
+    fn install_polkit_file(&self,
+                           dir: PathBuf,
+                           name: &str,
+                           contents: &[u8])
+                           -> io::Result<PathBuf> {
+        let file = self.join_file(&dir, name)?;
+        require_writable(&dir)?;
+        write_atomic(&file, contents)?;
+        Ok(file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::path::PathBuf;
+
+    /// Use the fixed system paths, and reject names that would leave them
+    /// whether or not the process is root.
+    #[test]
+    fn polkit_dirs() {
+        let app_dir = ::AppDir::new("s_app_dir");
+        assert_eq!(PathBuf::from("/usr/share/polkit-1/actions"), app_dir.polkit_actions_dir());
+        assert_eq!(PathBuf::from("/etc/polkit-1/rules.d"), app_dir.polkit_rules_dir());
+        let err = app_dir.install_polkit_rules("../escape.rules", b"").unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+    }
+}
@@ -0,0 +1,91 @@
+#[cfg(feature="plist")]
+use std::fs;
+use std::path::PathBuf;
+
+#[cfg(feature="plist")]
+use plist;
+#[cfg(feature="plist")]
+use serde::de::DeserializeOwned;
+#[cfg(feature="plist")]
+use serde::Serialize;
+
+#[cfg(feature="plist")]
+use {write_atomic, ConfigError};
+#[cfg(target_os = "macos")]
+use AppDir;
+
+#[cfg(target_os = "macos")]
+impl AppDir {
+    /// `~/Library/Preferences`, where macOS keeps each app's preferences in
+    /// a `<bundle-id>.plist` file. Shared by all apps.
+    pub fn preferences_dir(&self) -> Option<PathBuf> {
+        use std::env;
+
+        env::home_dir().map(|home| self.finish(home.join("Library").join("Preferences")))
+    }
+
+    /// The preferences file of the app with `bundle_id`.
+    pub fn preferences_file(&self, bundle_id: &str) -> Option<PathBuf> {
+        self.preferences_dir().map(|dir| dir.join(format!("{}.plist", bundle_id)))
+    }
+
+    /// Read the preferences of the app with `bundle_id`.
+    #[cfg(feature="plist")]
+    pub fn load_preferences<T>(&self, bundle_id: &str) -> Result<T, ConfigError>
+        where T: DeserializeOwned
+    {
+        load_plist(self.preferences_file(bundle_id).ok_or(ConfigError::NoConfigDir)?)
+    }
+
+    /// Replace the preferences of the app with `bundle_id`.
+    ///
+    /// macOS caches preferences in `cfprefsd`, so apps using `NSUserDefaults`
+    /// for the same domain may not see the change until they restart.
+    #[cfg(feature="plist")]
+    pub fn save_preferences<T>(&self, bundle_id: &str, value: &T) -> Result<(), ConfigError>
+        where T: Serialize
+    {
+        save_plist(self.preferences_file(bundle_id).ok_or(ConfigError::NoConfigDir)?, value)
+    }
+}
+
+/// Read a property list, in XML or binary form.
+#[cfg(feature="plist")]
+pub fn load_plist<T>(path: PathBuf) -> Result<T, ConfigError>
+    where T: DeserializeOwned
+{
+    let contents = match fs::read(&path) {
+        Ok(contents) => contents,
+        Err(err) => return Err(ConfigError::Io(path, err)),
+    };
+    plist::from_bytes(&contents).map_err(|err| ConfigError::Parse(path, err.into()))
+}
+
+/// Write a property list in XML form, atomically.
+#[cfg(feature="plist")]
+pub fn save_plist<T>(path: PathBuf, value: &T) -> Result<(), ConfigError>
+    where T: Serialize
+{
+    let mut contents = Vec::new();
+    if let Err(err) = plist::to_writer_xml(&mut contents, value) {
+        return Err(ConfigError::Serialize(path, err.into()));
+    }
+    write_atomic(&path, &contents).map_err(ConfigError::Write)
+}
+
+#[cfg(all(test, feature="plist"))]
+mod tests {
+    use std::collections::BTreeMap;
+
+    /// Read back what was written.
+    #[test]
+    fn save_plist() {
+        let guard = ::TempEnvGuard::new().unwrap();
+        let path = guard.home().join("com.example.app.plist");
+        let mut value = BTreeMap::new();
+        value.insert("theme".to_string(), "dark".to_string());
+
+        super::save_plist(path.clone(), &value).unwrap();
+        assert_eq!(value, super::load_plist::<BTreeMap<String, String>>(path).unwrap());
+    }
+}
@@ -0,0 +1,164 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use config::{ConfigFormat, ConfigMatch, ConfigScope};
+use AppDir;
+
+/// Options for `AppDir::project_config_with`.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ProjectSearch {
+    stop_at_vcs_root: bool,
+    root_config_dir: bool,
+}
+
+impl ProjectSearch {
+    /// Walk up to the filesystem root, looking only for the files
+    /// `project_config` does.
+    pub fn new() -> ProjectSearch {
+        ProjectSearch::default()
+    }
+
+    /// Stop at the first directory containing `.git` or `.hg`, so a config
+    /// outside the repository is never picked up.
+    pub fn stop_at_vcs_root(mut self, enabled: bool) -> ProjectSearch {
+        self.stop_at_vcs_root = enabled;
+        self
+    }
+
+    /// In a directory containing `.git` or `.hg`, also look for
+    /// `.config/app_name/config.*`, after the other project files.
+    pub fn root_config_dir(mut self, enabled: bool) -> ProjectSearch {
+        self.root_config_dir = enabled;
+        self
+    }
+}
+
+impl AppDir {
+    /// The project-local config for the directory `start_dir`: the nearest
+    /// `.app_name/config.*` or `.app_namerc` in `start_dir` or one of its
+    /// ancestors. It overrides the user config, so it has
+    /// `ConfigScope::Project`.
+    ///
+    /// In one directory, `.app_name/config.*` wins over `.app_namerc`, and
+    /// the extensions of `ConfigFormat::all()` are preferred, in order, over
+    /// others.
+    pub fn project_config<P>(&self, start_dir: P) -> Option<ConfigMatch>
+        where P: AsRef<Path>
+    {
+        self.project_config_with(start_dir, ProjectSearch::new())
+    }
+
+    /// Like `project_config`, but with the repository-aware options of
+    /// `search`.
+    pub fn project_config_with<P>(&self, start_dir: P, search: ProjectSearch) -> Option<ConfigMatch>
+        where P: AsRef<Path>
+    {
+        for dir in start_dir.as_ref().ancestors() {
+            let vcs_root = is_vcs_root(dir);
+            let found = self.project_config_in(dir).or_else(|| {
+                if vcs_root && search.root_config_dir {
+                    config_file_in(&dir.join(".config").join(&self.app_name))
+                } else {
+                    None
+                }
+            });
+            if let Some(path) = found {
+                return Some(ConfigMatch { path, scope: ConfigScope::Project });
+            }
+            if vcs_root && search.stop_at_vcs_root {
+                break;
+            }
+        }
+        None
+    }
+
+    fn project_config_in(&self, dir: &Path) -> Option<PathBuf> {
+        let hidden = dir.join(format!(".{}", self.app_name));
+        config_file_in(&hidden).or_else(|| {
+            let rc = dir.join(format!(".{}rc", self.app_name));
+            if rc.is_file() { Some(rc) } else { None }
+        })
+    }
+}
+
+/// Whether `dir` is the root of a Git or Mercurial working tree. `.git` may
+/// be a file, in worktrees and submodules.
+fn is_vcs_root(dir: &Path) -> bool {
+    dir.join(".git").exists() || dir.join(".hg").is_dir()
+}
+
+/// The `config.*` file in `dir`, preferring the known formats.
+fn config_file_in(dir: &Path) -> Option<PathBuf> {
+    let mut candidates = fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_stem().is_some_and(|stem| stem == "config") &&
+            path.extension().is_some() && path.is_file()
+        })
+        .collect::<Vec<_>>();
+    let rank = |path: &PathBuf| {
+        ConfigFormat::all()
+            .iter()
+            .position(|format| path.extension().is_some_and(|ext| ext == format.extension()))
+            .unwrap_or(ConfigFormat::all().len())
+    };
+    candidates.sort_by(|a, b| rank(a).cmp(&rank(b)).then_with(|| a.cmp(b)));
+    candidates.into_iter().next()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    /// Find the nearest project config, preferring `.app/config.*` over the
+    /// rc file and known formats over others.
+    #[test]
+    fn project_config() {
+        let guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        let project = guard.home().join("project");
+        let nested = project.join("src").join("bin");
+        fs::create_dir_all(&nested).unwrap();
+        assert_eq!(None, app_dir.project_config(&nested));
+
+        fs::write(project.join(".s_app_dirrc"), "").unwrap();
+        let found = app_dir.project_config(&nested).unwrap();
+        assert_eq!(project.join(".s_app_dirrc"), found.path);
+        assert_eq!(::ConfigScope::Project, found.scope);
+
+        fs::create_dir_all(project.join(".s_app_dir")).unwrap();
+        fs::write(project.join(".s_app_dir").join("config.ini"), "").unwrap();
+        fs::write(project.join(".s_app_dir").join("config.yaml"), "").unwrap();
+        assert_eq!(project.join(".s_app_dir").join("config.yaml"),
+                   app_dir.project_config(&nested).unwrap().path);
+
+        fs::write(nested.join(".s_app_dirrc"), "").unwrap();
+        assert_eq!(nested.join(".s_app_dirrc"), app_dir.project_config(&nested).unwrap().path);
+    }
+
+    /// Stop at the repository root and read its `.config/app_name` directory.
+    #[test]
+    fn project_config_with() {
+        let guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        let repo = guard.home().join("repo");
+        let nested = repo.join("crates").join("core");
+        fs::create_dir_all(&nested).unwrap();
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::write(guard.home().join(".s_app_dirrc"), "").unwrap();
+
+        let stop = ::ProjectSearch::new().stop_at_vcs_root(true);
+        assert_eq!(guard.home().join(".s_app_dirrc"),
+                   app_dir.project_config(&nested).unwrap().path);
+        assert_eq!(None, app_dir.project_config_with(&nested, stop));
+
+        let config = repo.join(".config").join("s_app_dir");
+        fs::create_dir_all(&config).unwrap();
+        fs::write(config.join("config.toml"), "").unwrap();
+        assert_eq!(None, app_dir.project_config_with(&nested, stop));
+        assert_eq!(config.join("config.toml"),
+                   app_dir.project_config_with(&nested, stop.root_config_dir(true)).unwrap().path);
+    }
+}
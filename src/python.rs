@@ -0,0 +1,114 @@
+//! The Python module `s_app_dir`, built into the cdylib with the `python`
+//! feature, e.g. by maturin.
+//!
+//! ```python
+//! from s_app_dir import AppDir
+//!
+//! app_dir = AppDir("foo")
+//! config = app_dir.ensure_xdg_dir("config")
+//! ```
+
+use std::path::PathBuf;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use {AppDir, XdgDir};
+
+/// `AppDir`, as the Python class `s_app_dir.AppDir`. Paths are returned as
+/// `pathlib.Path`, or `None` if they cannot be resolved.
+#[pyclass(name = "AppDir", module = "s_app_dir", frozen)]
+pub struct PyAppDir {
+    app_dir: AppDir,
+}
+
+#[pymethods]
+impl PyAppDir {
+    #[new]
+    fn new(app_name: &str) -> PyAppDir {
+        PyAppDir { app_dir: AppDir::new(app_name) }
+    }
+
+    #[getter]
+    fn app_name(&self) -> &str {
+        self.app_dir.app_name()
+    }
+
+    /// The directory of `kind`: `"data"`, `"config"`, `"cache"` or `"bin"`.
+    fn xdg_dir(&self, kind: &str) -> PyResult<Option<PathBuf>> {
+        Ok(self.app_dir.xdg_dir(parse_kind(kind)?))
+    }
+
+    fn data_dir(&self) -> Option<PathBuf> {
+        self.app_dir.xdg_dir(XdgDir::Data)
+    }
+
+    fn config_dir(&self) -> Option<PathBuf> {
+        self.app_dir.xdg_dir(XdgDir::Config)
+    }
+
+    fn cache_dir(&self) -> Option<PathBuf> {
+        self.app_dir.xdg_dir(XdgDir::Cache)
+    }
+
+    fn bin_dir(&self) -> Option<PathBuf> {
+        self.app_dir.xdg_dir(XdgDir::Bin)
+    }
+
+    fn temp_dir(&self) -> PathBuf {
+        self.app_dir.temp_dir()
+    }
+
+    /// Create the directory of `kind` if missing and return it; raises
+    /// `OSError` on failure.
+    fn ensure_xdg_dir(&self, kind: &str) -> PyResult<PathBuf> {
+        Ok(self.app_dir.ensure_xdg_dir(parse_kind(kind)?)?)
+    }
+
+    /// Like `ensure_xdg_dir`, but give the directory exactly `mode`, e.g.
+    /// `0o700`.
+    fn ensure_xdg_dir_with_mode(&self, kind: &str, mode: u32) -> PyResult<PathBuf> {
+        Ok(self.app_dir.ensure_xdg_dir_with_mode(parse_kind(kind)?, mode)?)
+    }
+
+    fn ensure_temp_dir(&self) -> PyResult<PathBuf> {
+        Ok(self.app_dir.ensure_temp_dir()?)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("AppDir({:?})", self.app_dir.app_name())
+    }
+}
+
+fn parse_kind(kind: &str) -> PyResult<XdgDir> {
+    kind.parse().map_err(|err: ::ParseXdgDirError| PyValueError::new_err(err.to_string()))
+}
+
+#[pymodule]
+fn s_app_dir(m: &Bound<PyModule>) -> PyResult<()> {
+    m.add_class::<PyAppDir>()
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::prelude::*;
+
+    /// Resolve the same paths from Python as from Rust.
+    #[test]
+    fn app_dir() {
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        Python::initialize();
+        Python::attach(|py| {
+            let class = py.get_type::<super::PyAppDir>();
+            let py_app_dir = class.call1(("s_app_dir",)).unwrap();
+            let config = py_app_dir.call_method1("xdg_dir", ("config",)).unwrap();
+            assert_eq!(app_dir.xdg_dir(::XdgDir::Config),
+                       config.extract::<Option<::std::path::PathBuf>>().unwrap());
+            assert!(py_app_dir.call_method1("xdg_dir", ("nowhere",)).is_err());
+
+            let data = py_app_dir.call_method1("ensure_xdg_dir", ("data",)).unwrap();
+            assert!(data.extract::<::std::path::PathBuf>().unwrap().is_dir());
+        });
+    }
+}
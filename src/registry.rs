@@ -0,0 +1,90 @@
+use std::ffi::{OsStr, OsString};
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+use std::path::PathBuf;
+use std::ptr;
+
+use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+use windows_sys::Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_SZ};
+
+use XdgDir;
+
+fn wide(s: &str) -> Vec<u16> {
+    OsStr::new(s).encode_wide().chain(Some(0)).collect()
+}
+
+/// The directory of kind `xdg` set under
+/// `HKCU\Software\<organization>\<app_name>\Directories`, if any.
+///
+/// Values are named after the kind (`Config`, `Data`, ...) and may be
+/// `REG_SZ` or `REG_EXPAND_SZ`; the latter have environment variables
+/// expanded by `RegGetValueW`, which then reports them as `REG_SZ`. Asking
+/// for `REG_EXPAND_SZ` as well, without `RRF_NOEXPAND`, is rejected.
+pub fn directory(organization: Option<&str>, app_name: &str, xdg: XdgDir) -> Option<PathBuf> {
+    let subkey = match organization {
+        Some(organization) => format!(r"Software\{}\{}\Directories", organization, app_name),
+        None => format!(r"Software\{}\Directories", app_name),
+    };
+    let (subkey, value) = (wide(&subkey), wide(xdg.as_str()));
+    let flags = RRF_RT_REG_SZ;
+
+    let mut len = 0u32;
+    let status = unsafe {
+        RegGetValueW(HKEY_CURRENT_USER,
+                     subkey.as_ptr(),
+                     value.as_ptr(),
+                     flags,
+                     ptr::null_mut(),
+                     ptr::null_mut(),
+                     &mut len)
+    };
+    if status != ERROR_SUCCESS {
+        return None;
+    }
+    let mut buf = vec![0u16; (len as usize).div_ceil(2)];
+    let status = unsafe {
+        RegGetValueW(HKEY_CURRENT_USER,
+                     subkey.as_ptr(),
+                     value.as_ptr(),
+                     flags,
+                     ptr::null_mut(),
+                     buf.as_mut_ptr() as *mut _,
+                     &mut len)
+    };
+    if status != ERROR_SUCCESS {
+        return None;
+    }
+    let end = buf.iter().position(|&c| c == 0).unwrap_or(buf.len());
+    if end == 0 {
+        return None;
+    }
+    Some(PathBuf::from(OsString::from_wide(&buf[..end])))
+}
+
+/// Set the directory of kind `xdg` for `app_name`, without an organization.
+#[cfg(test)]
+pub fn set_directory(app_name: &str, xdg: XdgDir, dir: &::std::path::Path) {
+    use windows_sys::Win32::System::Registry::{RegSetKeyValueW, REG_SZ};
+
+    let subkey = wide(&format!(r"Software\{}\Directories", app_name));
+    let data = dir.as_os_str().encode_wide().chain(Some(0)).collect::<Vec<_>>();
+    let status = unsafe {
+        RegSetKeyValueW(HKEY_CURRENT_USER,
+                        subkey.as_ptr(),
+                        wide(xdg.as_str()).as_ptr(),
+                        REG_SZ,
+                        data.as_ptr() as *const _,
+                        (data.len() * 2) as u32)
+    };
+    assert_eq!(ERROR_SUCCESS, status);
+}
+
+/// Remove the key `set_directory` created for `app_name`.
+#[cfg(test)]
+pub fn remove_app(app_name: &str) {
+    use windows_sys::Win32::System::Registry::RegDeleteTreeW;
+
+    let subkey = wide(&format!(r"Software\{}", app_name));
+    unsafe {
+        RegDeleteTreeW(HKEY_CURRENT_USER, subkey.as_ptr());
+    }
+}
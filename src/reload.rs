@@ -0,0 +1,96 @@
+use arc_swap::ArcSwap;
+use std::error;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use config::{self, ConfigError};
+use watch::{self, ConfigWatcher};
+use AppDir;
+
+/// A config value that is reloaded whenever its file changes.
+pub struct ReloadableConfig<T> {
+    current: Arc<ArcSwap<T>>,
+    watcher: ConfigWatcher,
+}
+
+impl<T> ReloadableConfig<T> {
+    /// The latest successfully parsed value.
+    pub fn get(&self) -> Arc<T> {
+        self.current.load_full()
+    }
+
+    /// The config file being watched.
+    pub fn path(&self) -> &Path {
+        self.watcher.path()
+    }
+}
+
+impl AppDir {
+    /// Load the config file `name` with `parse` and keep it up to date.
+    ///
+    /// If a later edit fails to parse, the previous value is kept.
+    pub fn reloadable_config<T, F, E>(&self,
+                                      name: &str,
+                                      parse: F)
+                                      -> Result<ReloadableConfig<T>, ConfigError>
+        where T: Send + Sync + 'static,
+              F: Fn(&str) -> Result<T, E> + Send + 'static,
+              E: Into<Box<dyn error::Error + Send + Sync>>
+    {
+        reload_file(self.find_config(name)?, parse)
+    }
+}
+
+fn reload_file<T, F, E>(path: PathBuf, parse: F) -> Result<ReloadableConfig<T>, ConfigError>
+    where T: Send + Sync + 'static,
+          F: Fn(&str) -> Result<T, E> + Send + 'static,
+          E: Into<Box<dyn error::Error + Send + Sync>>
+{
+    let current = Arc::new(ArcSwap::from_pointee(config::load_file(path.clone(), &parse)?));
+
+    let shared = current.clone();
+    let watcher = watch::watch_file(path, move |p| {
+                      if let Ok(value) = config::load_file(p.to_path_buf(), &parse) {
+                          shared.store(Arc::new(value));
+                      }
+                  })
+                      .map_err(ConfigError::Watch)?;
+
+    Ok(ReloadableConfig {
+        current,
+        watcher,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// Pick up a valid edit and keep the last value after an invalid one.
+    #[test]
+    fn reload_file() {
+        let base = env::temp_dir().join("s_app_dir-reload_file");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(&base).unwrap();
+        let path = base.join("config");
+        fs::write(&path, "1").unwrap();
+
+        let config = super::reload_file(path.clone(), |s| s.trim().parse::<u32>()).unwrap();
+        assert_eq!(1, *config.get());
+
+        fs::write(&path, "2").unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while *config.get() != 2 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(2, *config.get());
+
+        fs::write(&path, "two").unwrap();
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(2, *config.get());
+        let _ = fs::remove_dir_all(&base);
+    }
+}
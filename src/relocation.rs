@@ -0,0 +1,134 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use {expand_tilde, AppDir, XdgDir};
+
+/// The relocation file in the app's config directory.
+const FILE_NAME: &str = "dirs.toml";
+
+/// The relocations in the `dirs.toml` of `app_dir`, or none if it has no
+/// such file.
+///
+/// The file is found through `app_dir` as it resolves before relocation,
+/// and the config directory itself cannot be relocated, so the file can
+/// never redirect its own lookup.
+pub fn load(app_dir: &AppDir) -> io::Result<BTreeMap<XdgDir, PathBuf>> {
+    let file = app_dir.try_xdg_dir(XdgDir::Config)?.join(FILE_NAME);
+    let text = match fs::read_to_string(&file) {
+        Ok(text) => text,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(err) => return Err(err),
+    };
+    parse(&text).map_err(|err| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", file.display(), err))
+    })
+}
+
+/// Parse lines such as `cache = "/mnt/big/cache/foo"`, the subset of TOML
+/// the file needs. Paths must be absolute once a leading `~` is expanded.
+fn parse(text: &str) -> Result<BTreeMap<XdgDir, PathBuf>, String> {
+    let mut relocations = BTreeMap::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fail = |message: &str| format!("line {}: {}", number + 1, message);
+        let (key, value) = line.split_once('=').ok_or_else(|| fail("expected kind = \"path\""))?;
+        let xdg = key.trim().parse::<XdgDir>().map_err(|err| fail(&err.to_string()))?;
+        if xdg == XdgDir::Config {
+            return Err(fail("the config directory holds dirs.toml and cannot be relocated"));
+        }
+        let value = string(value.trim()).ok_or_else(|| fail("expected a quoted path"))?;
+        let dir = expand_tilde(&value).map_err(|err| fail(&err.to_string()))?;
+        if !dir.is_absolute() {
+            return Err(fail("not an absolute path"));
+        }
+        if relocations.insert(xdg, dir).is_some() {
+            return Err(fail(&format!("{} is relocated twice", xdg)));
+        }
+    }
+    Ok(relocations)
+}
+
+/// The TOML basic (`"..."`) or literal (`'...'`) string `value`, which may
+/// be followed by a comment.
+fn string(value: &str) -> Option<String> {
+    let mut chars = value.chars();
+    let quote = chars.next().filter(|&c| c == '"' || c == '\'')?;
+    let mut string = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c == quote => {
+                let rest = chars.as_str().trim_start();
+                return Some(string).filter(|_| rest.is_empty() || rest.starts_with('#'));
+            }
+            '\\' if quote == '"' => {
+                string.push(match chars.next()? {
+                    '\\' => '\\',
+                    '"' => '"',
+                    't' => '\t',
+                    'n' => '\n',
+                    _ => return None,
+                })
+            }
+            c => string.push(c),
+        }
+    }
+    None
+}
+
+#[cfg(feature="log")]
+pub fn warn_invalid(err: &io::Error) {
+    warn!("ignoring the relocation file: {}", err);
+}
+
+#[cfg(not(feature="log"))]
+pub fn warn_invalid(_: &io::Error) {}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// Parse quoted paths, comments and escapes, and reject the rest.
+    #[test]
+    fn parse() {
+        let relocations = super::parse("# Caches on the big disk\n\
+                                        cache = \"/mnt/big/cache\" # fast\n\
+                                        \n\
+                                        data = '/mnt/data\\x'\n")
+            .unwrap();
+        assert_eq!(Some(&PathBuf::from("/mnt/big/cache")), relocations.get(&::XdgDir::Cache));
+        assert_eq!(Some(&PathBuf::from("/mnt/data\\x")), relocations.get(&::XdgDir::Data));
+
+        assert!(super::parse("config = \"/mnt/config\"").is_err());
+        assert!(super::parse("cache = \"relative\"").is_err());
+        assert!(super::parse("cache = /mnt/cache").is_err());
+        assert!(super::parse("cache = \"/a\"\ncache = \"/b\"").is_err());
+        assert!(super::parse("[dirs]").is_err());
+    }
+
+    /// Relocate directories listed in `dirs.toml` only when enabled.
+    #[test]
+    fn relocation_file() {
+        let guard = ::TempEnvGuard::new().unwrap();
+        let config = ::AppDir::new("s_app_dir").ensure_xdg_dir(::XdgDir::Config).unwrap();
+        let cache = guard.home().join("big/cache");
+        fs::write(config.join("dirs.toml"), format!("cache = {:?}\n", cache)).unwrap();
+
+        let app_dir = ::AppDir::builder("s_app_dir").relocation_file(true).build();
+        assert_eq!(Some(cache), app_dir.xdg_dir(::XdgDir::Cache));
+        assert_eq!(::AppDir::new("s_app_dir").xdg_dir(::XdgDir::Data),
+                   app_dir.xdg_dir(::XdgDir::Data));
+        assert_eq!(::AppDir::new("s_app_dir"),
+                   ::AppDir::builder("s_app_dir").relocation_file(false).build());
+
+        fs::write(config.join("dirs.toml"), "config = \"/elsewhere\"\n").unwrap();
+        assert!(::AppDir::builder("s_app_dir").relocation_file(true).try_build().is_err());
+        assert_eq!(::AppDir::new("s_app_dir"),
+                   ::AppDir::builder("s_app_dir").relocation_file(true).build());
+    }
+}
@@ -0,0 +1,163 @@
+use std::cmp::Ordering;
+use std::fmt::{self, Debug, Formatter};
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use s_app_dir_core::Platform;
+
+use {AppDir, Env, ResolveError, XdgDir};
+
+/// Resolves the directories of an `AppDir`, set with
+/// `AppDirBuilder::resolver`. The path form and long-path settings of the
+/// `AppDir` are applied to the result afterwards.
+///
+/// To extend the default resolution, e.g. with telemetry or policy checks,
+/// delegate to `PlatformResolver`; calling `AppDir::xdg_dir` on `app_dir`
+/// would recurse.
+pub trait PathResolver: Send + Sync {
+    fn resolve(&self, app_dir: &AppDir, xdg: XdgDir) -> Result<PathBuf, ResolveError>;
+}
+
+/// The resolution `AppDir` uses by default: `AppDir::with_overrides`, the
+/// Windows registry, fallback chains, then the rules of the current
+/// platform.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct PlatformResolver;
+
+impl PathResolver for PlatformResolver {
+    fn resolve(&self, app_dir: &AppDir, xdg: XdgDir) -> Result<PathBuf, ResolveError> {
+        app_dir.resolve_platform(xdg, &Env::capture_for(app_dir))
+    }
+}
+
+/// Only the XDG rules: the variable, or else the directory under the home
+/// directory, on any platform.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct XdgResolver;
+
+impl PathResolver for XdgResolver {
+    fn resolve(&self, app_dir: &AppDir, xdg: XdgDir) -> Result<PathBuf, ResolveError> {
+        rules(app_dir, xdg, Platform::Unix)
+    }
+}
+
+/// Only the Windows rules: the XDG variable, or else `%APPDATA%`, on any
+/// platform.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct AppDataResolver;
+
+impl PathResolver for AppDataResolver {
+    fn resolve(&self, app_dir: &AppDir, xdg: XdgDir) -> Result<PathBuf, ResolveError> {
+        rules(app_dir, xdg, Platform::Windows)
+    }
+}
+
+fn rules(app_dir: &AppDir,
+         xdg: XdgDir,
+         platform: Platform)
+         -> Result<PathBuf, ResolveError> {
+    let env = Env::capture();
+    s_app_dir_core::app_dir(xdg.kind(), &app_dir.app_name, platform, app_dir.options(), &env)
+        .map_err(|err| env.resolve_error(err))
+}
+
+/// The custom resolver of an `AppDir`, if any. `AppDir`s compare equal
+/// only if they share the same one.
+#[derive(Clone, Default)]
+pub struct Resolver(Option<Arc<dyn PathResolver>>);
+
+impl Resolver {
+    pub fn new<R>(resolver: R) -> Resolver
+        where R: PathResolver + 'static
+    {
+        Resolver(Some(Arc::new(resolver)))
+    }
+
+    pub fn get(&self) -> Option<&dyn PathResolver> {
+        self.0.as_deref()
+    }
+
+    fn address(&self) -> usize {
+        self.0.as_ref().map_or(0, |resolver| Arc::as_ptr(resolver) as *const () as usize)
+    }
+}
+
+impl Debug for Resolver {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.0 {
+            Some(_) => f.write_str("Custom"),
+            None => f.write_str("Platform"),
+        }
+    }
+}
+
+impl PartialEq for Resolver {
+    fn eq(&self, other: &Resolver) -> bool {
+        self.address() == other.address()
+    }
+}
+
+impl Eq for Resolver {}
+
+impl PartialOrd for Resolver {
+    fn partial_cmp(&self, other: &Resolver) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Resolver {
+    fn cmp(&self, other: &Resolver) -> Ordering {
+        self.address().cmp(&other.address())
+    }
+}
+
+impl Hash for Resolver {
+    fn hash<H>(&self, state: &mut H)
+        where H: Hasher
+    {
+        self.address().hash(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use {AppDir, PathResolver, PlatformResolver, ResolveError, XdgDir, XdgResolver};
+
+    struct Counting(Arc<AtomicUsize>);
+
+    impl PathResolver for Counting {
+        fn resolve(&self, app_dir: &AppDir, xdg: XdgDir) -> Result<PathBuf, ResolveError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            PlatformResolver.resolve(app_dir, xdg)
+        }
+    }
+
+    /// Delegate resolution to a custom resolver that wraps the default.
+    #[test]
+    fn custom() {
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app_dir = AppDir::builder("s_app_dir").resolver(Counting(calls.clone())).build();
+        assert_eq!(AppDir::new("s_app_dir").xdg_dir(XdgDir::Config),
+                   app_dir.xdg_dir(XdgDir::Config));
+        assert_eq!(AppDir::new("s_app_dir").resolve_all(), app_dir.resolve_all());
+        assert_eq!(5, calls.load(Ordering::SeqCst));
+        assert_eq!(app_dir, app_dir.clone());
+        assert!(app_dir != AppDir::new("s_app_dir"));
+    }
+
+    /// Apply the XDG rules with `XdgResolver`.
+    #[test]
+    fn xdg() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        guard.remove("XDG_CACHE_HOME");
+        let app_dir = AppDir::builder("s_app_dir").resolver(XdgResolver).build();
+        assert_eq!(Some(guard.home().join(".cache").join("s_app_dir")),
+                   app_dir.xdg_dir(XdgDir::Cache));
+    }
+}
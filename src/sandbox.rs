@@ -0,0 +1,66 @@
+use std::env;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use AppDir;
+
+/// The `Data` directory of the App Sandbox container the process runs in,
+/// on macOS. Always `None` elsewhere.
+///
+/// Inside a container, `HOME` already points here, and every directory is
+/// resolved within it; XDG variables pointing outside it are ignored, since
+/// the sandbox would deny access anyway.
+pub fn sandbox_container() -> Option<PathBuf> {
+    if !cfg!(target_os = "macos") {
+        return None;
+    }
+    let id = env::var_os("APP_SANDBOX_CONTAINER_ID")?;
+    container_of(&env::home_dir()?, &id)
+}
+
+/// The container of `id` for the user whose home, or container, is `home`.
+fn container_of(home: &Path, id: &OsStr) -> Option<PathBuf> {
+    if id.is_empty() {
+        return None;
+    }
+    let suffix = Path::new("Library").join("Containers").join(id).join("Data");
+    if home.ends_with(&suffix) {
+        Some(home.to_path_buf())
+    } else {
+        Some(home.join(suffix))
+    }
+}
+
+impl AppDir {
+    /// Whether directories are resolved within an App Sandbox container.
+    pub fn is_sandboxed(&self) -> bool {
+        sandbox_container().is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsStr;
+    use std::path::Path;
+
+    /// Accept a home that already is the container.
+    #[test]
+    fn container_of() {
+        let container = Path::new("/Users/a/Library/Containers/com.example.app/Data");
+        let id = OsStr::new("com.example.app");
+        assert_eq!(Some(container.to_path_buf()),
+                   super::container_of(Path::new("/Users/a"), id));
+        assert_eq!(Some(container.to_path_buf()), super::container_of(container, id));
+        assert_eq!(None, super::container_of(Path::new("/Users/a"), OsStr::new("")));
+    }
+
+    /// Never report a container outside macOS.
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn sandbox_container() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        guard.set("APP_SANDBOX_CONTAINER_ID", "com.example.app");
+        assert_eq!(None, ::sandbox_container());
+        assert!(!::AppDir::new("s_app_dir").is_sandboxed());
+    }
+}
@@ -0,0 +1,23 @@
+use std::path::PathBuf;
+
+use objc2_foundation::{NSSearchPathDirectory, NSSearchPathDomainMask,
+                       NSSearchPathForDirectoriesInDomains};
+
+use XdgDir;
+
+/// The base directory Foundation reports for `xdg` in the user domain:
+/// `Application Support` for data and config, `Caches` for the cache.
+///
+/// Unlike building the path from `HOME`, this follows relocated home
+/// folders and the App Sandbox container.
+pub fn base_dir(xdg: XdgDir) -> Option<PathBuf> {
+    let directory = match xdg {
+        XdgDir::Data | XdgDir::Config => NSSearchPathDirectory::ApplicationSupportDirectory,
+        XdgDir::Cache => NSSearchPathDirectory::CachesDirectory,
+        XdgDir::Bin => return None,
+    };
+    let paths = NSSearchPathForDirectoriesInDomains(directory,
+                                                    NSSearchPathDomainMask::UserDomainMask,
+                                                    true);
+    paths.firstObject().map(|path| PathBuf::from(path.to_string()))
+}
@@ -0,0 +1,221 @@
+#[cfg(unix)]
+use std::ffi::CString;
+use std::fs::File;
+use std::io;
+#[cfg(unix)]
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::path::{Path, PathBuf};
+
+#[cfg(not(unix))]
+use ensure::create_dir_with_mode;
+#[cfg(unix)]
+use harden::InsecureDir;
+use {AppDir, XdgDir};
+
+impl AppDir {
+    /// Like `ensure_xdg_dir_with_mode`, but safe against symlinks another
+    /// user plants along the way, for directories holding secrets.
+    ///
+    /// On Unix each component is opened relative to the previous one with
+    /// `openat` and `O_NOFOLLOW`, so nothing can redirect the walk between
+    /// steps. Symlinks owned by the current user or root, such as a dotfile
+    /// manager's `~/.config`, are still followed; any other fails with
+    /// `InsecureDir::ForeignSymlink`. Paths with `..` are rejected.
+    pub fn ensure_xdg_dir_secure(&self, xdg: XdgDir, mode: u32) -> io::Result<PathBuf> {
+        let dir = self.try_xdg_dir(xdg)?;
+        create_dir_secure(&dir, Some(mode))?;
+        Ok(dir)
+    }
+
+    /// Create the file `name` in the `xdg` directory and open it for
+    /// writing, in one step: missing directories are created as in
+    /// `ensure_xdg_dir_secure`, without changing the mode of existing ones,
+    /// and the file is created with `O_EXCL | O_NOFOLLOW` relative to its
+    /// directory, readable by the user only. Fails with
+    /// `io::ErrorKind::AlreadyExists` if the file, or a symlink in its
+    /// place, already exists, so nothing can be redirected between the
+    /// checks and the write.
+    pub fn create_and_open(&self, xdg: XdgDir, name: &str) -> io::Result<File> {
+        create_file_secure(&self.join_file(self.try_xdg_dir(xdg)?, name)?)
+    }
+
+    /// `create_and_open` in the config directory.
+    pub fn create_and_open_config(&self, name: &str) -> io::Result<File> {
+        self.create_and_open(XdgDir::Config, name)
+    }
+}
+
+/// Create `dir` and its missing parents as `ensure_xdg_dir_secure`
+/// describes, giving `dir` exactly `mode` if any, and return it open.
+#[cfg(unix)]
+fn create_dir_secure(dir: &Path, mode: Option<u32>) -> io::Result<OwnedFd> {
+    use std::path::Component;
+
+    if !dir.is_absolute() || dir.components().any(|c| c == Component::ParentDir) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                  format!("not an absolute path without ..: {}", dir.display())));
+    }
+    let names = dir.components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => Some(Path::new(name)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let mut current = open_at(libc::AT_FDCWD, Path::new("/"), false)?;
+    let mut walked = PathBuf::from("/");
+    for (i, name) in names.iter().enumerate() {
+        walked.push(name);
+        let fd = current.as_raw_fd();
+        current = match open_at(fd, name, false) {
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+                let dir_mode = mode.filter(|_| i + 1 == names.len()).unwrap_or(0o777);
+                match mkdir_at(fd, name, dir_mode) {
+                    Err(ref err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+                    result => result?,
+                }
+                open_at(fd, name, false)?
+            }
+            Err(err) => {
+                match symlink_trusted(fd, name)? {
+                    Some(true) => open_at(fd, name, true)?,
+                    Some(false) => return Err(InsecureDir::ForeignSymlink(walked).into()),
+                    None => return Err(err),
+                }
+            }
+            Ok(next) => next,
+        };
+    }
+    if let Some(mode) = mode {
+        if unsafe { libc::fchmod(current.as_raw_fd(), mode as libc::mode_t) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(current)
+}
+
+/// Create `path` and its missing parents for `create_and_open`.
+#[cfg(unix)]
+fn create_file_secure(path: &Path) -> io::Result<File> {
+    let dir = create_dir_secure(path.parent().expect("joined a file name"), None)?;
+    let name = c_path(Path::new(path.file_name().expect("joined a file name")))?;
+    let flags = libc::O_WRONLY | libc::O_CREAT | libc::O_EXCL | libc::O_NOFOLLOW | libc::O_CLOEXEC;
+    let fd = unsafe { libc::openat(dir.as_raw_fd(), name.as_ptr(), flags, 0o600 as libc::c_uint) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+#[cfg(unix)]
+fn c_path(path: &Path) -> io::Result<CString> {
+    use std::os::unix::ffi::OsStrExt;
+
+    CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "path contains a NUL byte"))
+}
+
+/// Open the directory `name` in `dir`, following a final symlink only if
+/// `follow`.
+#[cfg(unix)]
+fn open_at(dir: RawFd, name: &Path, follow: bool) -> io::Result<OwnedFd> {
+    let mut flags = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC;
+    if !follow {
+        flags |= libc::O_NOFOLLOW;
+    }
+    let fd = unsafe { libc::openat(dir, c_path(name)?.as_ptr(), flags) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+#[cfg(unix)]
+fn mkdir_at(dir: RawFd, name: &Path, mode: u32) -> io::Result<()> {
+    if unsafe { libc::mkdirat(dir, c_path(name)?.as_ptr(), mode as libc::mode_t) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Whether the symlink `name` in `dir` is owned by the effective user or
+/// root, or `None` if it is not a symlink.
+#[cfg(unix)]
+fn symlink_trusted(dir: RawFd, name: &Path) -> io::Result<Option<bool>> {
+    let mut stat = unsafe { ::std::mem::zeroed::<libc::stat>() };
+    let name = c_path(name)?;
+    if unsafe { libc::fstatat(dir, name.as_ptr(), &mut stat, libc::AT_SYMLINK_NOFOLLOW) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if stat.st_mode & libc::S_IFMT != libc::S_IFLNK {
+        return Ok(None);
+    }
+    Ok(Some(stat.st_uid == 0 || stat.st_uid == unsafe { libc::geteuid() }))
+}
+
+/// `create_dir_with_mode`, since only Unix lets other users plant symlinks
+/// in the user's directories.
+#[cfg(not(unix))]
+fn create_dir_secure(dir: &Path, mode: Option<u32>) -> io::Result<()> {
+    match mode {
+        Some(mode) => create_dir_with_mode(dir, mode),
+        None => ::std::fs::create_dir_all(dir),
+    }
+}
+
+#[cfg(not(unix))]
+fn create_file_secure(path: &Path) -> io::Result<File> {
+    create_dir_secure(path.parent().expect("joined a file name"), None)?;
+    ::std::fs::OpenOptions::new().write(true).create_new(true).open(path)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::fs;
+    use std::io;
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    /// Create through trusted symlinks and refuse foreign ones.
+    #[test]
+    fn ensure_xdg_dir_secure() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        let dotfiles = guard.home().join("dotfiles");
+        fs::create_dir(&dotfiles).unwrap();
+        symlink(&dotfiles, guard.home().join(".config")).unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        let dir = app_dir.ensure_xdg_dir_secure(::XdgDir::Config, 0o700).unwrap();
+        assert_eq!(0o700, fs::metadata(&dir).unwrap().permissions().mode() & 0o777);
+        assert!(dotfiles.join("s_app_dir").is_dir());
+
+        let planted = guard.home().join("planted");
+        symlink(guard.home().join("elsewhere"), &planted).unwrap();
+        guard.set("XDG_DATA_HOME", planted.join("data"));
+        if unsafe { ::libc::geteuid() } == 0 {
+            ::std::os::unix::fs::lchown(&planted, Some(1), Some(1)).unwrap();
+            let err = app_dir.ensure_xdg_dir_secure(::XdgDir::Data, 0o700).unwrap_err();
+            assert_eq!(io::ErrorKind::PermissionDenied, err.kind());
+            assert!(!guard.home().join("elsewhere").exists());
+        }
+        assert!(super::create_dir_secure("/a/../b".as_ref(), Some(0o700)).is_err());
+    }
+
+    /// Create a private file once, and never through a symlink.
+    #[test]
+    fn create_and_open_config() {
+        use std::io::Write;
+
+        let guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        let mut file = app_dir.create_and_open_config("keys/token").unwrap();
+        file.write_all(b"secret").unwrap();
+        let path = app_dir.xdg_dir(::XdgDir::Config).unwrap().join("keys/token");
+        assert_eq!("secret", fs::read_to_string(&path).unwrap());
+        assert_eq!(0o600, fs::metadata(&path).unwrap().permissions().mode() & 0o777);
+        let err = app_dir.create_and_open_config("keys/token").unwrap_err();
+        assert_eq!(io::ErrorKind::AlreadyExists, err.kind());
+
+        let target = guard.home().join("target");
+        symlink(&target, path.with_file_name("link")).unwrap();
+        assert!(app_dir.create_and_open_config("keys/link").is_err());
+        assert!(!target.exists());
+    }
+}
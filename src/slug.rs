@@ -0,0 +1,99 @@
+/// How `AppDirBuilder::slug` turns a display name such as `My Great App!`
+/// into a directory name. Characters no platform allows in file names
+/// (`<>:"/\|?*` and control characters) are always removed, as are
+/// trailing dots and spaces, which Windows drops.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SlugPolicy {
+    lowercase: bool,
+    space: Option<char>,
+    strip_punctuation: bool,
+}
+
+impl SlugPolicy {
+    /// Keep the name as is, apart from the characters always removed.
+    pub fn new() -> SlugPolicy {
+        SlugPolicy::default()
+    }
+
+    /// The convention of the current platform: names as is on Windows and
+    /// macOS, as in `%APPDATA%\My Great App`, and lowercase with dashes and
+    /// without punctuation elsewhere, as in `~/.config/my-great-app`.
+    pub fn platform() -> SlugPolicy {
+        if cfg!(any(windows, target_os = "macos", target_os = "ios")) {
+            SlugPolicy::new()
+        } else {
+            SlugPolicy::new().lowercase(true).space(Some('-')).strip_punctuation(true)
+        }
+    }
+
+    /// Lowercase the name.
+    pub fn lowercase(mut self, enabled: bool) -> SlugPolicy {
+        self.lowercase = enabled;
+        self
+    }
+
+    /// Replace each run of whitespace with `space`, e.g. `-` or `_`, or keep
+    /// it as a single space with `None`.
+    pub fn space(mut self, space: Option<char>) -> SlugPolicy {
+        self.space = space;
+        self
+    }
+
+    /// Remove ASCII punctuation other than `-`, `_` and `.`.
+    pub fn strip_punctuation(mut self, enabled: bool) -> SlugPolicy {
+        self.strip_punctuation = enabled;
+        self
+    }
+
+    /// The directory name for `display_name`, or `app` if nothing is left.
+    pub fn apply(&self, display_name: &str) -> String {
+        let mut slug = String::with_capacity(display_name.len());
+        let mut pending_space = false;
+        for c in display_name.chars() {
+            if c.is_whitespace() {
+                pending_space = !slug.is_empty();
+                continue;
+            }
+            if c.is_control() || "<>:\"/\\|?*".contains(c) ||
+               self.strip_punctuation && c.is_ascii_punctuation() && !"-_.".contains(c) {
+                continue;
+            }
+            if pending_space {
+                slug.push(self.space.unwrap_or(' '));
+                pending_space = false;
+            }
+            if self.lowercase {
+                slug.extend(c.to_lowercase());
+            } else {
+                slug.push(c);
+            }
+        }
+        let trimmed = slug.trim_end_matches(['.', ' ']).len();
+        slug.truncate(trimmed);
+        if slug.is_empty() {
+            slug.push_str("app");
+        }
+        slug
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use SlugPolicy;
+
+    /// Apply each option and always remove unportable characters.
+    #[test]
+    fn apply() {
+        let name = "  My Great\tApp: Pro!  ";
+        assert_eq!("My Great App Pro!", SlugPolicy::new().apply(name));
+        let slug = SlugPolicy::new().lowercase(true).space(Some('-')).strip_punctuation(true);
+        assert_eq!("my-great-app-pro", slug.apply(name));
+        assert_eq!("Über_App", SlugPolicy::new().space(Some('_')).apply("Über App..."));
+        assert_eq!("app", slug.apply("?!"));
+
+        let app_dir = ::AppDir::builder("My Great App").slug(&SlugPolicy::platform()).build();
+        if cfg!(target_os = "linux") {
+            assert_eq!("my-great-app", app_dir.app_name());
+        }
+    }
+}
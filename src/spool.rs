@@ -0,0 +1,187 @@
+use std::convert::TryFrom;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+
+use timestamp::Utc;
+use {AppDir, XdgDir};
+
+/// The file records are appended to until a batch is claimed.
+const CURRENT: &str = "current";
+
+/// Locked shared by appenders and exclusively by claimers, so `current` is
+/// never renamed between an appender opening and writing it.
+const LOCK: &str = "lock";
+
+static NEXT_BATCH: AtomicUsize = AtomicUsize::new(0);
+
+impl AppDir {
+    /// `spool/<name>` in the data directory, e.g. `spool_dir("telemetry")`,
+    /// where records wait to be uploaded.
+    pub fn spool_dir(&self, name: &str) -> Option<PathBuf> {
+        self.try_spool_dir(name).ok()
+    }
+
+    /// Append `record` to the spool `name`, prefixed by its length as a
+    /// 32-bit big-endian integer. The record is written with a single
+    /// append, so processes may spool concurrently.
+    pub fn append_spool(&self, name: &str, record: &[u8]) -> io::Result<()> {
+        let dir = self.try_spool_dir(name)?;
+        fs::create_dir_all(&dir)?;
+        let len = u32::try_from(record.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "record too long"))?;
+        let mut buf = Vec::with_capacity(4 + record.len());
+        buf.extend_from_slice(&len.to_be_bytes());
+        buf.extend_from_slice(record);
+        let lock = open_lock(&dir)?;
+        lock.lock_shared()?;
+        OpenOptions::new().create(true).append(true).open(dir.join(CURRENT))?.write_all(&buf)
+    }
+
+    /// Claim a batch of spooled records to upload, or `None` if there are
+    /// none. A batch claimed earlier but neither committed nor still held,
+    /// say because the uploader crashed, is returned again before new
+    /// records are claimed.
+    ///
+    /// Each batch is held by one `SpoolBatch` at a time, across processes.
+    /// New records are claimed by renaming the spool file, so records
+    /// appended from then on go into the next batch.
+    pub fn claim_spool_batch(&self, name: &str) -> io::Result<Option<SpoolBatch>> {
+        let dir = self.try_spool_dir(name)?;
+        let lock = match open_lock(&dir) {
+            Ok(lock) => lock,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        lock.lock()?;
+        for path in batches(&dir)? {
+            if let Some(batch) = SpoolBatch::try_hold(path)? {
+                return Ok(Some(batch));
+            }
+        }
+        let stamp = Utc::from(SystemTime::now()).compact();
+        let path = dir.join(format!("batch-{}-{}-{}",
+                                    stamp,
+                                    process::id(),
+                                    NEXT_BATCH.fetch_add(1, Ordering::SeqCst)));
+        match fs::rename(dir.join(CURRENT), &path) {
+            Ok(()) => SpoolBatch::try_hold(path),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn try_spool_dir(&self, name: &str) -> io::Result<PathBuf> {
+        self.join_file(self.try_xdg_dir(XdgDir::Data)?.join("spool"), name)
+    }
+}
+
+fn open_lock(dir: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).truncate(false).write(true).open(dir.join(LOCK))
+}
+
+/// The claimed batches in `dir`, oldest first.
+fn batches(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut batches = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().and_then(|name| name.to_str());
+        if name.is_some_and(|name| name.starts_with("batch-")) {
+            batches.push(path);
+        }
+    }
+    batches.sort();
+    Ok(batches)
+}
+
+/// Spooled records claimed by `AppDir::claim_spool_batch`, held until it is
+/// committed or dropped.
+#[derive(Debug)]
+pub struct SpoolBatch {
+    path: PathBuf,
+    _held: File,
+}
+
+impl SpoolBatch {
+    /// Hold the batch at `path`, unless someone else does or has committed
+    /// it since it was listed.
+    fn try_hold(path: PathBuf) -> io::Result<Option<SpoolBatch>> {
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        match file.try_lock() {
+            Ok(()) if path.exists() => Ok(Some(SpoolBatch { path, _held: file })),
+            Ok(()) | Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(err)) => Err(err),
+        }
+    }
+
+    /// The file holding the batch.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The records in the batch, in the order they were appended. A record
+    /// cut short at the end, as by a crash while appending, is skipped.
+    pub fn records(&self) -> io::Result<Vec<Vec<u8>>> {
+        let data = fs::read(&self.path)?;
+        let mut records = Vec::new();
+        let mut rest = &data[..];
+        while rest.len() >= 4 {
+            let len = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+            if rest.len() - 4 < len {
+                break;
+            }
+            records.push(rest[4..4 + len].to_vec());
+            rest = &rest[4 + len..];
+        }
+        Ok(records)
+    }
+
+    /// Remove the batch once its records are uploaded.
+    pub fn commit(self) -> io::Result<()> {
+        fs::remove_file(&self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    /// Claim appended records once, and again once released uncommitted.
+    #[test]
+    fn spool() {
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        assert!(app_dir.claim_spool_batch("telemetry").unwrap().is_none());
+        app_dir.append_spool("telemetry", b"one").unwrap();
+        app_dir.append_spool("telemetry", b"").unwrap();
+
+        let batch = app_dir.claim_spool_batch("telemetry").unwrap().unwrap();
+        assert!(app_dir.claim_spool_batch("telemetry").unwrap().is_none());
+        app_dir.append_spool("telemetry", b"two").unwrap();
+        let mut file = OpenOptions::new().append(true).open(batch.path()).unwrap();
+        file.write_all(&[0, 0, 0, 9, b'x']).unwrap();
+        assert_eq!(vec![b"one".to_vec(), Vec::new()], batch.records().unwrap());
+        let path = batch.path().to_path_buf();
+        drop(batch);
+
+        let batch = app_dir.claim_spool_batch("telemetry").unwrap().unwrap();
+        assert_eq!(path, batch.path());
+        let next = app_dir.claim_spool_batch("telemetry").unwrap().unwrap();
+        assert_eq!(vec![b"two".to_vec()], next.records().unwrap());
+        assert_eq!(app_dir.spool_dir("telemetry").as_deref(), next.path().parent());
+        batch.commit().unwrap();
+        next.commit().unwrap();
+        assert!(app_dir.claim_spool_batch("telemetry").unwrap().is_none());
+
+        assert!(app_dir.append_spool("/etc", b"x").is_err());
+        assert_eq!(None, app_dir.spool_dir("../escape"));
+    }
+}
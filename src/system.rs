@@ -0,0 +1,115 @@
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature="serde")]
+use serde::{Deserialize, Serialize};
+
+use writable;
+use AppDir;
+
+/// Which machine-wide data directory to use on Unix. Windows has only one.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(rename_all = "snake_case"))]
+pub enum SystemScope {
+    /// Data installed along with the app and read at runtime, under
+    /// `/usr/local/share`.
+    Installed,
+    /// Data the app writes at runtime, under `/var/lib`.
+    State,
+}
+
+impl AppDir {
+    /// The directory for data shared by all users of the machine:
+    /// `%ProgramData%\app_name` on Windows, or `/usr/local/share/app_name` or
+    /// `/var/lib/app_name` on Unix depending on `scope`.
+    #[cfg(unix)]
+    pub fn system_data_dir(&self, scope: SystemScope) -> Option<PathBuf> {
+        let base = match scope {
+            SystemScope::Installed => "/usr/local/share",
+            SystemScope::State => "/var/lib",
+        };
+        Some(self.finish(PathBuf::from(base).join(&self.app_name)))
+    }
+
+    /// The directory for data shared by all users of the machine:
+    /// `%ProgramData%\app_name` on Windows, or `/usr/local/share/app_name` or
+    /// `/var/lib/app_name` on Unix depending on `scope`.
+    #[cfg(not(unix))]
+    pub fn system_data_dir(&self, _: SystemScope) -> Option<PathBuf> {
+        ::env_var_os("ProgramData")
+            .map(|dir| self.finish(PathBuf::from(dir).join(&self.app_name)))
+    }
+
+    /// Whether writing to `system_data_dir(scope)` needs more privileges than
+    /// the process has, e.g. root or an elevated administrator.
+    ///
+    /// Like `is_writable`, a missing directory is judged by its closest
+    /// existing ancestor, which is probed on Windows, e.g. `%ProgramData%`.
+    pub fn system_data_requires_elevation(&self, scope: SystemScope) -> bool {
+        self.system_data_dir(scope).is_none_or(|dir| require_writable(&dir).is_err())
+    }
+}
+
+/// The error for a directory that only root or an elevated administrator
+/// may write to, so installers can ask the user to rerun with privileges.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ElevationRequired {
+    path: PathBuf,
+}
+
+impl ElevationRequired {
+    /// The directory that could not be written.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Display for ElevationRequired {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "writing to {} requires elevated privileges", self.path.display())
+    }
+}
+
+impl error::Error for ElevationRequired {}
+
+impl From<ElevationRequired> for io::Error {
+    fn from(err: ElevationRequired) -> io::Error {
+        io::Error::new(io::ErrorKind::PermissionDenied, err)
+    }
+}
+
+/// Fail unless the process may write to `dir`, or create it in its closest
+/// existing ancestor.
+pub fn require_writable(dir: &Path) -> Result<(), ElevationRequired> {
+    if dir.ancestors().find(|p| p.exists()).is_some_and(writable::can_write) {
+        Ok(())
+    } else {
+        Err(ElevationRequired { path: dir.to_path_buf() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    /// Pick the Unix directory by scope.
+    #[cfg(unix)]
+    #[test]
+    fn system_data_dir() {
+        use std::path::PathBuf;
+
+        let app_dir = ::AppDir::new("s_app_dir");
+        assert_eq!(Some(PathBuf::from("/usr/local/share/s_app_dir")),
+                   app_dir.system_data_dir(::SystemScope::Installed));
+        assert_eq!(Some(PathBuf::from("/var/lib/s_app_dir")),
+                   app_dir.system_data_dir(::SystemScope::State));
+    }
+
+    /// Accept a missing directory under a writable one.
+    #[test]
+    fn require_writable() {
+        let guard = ::TempEnvGuard::new().unwrap();
+        assert_eq!(Ok(()), super::require_writable(&guard.home().join("a").join("b")));
+    }
+}
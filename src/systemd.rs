@@ -0,0 +1,68 @@
+use std::io;
+use std::path::PathBuf;
+
+use system::require_writable;
+use {resolve_base_dir, write_atomic, AppDir, Env, XdgDir};
+
+impl AppDir {
+    /// `$XDG_CONFIG_HOME/systemd/user`, where units of the user's own
+    /// service manager are installed. Shared by all apps.
+    pub fn user_unit_dir(&self) -> Option<PathBuf> {
+        resolve_base_dir(XdgDir::Config, self.options(), &Env::capture())
+            .ok()
+            .map(|config| self.finish(config.join("systemd/user")))
+    }
+
+    /// `/etc/systemd/system`, where the administrator installs units of the
+    /// system service manager.
+    pub fn system_unit_dir(&self) -> PathBuf {
+        self.finish(PathBuf::from("/etc/systemd/system"))
+    }
+
+    /// Whether installing into `system_unit_dir()` needs root.
+    pub fn system_unit_requires_elevation(&self) -> bool {
+        require_writable(&self.system_unit_dir()).is_err()
+    }
+
+    /// Write the unit file `name`, e.g. `app.service`, into
+    /// `user_unit_dir()` and return its path. Run `systemctl --user
+    /// daemon-reload` afterwards for it to be picked up.
+    pub fn install_user_unit(&self, name: &str, contents: &[u8]) -> io::Result<PathBuf> {
+        let dir = self.user_unit_dir()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no user unit directory"))?;
+        let file = self.join_file(dir, name)?;
+        write_atomic(&file, contents)?;
+        Ok(file)
+    }
+
+    /// Like `install_user_unit`, but into `system_unit_dir()`.
+    ///
+    /// Without the privileges to write there, this fails with an error of
+    /// kind `PermissionDenied` wrapping an `ElevationRequired`.
+    pub fn install_system_unit(&self, name: &str, contents: &[u8]) -> io::Result<PathBuf> {
+        let dir = self.system_unit_dir();
+        require_writable(&dir)?;
+        let file = self.join_file(dir, name)?;
+        write_atomic(&file, contents)?;
+        Ok(file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    /// Install a unit under the config directory.
+    #[test]
+    fn install_user_unit() {
+        let guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        let units = guard.home().join(".config/systemd/user");
+        assert_eq!(Some(units.clone()), app_dir.user_unit_dir());
+
+        let file = app_dir.install_user_unit("s_app_dir.service", b"[Service]\n").unwrap();
+        assert_eq!(units.join("s_app_dir.service"), file);
+        assert_eq!(b"[Service]\n".to_vec(), fs::read(&file).unwrap());
+        assert!(app_dir.install_user_unit("../escape.service", b"").is_err());
+    }
+}
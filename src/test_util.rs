@@ -0,0 +1,171 @@
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use {AppDir, ResolveStep};
+
+/// Variables that affect how directories are resolved.
+const VARS: &[&str] = &["HOME",
+                        "USERPROFILE",
+                        "APPDATA",
+                        "LOCALAPPDATA",
+                        "XDG_DATA_HOME",
+                        "XDG_CONFIG_HOME",
+                        "XDG_CACHE_HOME",
+                        "XDG_BIN_HOME",
+                        "XDG_CONFIG_DIRS",
+                        "XDG_DATA_DIRS",
+                        "XDG_RUNTIME_DIR",
+                        "APP_SANDBOX_CONTAINER_ID"];
+
+/// Serializes guards, since the environment is shared by all threads.
+static LOCK: Mutex<()> = Mutex::new(());
+
+static NEXT_HOME: AtomicUsize = AtomicUsize::new(0);
+
+/// An isolated environment for tests that resolve directories.
+///
+/// Creating a guard waits for any other guard in the process to be dropped,
+/// creates an empty home directory, points `HOME` (and `USERPROFILE`,
+/// `APPDATA` and `LOCALAPPDATA` on Windows) at it, and unsets the XDG
+/// variables. Dropping it restores every variable it touched and removes
+/// the home directory.
+///
+/// Variables of a particular app, such as its search-path variable, are left
+/// alone; use `for_app` to unset them too.
+///
+/// Tests that read these variables without a guard can still race with
+/// ones that hold it.
+pub struct TempEnvGuard {
+    home: PathBuf,
+    saved: Vec<(OsString, Option<OsString>)>,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl TempEnvGuard {
+    pub fn new() -> io::Result<TempEnvGuard> {
+        let lock = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let home = env::temp_dir().join(format!("s_app_dir-home-{}-{}",
+                                                process::id(),
+                                                NEXT_HOME.fetch_add(1, Ordering::SeqCst)));
+        let _ = fs::remove_dir_all(&home);
+        fs::create_dir_all(&home)?;
+
+        let mut guard = TempEnvGuard {
+            home: home.clone(),
+            saved: Vec::new(),
+            _lock: lock,
+        };
+        for key in VARS {
+            guard.remove(key);
+        }
+        guard.set("HOME", &home);
+        if cfg!(windows) {
+            guard.set("USERPROFILE", &home);
+            guard.set("APPDATA", home.join("AppData").join("Roaming"));
+            guard.set("LOCALAPPDATA", home.join("AppData").join("Local"));
+        }
+        Ok(guard)
+    }
+
+    /// Like `new`, but also unset the variables only `app_dir` reads: its
+    /// search-path variable (see `AppDir::path_var`) and those of its
+    /// fallback chains.
+    pub fn for_app(app_dir: &AppDir) -> io::Result<TempEnvGuard> {
+        let mut guard = TempEnvGuard::new()?;
+        guard.remove(app_dir.path_var());
+        for step in app_dir.chains.values().flatten() {
+            if let ResolveStep::Var(ref key) = *step {
+                guard.remove(key);
+            }
+        }
+        Ok(guard)
+    }
+
+    /// The isolated home directory.
+    pub fn home(&self) -> &Path {
+        &self.home
+    }
+
+    /// Set `key` until the guard is dropped.
+    pub fn set<K, V>(&mut self, key: K, value: V)
+        where K: AsRef<OsStr>,
+              V: AsRef<OsStr>
+    {
+        self.save(key.as_ref());
+        env::set_var(key, value);
+    }
+
+    /// Unset `key` until the guard is dropped.
+    pub fn remove<K>(&mut self, key: K)
+        where K: AsRef<OsStr>
+    {
+        self.save(key.as_ref());
+        env::remove_var(key);
+    }
+
+    fn save(&mut self, key: &OsStr) {
+        if !self.saved.iter().any(|(saved, _)| saved == key) {
+            self.saved.push((key.to_os_string(), env::var_os(key)));
+        }
+    }
+}
+
+impl Drop for TempEnvGuard {
+    fn drop(&mut self) {
+        for (key, value) in self.saved.drain(..) {
+            match value {
+                Some(value) => env::set_var(key, value),
+                None => env::remove_var(key),
+            }
+        }
+        let _ = fs::remove_dir_all(&self.home);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    /// Isolate the home directory and restore variables on drop.
+    #[test]
+    fn temp_env_guard() {
+        let (home, before) = {
+            let mut guard = ::TempEnvGuard::new().unwrap();
+            assert!(guard.home().is_dir());
+            assert_eq!(Some(guard.home().as_os_str().to_os_string()), env::var_os("HOME"));
+            assert_eq!(None, env::var_os("XDG_CONFIG_HOME"));
+            let before = env::var_os("S_APP_DIR_TEST_UTIL");
+            guard.set("S_APP_DIR_TEST_UTIL", "a");
+            guard.set("S_APP_DIR_TEST_UTIL", "b");
+            (guard.home().to_path_buf(), before)
+        };
+        let _guard = ::TempEnvGuard::new().unwrap();
+        assert!(!home.exists());
+        assert_eq!(before, env::var_os("S_APP_DIR_TEST_UTIL"));
+    }
+
+    /// Unset the app's own variables with `for_app`.
+    #[test]
+    fn for_app() {
+        let app_dir = ::AppDir::builder("s_app_dir_for_app")
+            .fallback_chain(::XdgDir::Config,
+                            vec![::ResolveStep::Var("S_APP_DIR_FOR_APP_ROOT".to_string())])
+            .build();
+        env::set_var("S_APP_DIR_FOR_APP_PATH", "/opt/a");
+        env::set_var("S_APP_DIR_FOR_APP_ROOT", "/opt/b");
+        {
+            let _guard = ::TempEnvGuard::for_app(&app_dir).unwrap();
+            assert_eq!(None, env::var_os("S_APP_DIR_FOR_APP_PATH"));
+            assert_eq!(None, env::var_os("S_APP_DIR_FOR_APP_ROOT"));
+        }
+        assert_eq!(Some("/opt/a".into()), env::var_os("S_APP_DIR_FOR_APP_PATH"));
+        env::remove_var("S_APP_DIR_FOR_APP_PATH");
+        env::remove_var("S_APP_DIR_FOR_APP_ROOT");
+    }
+}
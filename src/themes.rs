@@ -0,0 +1,63 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+
+use AppDir;
+
+impl AppDir {
+    /// The `themes` directory in each data directory, in order of
+    /// precedence: themes the user installed come before system ones.
+    pub fn theme_dirs(&self) -> Vec<PathBuf> {
+        self.data_search_path().into_iter().map(|dir| dir.join("themes")).collect()
+    }
+
+    /// The names of the themes available in `theme_dirs()`, sorted and
+    /// without duplicates.
+    ///
+    /// A theme is a directory, named after the theme, or a file, named after
+    /// the theme plus an extension such as `.toml`. Hidden entries and names
+    /// that are not valid Unicode are skipped.
+    pub fn theme_names(&self) -> Vec<String> {
+        let mut names = BTreeSet::new();
+        for dir in self.theme_dirs() {
+            let entries = match fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            for entry in entries.filter_map(Result::ok) {
+                let path = entry.path();
+                let name = if path.is_dir() { path.file_name() } else { path.file_stem() };
+                if let Some(name) = name.and_then(|name| name.to_str()) {
+                    if !name.starts_with('.') {
+                        names.insert(name.to_string());
+                    }
+                }
+            }
+        }
+        names.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    /// Merge theme directories and files from the user and system data dirs.
+    #[cfg(unix)]
+    #[test]
+    fn theme_names() {
+        use std::fs;
+
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        let system = guard.home().join("system");
+        guard.set("XDG_DATA_DIRS", &system);
+        let app_dir = ::AppDir::new("s_app_dir");
+        let user = guard.home().join(".local/share/s_app_dir/themes");
+        assert_eq!(vec![user.clone(), system.join("s_app_dir/themes")], app_dir.theme_dirs());
+
+        fs::create_dir_all(user.join("dark")).unwrap();
+        fs::write(user.join("light.toml"), b"").unwrap();
+        fs::write(user.join(".hidden.toml"), b"").unwrap();
+        fs::create_dir_all(system.join("s_app_dir/themes/dark")).unwrap();
+        fs::create_dir_all(system.join("s_app_dir/themes/classic")).unwrap();
+        assert_eq!(vec!["classic", "dark", "light"], app_dir.theme_names());
+    }
+}
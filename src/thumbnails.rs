@@ -0,0 +1,167 @@
+use std::path::{Path, PathBuf};
+
+#[cfg(feature="serde")]
+use serde::{Deserialize, Serialize};
+
+use {resolve_base_dir, AppDir, Env, XdgDir};
+
+/// A thumbnail size from the freedesktop thumbnail spec.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(rename_all = "kebab-case"))]
+pub enum ThumbnailSize {
+    /// At most 128×128 pixels.
+    Normal,
+    /// At most 256×256 pixels.
+    Large,
+    /// At most 512×512 pixels.
+    XLarge,
+    /// At most 1024×1024 pixels.
+    XXLarge,
+}
+
+impl ThumbnailSize {
+    /// The name of the directory holding thumbnails of this size.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            ThumbnailSize::Normal => "normal",
+            ThumbnailSize::Large => "large",
+            ThumbnailSize::XLarge => "x-large",
+            ThumbnailSize::XXLarge => "xx-large",
+        }
+    }
+
+    /// The maximum width and height, in pixels.
+    pub fn pixels(&self) -> u32 {
+        match *self {
+            ThumbnailSize::Normal => 128,
+            ThumbnailSize::Large => 256,
+            ThumbnailSize::XLarge => 512,
+            ThumbnailSize::XXLarge => 1024,
+        }
+    }
+}
+
+impl AppDir {
+    /// `$XDG_CACHE_HOME/thumbnails/<size>`, the thumbnail cache shared by
+    /// all apps.
+    pub fn thumbnail_dir(&self, size: ThumbnailSize) -> Option<PathBuf> {
+        resolve_base_dir(XdgDir::Cache, self.options(), &Env::capture())
+            .ok()
+            .map(|cache| self.finish(cache.join("thumbnails").join(size.as_str())))
+    }
+
+    /// Where the thumbnail of `file`, an absolute path, is cached.
+    pub fn thumbnail_path<P>(&self, size: ThumbnailSize, file: P) -> Option<PathBuf>
+        where P: AsRef<Path>
+    {
+        let name = thumbnail_name(&file_uri(file.as_ref())?);
+        self.thumbnail_dir(size).map(|dir| dir.join(name))
+    }
+}
+
+/// The `file://` URI of `path` in the form the thumbnail spec hashes, or
+/// `None` if `path` is relative.
+pub fn file_uri(path: &Path) -> Option<String> {
+    use std::os::unix::ffi::OsStrExt;
+
+    if !path.is_absolute() {
+        return None;
+    }
+    let mut uri = String::from("file://");
+    for &byte in path.as_os_str().as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.!~*'()/&=:@+$,".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    Some(uri)
+}
+
+/// The file name of the thumbnail of `uri`: the hex MD5 of the URI
+/// followed by `.png`.
+pub fn thumbnail_name(uri: &str) -> String {
+    let mut name = String::with_capacity(36);
+    for byte in &md5(uri.as_bytes()) {
+        name.push_str(&format!("{:02x}", byte));
+    }
+    name.push_str(".png");
+    name
+}
+
+/// MD5, which the spec mandates for thumbnail names. Not for security use.
+fn md5(data: &[u8]) -> [u8; 16] {
+    const SHIFTS: [[u32; 4]; 4] = [[7, 12, 17, 22],
+                                   [5, 9, 14, 20],
+                                   [4, 11, 16, 23],
+                                   [6, 10, 15, 21]];
+
+    let constants = (0..64)
+        .map(|i| ((i as f64 + 1.0).sin().abs() * 4_294_967_296.0) as u32)
+        .collect::<Vec<_>>();
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64).wrapping_mul(8)).to_le_bytes());
+
+    let mut state = [0x6745_2301u32, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476];
+    for block in message.chunks(64) {
+        let words = block.chunks(4)
+            .map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]]))
+            .collect::<Vec<_>>();
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let f = f.wrapping_add(a).wrapping_add(constants[i]).wrapping_add(words[g]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(f.rotate_left(SHIFTS[i / 16][i % 4]));
+        }
+        for (s, v) in state.iter_mut().zip(&[a, b, c, d]) {
+            *s = s.wrapping_add(*v);
+        }
+    }
+
+    let mut digest = [0; 16];
+    for (chunk, s) in digest.chunks_mut(4).zip(&state) {
+        chunk.copy_from_slice(&s.to_le_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    /// Match the example in the thumbnail spec and escape unsafe bytes.
+    #[test]
+    fn thumbnail_name() {
+        let uri = ::file_uri(Path::new("/home/jens/photos/me.png")).unwrap();
+        assert_eq!("file:///home/jens/photos/me.png", uri);
+        assert_eq!("c6ee772d9e49320e97ec29a7eb5b1697.png", ::thumbnail_name(&uri));
+        assert_eq!("d41d8cd98f00b204e9800998ecf8427e.png", ::thumbnail_name(""));
+        assert_eq!(Some("file:///a%20b/%C3%A9%23".to_string()),
+                   ::file_uri(Path::new("/a b/é#")));
+        assert_eq!(None, ::file_uri(Path::new("relative.png")));
+    }
+
+    /// Put thumbnails under the shared cache directory.
+    #[test]
+    fn thumbnail_path() {
+        let guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        let expect = guard.home()
+            .join(".cache/thumbnails/large/c6ee772d9e49320e97ec29a7eb5b1697.png");
+        assert_eq!(Some(expect),
+                   app_dir.thumbnail_path(::ThumbnailSize::Large, "/home/jens/photos/me.png"));
+    }
+}
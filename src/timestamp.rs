@@ -0,0 +1,81 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A UTC date and time, for naming files.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Utc {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    pub nanos: u32,
+}
+
+impl Utc {
+    /// `time` in UTC. Times before the epoch are clamped to it.
+    pub fn from(time: SystemTime) -> Utc {
+        let since = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let secs = since.as_secs();
+        let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+        let of_day = (secs % 86_400) as u32;
+        Utc {
+            year,
+            month,
+            day,
+            hour: of_day / 3600,
+            minute: of_day / 60 % 60,
+            second: of_day % 60,
+            nanos: since.subsec_nanos(),
+        }
+    }
+
+    /// `YYYY-MM-DD`.
+    pub fn date(&self) -> String {
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+
+    /// `YYYYMMDDTHHMMSS.nnnnnnnnnZ`, which sorts in time order.
+    pub fn compact(&self) -> String {
+        format!("{:04}{:02}{:02}T{:02}{:02}{:02}.{:09}Z",
+                self.year,
+                self.month,
+                self.day,
+                self.hour,
+                self.minute,
+                self.second,
+                self.nanos)
+    }
+}
+
+/// The proleptic Gregorian date `days` after 1970-01-01, after Howard
+/// Hinnant's `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::Utc;
+
+    /// Convert times to UTC dates, across leap days.
+    #[test]
+    fn utc() {
+        assert_eq!("19700101T000000.000000000Z", Utc::from(UNIX_EPOCH).compact());
+        let leap = UNIX_EPOCH + Duration::new(951_782_400 + 3723, 5);
+        assert_eq!("20000229T010203.000000005Z", Utc::from(leap).compact());
+        let today = Utc::from(UNIX_EPOCH + Duration::from_secs(1_792_108_800));
+        assert_eq!("2026-10-16", today.date());
+    }
+}
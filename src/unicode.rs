@@ -0,0 +1,102 @@
+use std::path::{Component, Path};
+
+#[cfg(feature="serde")]
+use serde::{Deserialize, Deserializer};
+#[cfg(feature="unicode-normalization")]
+use unicode_normalization::UnicodeNormalization;
+
+/// `name` in Unicode Normalization Form C, as app and organization names
+/// are stored, so that `é` typed as one code point or as `e` and a
+/// combining accent yields the same directory.
+#[cfg(feature="unicode-normalization")]
+pub fn nfc(name: &str) -> String {
+    name.nfc().collect()
+}
+
+/// `name` as is, without the `unicode-normalization` feature.
+#[cfg(not(feature="unicode-normalization"))]
+pub fn nfc(name: &str) -> String {
+    name.to_string()
+}
+
+/// A name passed through `nfc`, so a deserialized `AppDir` stores it as
+/// `AppDir::new` would.
+#[cfg(feature="serde")]
+pub fn deserialize_nfc<'de, D>(deserializer: D) -> Result<String, D::Error>
+    where D: Deserializer<'de>
+{
+    String::deserialize(deserializer).map(|name| nfc(&name))
+}
+
+/// Like `deserialize_nfc`, for an optional name.
+#[cfg(feature="serde")]
+pub fn deserialize_nfc_option<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+    where D: Deserializer<'de>
+{
+    Option::<String>::deserialize(deserializer).map(|name| name.map(|name| nfc(&name)))
+}
+
+/// Whether the file names `a` and `b` name the same file on a
+/// normalization-insensitive filesystem such as APFS, or on HFS+, which
+/// stores names decomposed: whether they are canonically equivalent.
+/// Without the `unicode-normalization` feature, whether they are equal.
+#[cfg(feature="unicode-normalization")]
+pub fn names_equivalent(a: &str, b: &str) -> bool {
+    a.nfd().eq(b.nfd())
+}
+
+/// Whether `a` and `b` are equal, without the `unicode-normalization`
+/// feature to check canonical equivalence.
+#[cfg(not(feature="unicode-normalization"))]
+pub fn names_equivalent(a: &str, b: &str) -> bool {
+    a == b
+}
+
+/// Like `names_equivalent`, but for each component of the paths `a` and
+/// `b`. Components that are not valid Unicode must be equal.
+pub fn paths_equivalent<A, B>(a: A, b: B) -> bool
+    where A: AsRef<Path>,
+          B: AsRef<Path>
+{
+    let (mut a, mut b) = (a.as_ref().components(), b.as_ref().components());
+    loop {
+        match (a.next(), b.next()) {
+            (None, None) => return true,
+            (Some(Component::Normal(x)), Some(Component::Normal(y))) => {
+                match (x.to_str(), y.to_str()) {
+                    (Some(x), Some(y)) if names_equivalent(x, y) => {}
+                    _ if x == y => {}
+                    _ => return false,
+                }
+            }
+            (x, y) if x == y => {}
+            _ => return false,
+        }
+    }
+}
+
+#[cfg(all(test, feature="unicode-normalization"))]
+mod tests {
+    /// Store names composed and compare them by canonical equivalence.
+    #[test]
+    fn nfc() {
+        let app_dir = ::AppDir::new("Cafe\u{301}");
+        assert_eq!("Caf\u{e9}", app_dir.app_name());
+        assert_eq!(app_dir, ::AppDir::new("x").with_app_name("Cafe\u{301}"));
+        assert!(::names_equivalent("Caf\u{e9}", "Cafe\u{301}"));
+        assert!(!::names_equivalent("Cafe", "Caf\u{e9}"));
+        assert!(::paths_equivalent("/a/Caf\u{e9}/b", "/a/Cafe\u{301}/b"));
+        assert!(!::paths_equivalent("/a/Caf\u{e9}", "/a/Caf\u{e9}/b"));
+    }
+
+    /// Normalize names read back from configuration too.
+    #[cfg(feature="serde")]
+    #[test]
+    fn nfc_deserialize() {
+        use serde_json;
+
+        let json = "{\"app_name\": \"Cafe\u{301}\", \"organization\": \"Cafe\u{301}\"}";
+        let app_dir: ::AppDir = serde_json::from_str(json).unwrap();
+        assert_eq!(::AppDir::builder("Caf\u{e9}").organization("Caf\u{e9}").build(), app_dir);
+    }
+}
@@ -0,0 +1,188 @@
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use {AppDir, XdgDir};
+
+/// The extension of files still being downloaded.
+const DOWNLOAD: &str = "download";
+
+static NEXT_DOWNLOAD: AtomicUsize = AtomicUsize::new(0);
+
+impl AppDir {
+    /// `updates` in the cache directory, where self-updates are downloaded
+    /// and verified. The cache directory is machine-local, so updates are
+    /// not roamed to other machines.
+    pub fn update_staging_dir(&self) -> Option<PathBuf> {
+        self.xdg_dir(XdgDir::Cache).map(|dir| dir.join("updates"))
+    }
+
+    /// Start downloading the update `name`, e.g. `foo-1.2.0.tar.gz`, into a
+    /// temporary file in `update_staging_dir`. Write the download to the
+    /// returned `StagedUpdate`, then `finish` it.
+    ///
+    /// The temporary file is locked while the download is in progress, so
+    /// `clean_update_staging` in another process leaves it alone.
+    pub fn stage_update(&self, name: &str) -> io::Result<StagedUpdate> {
+        let dir = self.try_xdg_dir(XdgDir::Cache)?.join("updates");
+        fs::create_dir_all(&dir)?;
+        let target = self.join_file(&dir, name)?;
+        let temp = dir.join(format!("{}.{}-{}.{}",
+                                    name,
+                                    process::id(),
+                                    NEXT_DOWNLOAD.fetch_add(1, Ordering::SeqCst),
+                                    DOWNLOAD));
+        let file = OpenOptions::new().write(true).create_new(true).open(&temp)?;
+        file.lock()?;
+        Ok(StagedUpdate {
+            temp,
+            target,
+            file: Some(file),
+        })
+    }
+
+    /// Remove the downloads left in `update_staging_dir` when their updates
+    /// were aborted, and return their paths. Downloads still locked by a
+    /// `StagedUpdate`, in this process or another, and finished updates are
+    /// kept.
+    pub fn clean_update_staging(&self) -> io::Result<Vec<PathBuf>> {
+        let dir = self.try_xdg_dir(XdgDir::Cache)?.join("updates");
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+        let mut removed = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == DOWNLOAD) && is_aborted(&path)? {
+                match fs::remove_file(&path) {
+                    Ok(()) => removed.push(path),
+                    Err(ref err) if err.kind() == io::ErrorKind::NotFound => {}
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+        removed.sort();
+        Ok(removed)
+    }
+}
+
+/// Whether no `StagedUpdate` holds the download at `path`. One that is gone
+/// already counts as held, since someone else cleaned it up.
+fn is_aborted(path: &Path) -> io::Result<bool> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err),
+    };
+    match file.try_lock() {
+        Ok(()) => Ok(true),
+        Err(TryLockError::WouldBlock) => Ok(false),
+        Err(TryLockError::Error(err)) => Err(err),
+    }
+}
+
+/// An update being downloaded, returned by `AppDir::stage_update`. The
+/// temporary file is removed if this is dropped before `finish`.
+#[derive(Debug)]
+pub struct StagedUpdate {
+    temp: PathBuf,
+    target: PathBuf,
+    file: Option<File>,
+}
+
+impl StagedUpdate {
+    /// The temporary file being written.
+    pub fn path(&self) -> &Path {
+        &self.temp
+    }
+
+    /// Flush the download, check it with `verify`, e.g. against a checksum
+    /// or signature, and rename it to its final name in one step, replacing
+    /// any earlier download of the same update. Return the final path.
+    ///
+    /// If `verify` fails, the download is removed and its error returned.
+    pub fn finish<F>(mut self, verify: F) -> io::Result<PathBuf>
+        where F: FnOnce(&Path) -> io::Result<()>
+    {
+        if let Some(ref file) = self.file {
+            file.sync_all()?;
+        }
+        // The lock keeps other handles from reading the file on Windows, so
+        // it is released before `verify` there; elsewhere it is held until
+        // the download has its final name.
+        if cfg!(windows) {
+            self.file.take();
+        }
+        verify(&self.temp)?;
+        fs::rename(&self.temp, &self.target)?;
+        self.file.take();
+        Ok(self.target.clone())
+    }
+}
+
+impl Write for StagedUpdate {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.as_mut().expect("not finished").write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.as_mut().expect("not finished").flush()
+    }
+}
+
+impl Drop for StagedUpdate {
+    fn drop(&mut self) {
+        self.file.take();
+        let _ = fs::remove_file(&self.temp);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::{self, Write};
+
+    /// Promote verified downloads and discard the rest.
+    #[test]
+    fn stage_update() {
+        let _guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        let dir = app_dir.update_staging_dir().unwrap();
+
+        let mut update = app_dir.stage_update("foo-1.2.0").unwrap();
+        update.write_all(b"new").unwrap();
+        let temp = update.path().to_path_buf();
+        let path = update.finish(|path| {
+                assert_eq!("new", fs::read_to_string(path).unwrap());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(dir.join("foo-1.2.0"), path);
+        assert!(!temp.exists());
+
+        let update = app_dir.stage_update("foo-1.3.0").unwrap();
+        let temp = update.path().to_path_buf();
+        let err = update.finish(|_| Err(io::Error::new(io::ErrorKind::InvalidData, "bad")));
+        assert_eq!(io::ErrorKind::InvalidData, err.unwrap_err().kind());
+        assert!(!temp.exists() && !dir.join("foo-1.3.0").exists());
+
+        let aborted = dir.join("foo-1.4.0.1-0.download");
+        fs::write(&aborted, "").unwrap();
+        let mut first = app_dir.stage_update("foo-1.5.0").unwrap();
+        let mut second = app_dir.stage_update("foo-1.5.0").unwrap();
+        assert_ne!(first.path(), second.path());
+        assert_eq!(vec![aborted], app_dir.clean_update_staging().unwrap());
+        assert_eq!(3, fs::read_dir(&dir).unwrap().count());
+
+        first.write_all(b"first").unwrap();
+        second.write_all(b"second").unwrap();
+        assert_eq!(dir.join("foo-1.5.0"), first.finish(|_| Ok(())).unwrap());
+        assert_eq!(dir.join("foo-1.5.0"), second.finish(|_| Ok(())).unwrap());
+        assert_eq!("second", fs::read_to_string(dir.join("foo-1.5.0")).unwrap());
+        assert_eq!(2, fs::read_dir(&dir).unwrap().count());
+    }
+}
@@ -0,0 +1,398 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature="serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(windows)]
+use windows_sys::core::GUID;
+
+use {AppDir, Env, ResolveError};
+
+/// A folder in the home directory for files the user handles directly,
+/// shared by all apps.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(rename_all = "lowercase"))]
+pub enum UserDir {
+    Downloads,
+    Documents,
+    Pictures,
+    Music,
+    /// `~/Movies` on macOS.
+    Videos,
+}
+
+impl UserDir {
+    /// Every folder kind, in declaration order.
+    pub fn all() -> &'static [UserDir] {
+        static ALL: [UserDir; 5] = [UserDir::Downloads,
+                                    UserDir::Documents,
+                                    UserDir::Pictures,
+                                    UserDir::Music,
+                                    UserDir::Videos];
+        &ALL
+    }
+
+    /// The entry for this folder in `user-dirs.dirs`.
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn xdg_key(&self) -> &'static str {
+        match *self {
+            UserDir::Downloads => "XDG_DOWNLOAD_DIR",
+            UserDir::Documents => "XDG_DOCUMENTS_DIR",
+            UserDir::Pictures => "XDG_PICTURES_DIR",
+            UserDir::Music => "XDG_MUSIC_DIR",
+            UserDir::Videos => "XDG_VIDEOS_DIR",
+        }
+    }
+
+    /// The name of the folder under the home directory when nothing
+    /// configures it elsewhere.
+    fn default_name(&self) -> &'static str {
+        match *self {
+            UserDir::Downloads => "Downloads",
+            UserDir::Documents => "Documents",
+            UserDir::Pictures => "Pictures",
+            UserDir::Music => "Music",
+            UserDir::Videos if cfg!(target_os = "macos") => "Movies",
+            UserDir::Videos => "Videos",
+        }
+    }
+
+    #[cfg(windows)]
+    fn folder_id(&self) -> GUID {
+        use windows_sys::Win32::UI::Shell::{FOLDERID_Documents, FOLDERID_Downloads,
+                                            FOLDERID_Music, FOLDERID_Pictures,
+                                            FOLDERID_Videos};
+
+        match *self {
+            UserDir::Downloads => FOLDERID_Downloads,
+            UserDir::Documents => FOLDERID_Documents,
+            UserDir::Pictures => FOLDERID_Pictures,
+            UserDir::Music => FOLDERID_Music,
+            UserDir::Videos => FOLDERID_Videos,
+        }
+    }
+}
+
+impl AppDir {
+    /// The user's `user_dir` folder: the matching `XDG_*_DIR` entry in
+    /// `user-dirs.dirs` on Linux, the Known Folder on Windows, or the folder
+    /// of that name in the home directory.
+    pub fn user_dir(&self, user_dir: UserDir) -> Option<PathBuf> {
+        resolve(user_dir, &Env::capture()).map(|dir| self.finish(dir))
+    }
+
+    /// Create `user_dir(user_dir)/app_name` if missing, e.g. for the
+    /// screenshots or recordings the app saves.
+    pub fn ensure_app_user_dir(&self, user_dir: UserDir) -> io::Result<PathBuf> {
+        let env = Env::capture();
+        let base = resolve(user_dir, &env).ok_or_else(|| ResolveError::NoHome(env.report()))?;
+        let dir = self.finish(base.join(&self.app_name));
+        fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// The user's Downloads folder, shared by all apps: `XDG_DOWNLOAD_DIR`
+    /// from `user-dirs.dirs` on Linux, the Downloads Known Folder on Windows,
+    /// or `~/Downloads`.
+    pub fn downloads_dir(&self) -> Option<PathBuf> {
+        self.user_dir(UserDir::Downloads)
+    }
+
+    /// Create `downloads_dir()/app_name` if missing, for files the app fetches
+    /// on the user's behalf.
+    pub fn ensure_app_downloads_dir(&self) -> io::Result<PathBuf> {
+        self.ensure_app_user_dir(UserDir::Downloads)
+    }
+
+    /// The user's Documents folder, shared by all apps: `XDG_DOCUMENTS_DIR`
+    /// from `user-dirs.dirs` on Linux, the Documents Known Folder on Windows,
+    /// or `~/Documents`.
+    pub fn documents_dir(&self) -> Option<PathBuf> {
+        self.user_dir(UserDir::Documents)
+    }
+
+    /// Create `documents_dir()/app_name` if missing, for exports the user
+    /// should be able to find, unlike files in the data or config directory.
+    pub fn ensure_app_documents_dir(&self) -> io::Result<PathBuf> {
+        self.ensure_app_user_dir(UserDir::Documents)
+    }
+
+    /// The user's Pictures folder; see `user_dir`.
+    pub fn pictures_dir(&self) -> Option<PathBuf> {
+        self.user_dir(UserDir::Pictures)
+    }
+
+    /// The user's Music folder; see `user_dir`.
+    pub fn music_dir(&self) -> Option<PathBuf> {
+        self.user_dir(UserDir::Music)
+    }
+
+    /// The user's Videos folder, `~/Movies` on macOS; see `user_dir`.
+    pub fn videos_dir(&self) -> Option<PathBuf> {
+        self.user_dir(UserDir::Videos)
+    }
+}
+
+/// Point `user_dir` at `path` in `user-dirs.dirs`, as
+/// `xdg-user-dirs-update --set` does, for setup tools that register or move
+/// a folder. Only on Linux and other XDG platforms.
+///
+/// Other entries and comments in the file are kept. `path` must be absolute,
+/// and is written relative to `$HOME` when inside it. The folder itself is
+/// not created.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn set_user_dir<P>(user_dir: UserDir, path: P) -> io::Result<()>
+    where P: AsRef<Path>
+{
+    use write_atomic;
+
+    let env = Env::capture();
+    let home = env.home.as_ref().ok_or_else(|| ResolveError::NoHome(env.report()))?;
+    let value = entry_value(path.as_ref(), home)?;
+    let file = user_dirs_file(&env)?;
+    let contents = match fs::read_to_string(&file) {
+        Ok(contents) => contents,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err),
+    };
+    Ok(write_atomic(&file, set_entry(&contents, user_dir.xdg_key(), &value).as_bytes())?)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn user_dirs_file(env: &Env) -> Result<PathBuf, ResolveError> {
+    use s_app_dir_core::Options;
+    use {resolve_base_dir, XdgDir};
+
+    resolve_base_dir(XdgDir::Config, Options::default(), env)
+        .map(|config| config.join("user-dirs.dirs"))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn resolve(user_dir: UserDir, env: &Env) -> Option<PathBuf> {
+    let home = env.home.as_ref()?;
+    let configured = user_dirs_file(env)
+        .ok()
+        .and_then(|file| fs::read_to_string(file).ok())
+        .and_then(|contents| parse_entry(&contents, user_dir.xdg_key(), home));
+    Some(configured.unwrap_or_else(|| home.join(user_dir.default_name())))
+}
+
+#[cfg(any(target_os = "macos", not(any(unix, windows))))]
+fn resolve(user_dir: UserDir, env: &Env) -> Option<PathBuf> {
+    env.home.as_ref().map(|home| home.join(user_dir.default_name()))
+}
+
+#[cfg(windows)]
+fn resolve(user_dir: UserDir, env: &Env) -> Option<PathBuf> {
+    known_folder(&user_dir.folder_id())
+        .or_else(|| env.home.as_ref().map(|home| home.join(user_dir.default_name())))
+}
+
+/// The path of a Known Folder, which the user may have moved, e.g. to
+/// another drive.
+#[cfg(windows)]
+fn known_folder(id: &GUID) -> Option<PathBuf> {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStringExt;
+    use std::ptr;
+    use std::slice;
+    use windows_sys::Win32::System::Com::CoTaskMemFree;
+    use windows_sys::Win32::UI::Shell::SHGetKnownFolderPath;
+
+    unsafe {
+        let mut path = ptr::null_mut();
+        let dir = if SHGetKnownFolderPath(id, 0, ptr::null_mut(), &mut path) == 0 {
+            let len = (0..).take_while(|&i| *path.offset(i) != 0).count();
+            Some(PathBuf::from(OsString::from_wide(slice::from_raw_parts(path, len))))
+        } else {
+            None
+        };
+        // The buffer must be freed even when the call fails.
+        CoTaskMemFree(path as *const _);
+        dir
+    }
+}
+
+/// The path `key` is set to in the contents of a `user-dirs.dirs` file.
+///
+/// The file is a shell fragment, but only the forms `xdg-user-dirs-update`
+/// writes are understood: `"$HOME/relative"` and `"/absolute"`. As in the
+/// shell, the last assignment wins.
+#[cfg_attr(not(all(unix, not(target_os = "macos"))), allow(dead_code))]
+fn parse_entry(contents: &str, key: &str, home: &Path) -> Option<PathBuf> {
+    let value = contents.lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .rev()
+        .find(|&(k, _)| k.trim() == key)?
+        .1
+        .trim();
+    let value = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(value);
+    if value == "$HOME" {
+        Some(home.to_path_buf())
+    } else if let Some(relative) = value.strip_prefix("$HOME/") {
+        Some(home.join(relative))
+    } else if value.starts_with('/') {
+        Some(PathBuf::from(value))
+    } else {
+        None
+    }
+}
+
+/// The quoted value `parse_entry` reads back as `path`.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn entry_value(path: &Path, home: &Path) -> io::Result<String> {
+    let invalid = |reason| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("{}: {}", reason, path.display()))
+    };
+    if !path.is_absolute() {
+        return Err(invalid("not an absolute path"));
+    }
+    let (prefix, rest) = match path.strip_prefix(home) {
+        Ok(relative) if relative.as_os_str().is_empty() => ("$HOME", Path::new("")),
+        Ok(relative) => ("$HOME/", relative),
+        Err(_) => ("", path),
+    };
+    let rest = rest.to_str().ok_or_else(|| invalid("not valid Unicode"))?;
+    // These would need shell escaping, which `parse_entry` doesn't undo.
+    if rest.contains(['"', '\\', '`', '$', '\n']) {
+        return Err(invalid("unsupported character in path"));
+    }
+    Ok(format!("\"{}{}\"", prefix, rest))
+}
+
+/// `contents` with `key` set to `value`: the first assignment is replaced,
+/// later ones are dropped, and one is appended if there was none.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn set_entry(contents: &str, key: &str, value: &str) -> String {
+    let entry = format!("{}={}", key, value);
+    let mut replaced = false;
+    let mut updated = String::with_capacity(contents.len() + entry.len() + 1);
+    for line in contents.lines() {
+        let trimmed = line.trim();
+        let matches = !trimmed.starts_with('#') &&
+                      trimmed.split_once('=').is_some_and(|(k, _)| k.trim() == key);
+        if !matches {
+            updated.push_str(line);
+        } else if replaced {
+            continue;
+        } else {
+            updated.push_str(&entry);
+            replaced = true;
+        }
+        updated.push('\n');
+    }
+    if !replaced {
+        updated.push_str(&entry);
+        updated.push('\n');
+    }
+    updated
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    /// Parse the forms `xdg-user-dirs-update` writes, letting the last one win.
+    #[test]
+    fn parse_entry() {
+        let home = Path::new("/home/a");
+        let contents = "# written by xdg-user-dirs-update\n\
+                        XDG_DOWNLOAD_DIR=\"$HOME/Downloads\"\n\
+                        XDG_DOCUMENTS_DIR=\"$HOME\"\n\
+                        XDG_DOWNLOAD_DIR=\"/mnt/fetched\"\n\
+                        XDG_MUSIC_DIR=\"music\"\n";
+        assert_eq!(Some(PathBuf::from("/mnt/fetched")),
+                   super::parse_entry(contents, "XDG_DOWNLOAD_DIR", home));
+        assert_eq!(Some(home.to_path_buf()),
+                   super::parse_entry(contents, "XDG_DOCUMENTS_DIR", home));
+        assert_eq!(None, super::parse_entry(contents, "XDG_MUSIC_DIR", home));
+        assert_eq!(None, super::parse_entry(contents, "XDG_VIDEOS_DIR", home));
+    }
+
+    /// Read `user-dirs.dirs` from the config directory and create the app
+    /// subfolder inside the configured folder.
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn ensure_app_downloads_dir() {
+        use std::fs;
+
+        let guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        assert_eq!(Some(guard.home().join("Downloads")), app_dir.downloads_dir());
+
+        fs::create_dir_all(guard.home().join(".config")).unwrap();
+        fs::write(guard.home().join(".config").join("user-dirs.dirs"),
+                  "XDG_DOWNLOAD_DIR=\"$HOME/Fetched\"\n")
+            .unwrap();
+        let dir = app_dir.ensure_app_downloads_dir().unwrap();
+        assert_eq!(guard.home().join("Fetched").join("s_app_dir"), dir);
+        assert!(dir.is_dir());
+    }
+
+    /// Fall back to `~/Documents` when `user-dirs.dirs` has no entry for it.
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn documents_dir() {
+        use std::fs;
+
+        let guard = ::TempEnvGuard::new().unwrap();
+        fs::create_dir_all(guard.home().join(".config")).unwrap();
+        fs::write(guard.home().join(".config").join("user-dirs.dirs"),
+                  "XDG_DOWNLOAD_DIR=\"$HOME/Fetched\"\n")
+            .unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        assert_eq!(Some(guard.home().join("Documents")), app_dir.documents_dir());
+    }
+
+    /// Resolve every media folder from `user-dirs.dirs`.
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn user_dir() {
+        use std::fs;
+
+        let guard = ::TempEnvGuard::new().unwrap();
+        fs::create_dir_all(guard.home().join(".config")).unwrap();
+        fs::write(guard.home().join(".config").join("user-dirs.dirs"),
+                  "XDG_PICTURES_DIR=\"$HOME/Media/Pictures\"\n\
+                   XDG_MUSIC_DIR=\"$HOME/Media/Music\"\n\
+                   XDG_VIDEOS_DIR=\"$HOME/Media/Videos\"\n")
+            .unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        for &user_dir in &[::UserDir::Pictures, ::UserDir::Music, ::UserDir::Videos] {
+            let name = format!("{:?}", user_dir);
+            assert_eq!(Some(guard.home().join("Media").join(name)), app_dir.user_dir(user_dir));
+        }
+        assert_eq!(app_dir.user_dir(::UserDir::Music), app_dir.music_dir());
+    }
+
+    /// Update an entry in place, keeping comments and other entries, and
+    /// read it back.
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn set_user_dir() {
+        use std::fs;
+
+        let guard = ::TempEnvGuard::new().unwrap();
+        let file = guard.home().join(".config").join("user-dirs.dirs");
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file,
+                  "# comment\nXDG_DOWNLOAD_DIR=\"$HOME/Downloads\"\nXDG_MUSIC_DIR=\"$HOME\"\n")
+            .unwrap();
+
+        ::set_user_dir(::UserDir::Downloads, guard.home().join("Fetched")).unwrap();
+        ::set_user_dir(::UserDir::Videos, "/mnt/videos").unwrap();
+        assert_eq!("# comment\nXDG_DOWNLOAD_DIR=\"$HOME/Fetched\"\nXDG_MUSIC_DIR=\"$HOME\"\n\
+                    XDG_VIDEOS_DIR=\"/mnt/videos\"\n",
+                   fs::read_to_string(&file).unwrap());
+        let app_dir = ::AppDir::new("s_app_dir");
+        assert_eq!(Some(guard.home().join("Fetched")), app_dir.downloads_dir());
+        assert_eq!(Some(PathBuf::from("/mnt/videos")), app_dir.videos_dir());
+
+        assert!(::set_user_dir(::UserDir::Music, "relative").is_err());
+        assert!(::set_user_dir(::UserDir::Music, "/mnt/\"quoted\"").is_err());
+    }
+}
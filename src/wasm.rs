@@ -0,0 +1,44 @@
+//! Directories on `wasm32-unknown-unknown`, which has neither environment
+//! variables nor a filesystem. Each directory resolves to a stable
+//! identifier, such as `idb://app_name/config` for the config directory,
+//! that a browser app can map onto IndexedDB, OPFS or another store.
+
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+static ROOT: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Resolve directories as `root/app_name/kind` instead of
+/// `idb://app_name/kind`, e.g. under the mount point of a virtual
+/// filesystem the host provides. `None` restores the identifiers.
+///
+/// Base directories set with `AppDir::with_overrides` still win, as on
+/// other platforms. Only on `wasm32-unknown-unknown`.
+pub fn set_virtual_root(root: Option<PathBuf>) {
+    *ROOT.write().unwrap_or_else(|err| err.into_inner()) = root;
+}
+
+/// The directory of `kind`, e.g. `"config"` or `"temp"`, of `app_name`.
+pub fn virtual_dir(app_name: &str, kind: &str) -> PathBuf {
+    match *ROOT.read().unwrap_or_else(|err| err.into_inner()) {
+        Some(ref root) => root.join(app_name).join(kind),
+        None => PathBuf::from(format!("idb://{}/{}", app_name, kind)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    /// Use the identifiers until a root is injected.
+    #[test]
+    fn virtual_dir() {
+        let app_dir = ::AppDir::new("s_app_dir");
+        assert_eq!(Some(PathBuf::from("idb://s_app_dir/config")),
+                   app_dir.xdg_dir(::XdgDir::Config));
+
+        ::set_virtual_root(Some(PathBuf::from("/vfs")));
+        assert_eq!(PathBuf::from("/vfs/s_app_dir/temp"), app_dir.temp_dir());
+        ::set_virtual_root(None);
+    }
+}
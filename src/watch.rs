@@ -0,0 +1,92 @@
+use notify::{self, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+
+use {AppDir, XdgDir};
+
+/// Watches a config file until dropped.
+pub struct ConfigWatcher {
+    path: PathBuf,
+    _watcher: RecommendedWatcher,
+}
+
+impl ConfigWatcher {
+    /// The config file being watched.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AppDir {
+    /// Call `callback` whenever the config file `name` changes.
+    ///
+    /// The parent directory is watched instead of the file itself, so editors
+    /// that save by writing a temporary file and renaming it over the original
+    /// keep triggering the callback.
+    pub fn watch_config<F>(&self, name: &str, callback: F) -> notify::Result<ConfigWatcher>
+        where F: FnMut(&Path) + Send + 'static
+    {
+        let path = self.xdg_dir(XdgDir::Config)
+            .map(|dir| dir.join(name))
+            .ok_or_else(|| notify::Error::generic("cannot resolve the config directory"))?;
+        watch_file(path, callback)
+    }
+}
+
+pub fn watch_file<F>(path: PathBuf, mut callback: F) -> notify::Result<ConfigWatcher>
+    where F: FnMut(&Path) + Send + 'static
+{
+    let (dir, file_name) = match (path.parent(), path.file_name()) {
+        (Some(dir), Some(file_name)) => (dir.to_path_buf(), file_name.to_os_string()),
+        _ => return Err(notify::Error::path_not_found().add_path(path)),
+    };
+
+    let target = path.clone();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+        if let Ok(event) = res {
+            if is_change(&event.kind) &&
+               event.paths.iter().any(|p| p.file_name() == Some(file_name.as_os_str())) {
+                callback(&target);
+            }
+        }
+    })?;
+    watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+
+    Ok(ConfigWatcher {
+        path,
+        _watcher: watcher,
+    })
+}
+
+fn is_change(kind: &EventKind) -> bool {
+    matches!(*kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    /// Invoke the callback when the watched file is replaced by rename.
+    #[test]
+    fn watch_config_rename() {
+        let base = env::temp_dir().join("s_app_dir-watch_config_rename");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(&base).unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let watcher = super::watch_file(base.join("config.toml"), move |p| {
+                          let _ = tx.send(p.to_path_buf());
+                      })
+                          .unwrap();
+
+        let tmp = base.join(".config.toml.swp");
+        fs::write(&tmp, "a = 1").unwrap();
+        fs::rename(&tmp, watcher.path()).unwrap();
+
+        let changed = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(watcher.path(), changed.as_path());
+        let _ = fs::remove_dir_all(&base);
+    }
+}
@@ -0,0 +1,112 @@
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use {AppDir, XdgDir};
+
+impl AppDir {
+    /// Whether the process may write to the resolved `xdg` directory.
+    ///
+    /// If the directory doesn't exist yet, its closest existing ancestor is
+    /// checked instead, since that is where it would be created. This only
+    /// consults permissions; see `probe_writable` for a definitive answer.
+    /// On Windows, where the read-only attribute means nothing for
+    /// directories and access is governed by ACLs, directories are probed.
+    pub fn is_writable(&self, xdg: XdgDir) -> bool {
+        self.xdg_dir(xdg)
+            .and_then(|dir| dir.ancestors().find(|p| p.exists()).map(|p| p.to_path_buf()))
+            .is_some_and(|dir| can_write(&dir))
+    }
+
+    /// Like `is_writable`, but check by creating and removing a probe file.
+    ///
+    /// This catches cases permissions don't reveal, such as read-only
+    /// mounts, quotas, or ACLs.
+    pub fn probe_writable(&self, xdg: XdgDir) -> bool {
+        self.xdg_dir(xdg)
+            .and_then(|dir| dir.ancestors().find(|p| p.is_dir()).map(|p| p.to_path_buf()))
+            .is_some_and(|dir| probe(&dir))
+    }
+
+    /// The first directory of `data_search_path` the process can write to,
+    /// checked by probing. The user's data directory is created if missing;
+    /// the others must already exist. For read-only homes and live systems,
+    /// where writes must go elsewhere instead of failing.
+    pub fn writable_data_dir(&self) -> Option<PathBuf> {
+        if let Some(dir) = self.xdg_dir(XdgDir::Data) {
+            let _ = fs::create_dir_all(dir);
+        }
+        self.data_search_path().into_iter().find(|dir| dir.is_dir() && probe(dir))
+    }
+}
+
+#[cfg(unix)]
+pub fn can_write(path: &Path) -> bool {
+    use libc;
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = match CString::new(path.as_os_str().as_bytes()) {
+        Ok(path) => path,
+        Err(_) => return false,
+    };
+    let mode = libc::W_OK | libc::X_OK;
+    unsafe { libc::faccessat(libc::AT_FDCWD, path.as_ptr(), mode, libc::AT_EACCESS) == 0 }
+}
+
+#[cfg(not(unix))]
+pub fn can_write(path: &Path) -> bool {
+    match fs::metadata(path) {
+        Ok(ref metadata) if metadata.is_dir() => probe(path),
+        Ok(metadata) => !metadata.permissions().readonly(),
+        Err(_) => false,
+    }
+}
+
+pub fn probe(dir: &Path) -> bool {
+    let probe = dir.join(format!(".s_app_dir-probe-{}", process::id()));
+    match OpenOptions::new().write(true).create_new(true).open(&probe) {
+        Ok(_) => {
+            let _ = fs::remove_file(&probe);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    /// Report a missing directory under a writable ancestor as writable.
+    #[test]
+    fn is_writable() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        let base = guard.home().join("data");
+        fs::create_dir_all(&base).unwrap();
+        guard.set("XDG_DATA_HOME", &base);
+        let app_dir = ::AppDir::new("s_app_dir");
+
+        assert!(app_dir.is_writable(::XdgDir::Data));
+        assert!(app_dir.probe_writable(::XdgDir::Data));
+        assert_eq!(0, fs::read_dir(&base).unwrap().count());
+    }
+
+    /// Fall back to the next searched directory when the user's cannot be
+    /// created.
+    #[test]
+    fn writable_data_dir() {
+        let mut guard = ::TempEnvGuard::new().unwrap();
+        let app_dir = ::AppDir::new("s_app_dir");
+        assert_eq!(app_dir.xdg_dir(::XdgDir::Data), app_dir.writable_data_dir());
+        assert!(app_dir.existing_xdg_dir(::XdgDir::Data).is_some());
+
+        let file = guard.home().join("file");
+        let extra = guard.home().join("extra");
+        fs::write(&file, "").unwrap();
+        fs::create_dir(&extra).unwrap();
+        guard.set("XDG_DATA_HOME", &file);
+        guard.set("S_APP_DIR_PATH", &extra);
+        assert_eq!(Some(extra), app_dir.writable_data_dir());
+    }
+}